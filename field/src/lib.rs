@@ -11,6 +11,8 @@
 
 pub(crate) mod arch;
 pub mod batch_util;
+pub mod bn254_base;
+pub mod bn254_scalar;
 pub mod cosets;
 pub mod extension_field;
 pub mod fft;
@@ -22,9 +24,11 @@ mod inversion;
 pub mod ops;
 pub mod packable;
 pub mod packed_field;
+pub mod pallas_base;
 pub mod polynomial;
 pub mod secp256k1_base;
 pub mod secp256k1_scalar;
+pub mod vesta_base;
 pub mod zero_poly_coset;
 
 #[cfg(test)]
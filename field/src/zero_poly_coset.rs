@@ -1,19 +1,44 @@
+use std::sync::Arc;
+
 use crate::field_types::Field;
 use crate::packed_field::PackedField;
 
 /// Precomputations of the evaluation of `Z_H(X) = X^n - 1` on a coset `gK` with `H <= K`.
+///
+/// The precomputed tables are reference-counted, so cloning an instance is cheap and the clone
+/// shares its tables with the original. Callers proving several circuits of the same size can
+/// compare instances with `==` to tell whether one can be reused in place of another.
+#[derive(Clone, Debug)]
 pub struct ZeroPolyOnCoset<F: Field> {
+    /// `log_2(|H|)`.
+    n_log: usize,
+    /// `log_2(|K|/|H|)`.
+    rate_bits: usize,
     /// `n = |H|`.
     n: F,
     /// `rate = |K|/|H|`.
     rate: usize,
     /// Holds `g^n * (w^n)^i - 1 = g^n * v^i - 1` for `i in 0..rate`, with `w` a generator of `K` and `v` a
     /// `rate`-primitive root of unity.
-    evals: Vec<F>,
+    evals: Arc<Vec<F>>,
     /// Holds the multiplicative inverses of `evals`.
-    inverses: Vec<F>,
+    inverses: Arc<Vec<F>>,
+    /// Holds `L_1(g * w^i)` for `i in 0..n * rate`, if precomputed with `new_with_l1`.
+    l1s: Option<Arc<Vec<F>>>,
+}
+
+/// Two instances are equal if they were built for the same `H` and `K`, and agree on whether `L_1`
+/// was precomputed. The tables themselves are determined by these parameters, so are not compared.
+impl<F: Field> PartialEq for ZeroPolyOnCoset<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.n_log == other.n_log
+            && self.rate_bits == other.rate_bits
+            && self.l1s.is_some() == other.l1s.is_some()
+    }
 }
 
+impl<F: Field> Eq for ZeroPolyOnCoset<F> {}
+
 impl<F: Field> ZeroPolyOnCoset<F> {
     pub fn new(n_log: usize, rate_bits: usize) -> Self {
         let g_pow_n = F::coset_shift().exp_power_of_2(n_log);
@@ -23,13 +48,36 @@ impl<F: Field> ZeroPolyOnCoset<F> {
             .collect::<Vec<_>>();
         let inverses = F::batch_multiplicative_inverse(&evals);
         Self {
+            n_log,
+            rate_bits,
             n: F::from_canonical_usize(1 << n_log),
             rate: 1 << rate_bits,
-            evals,
-            inverses,
+            evals: Arc::new(evals),
+            inverses: Arc::new(inverses),
+            l1s: None,
         }
     }
 
+    /// Like `new`, but additionally precomputes `L_1` on the whole coset `gK`, so that `l1` does
+    /// not need to invert anything. All denominators are inverted at once using Montgomery's trick.
+    pub fn new_with_l1(n_log: usize, rate_bits: usize) -> Self {
+        let mut res = Self::new(n_log, rate_bits);
+        let g = F::coset_shift();
+        let denominators = F::two_adic_subgroup(n_log + rate_bits)
+            .into_iter()
+            .map(|x| res.n * (g * x - F::ONE))
+            .collect::<Vec<_>>();
+        let denominator_inverses = F::batch_multiplicative_inverse(&denominators);
+        res.l1s = Some(Arc::new(
+            denominator_inverses
+                .into_iter()
+                .enumerate()
+                .map(|(i, d_inv)| res.eval(i) * d_inv)
+                .collect(),
+        ));
+        res
+    }
+
     /// Returns `Z_H(g * w^i)`.
     pub fn eval(&self, i: usize) -> F {
         self.evals[i % self.rate]
@@ -56,4 +104,63 @@ impl<F: Field> ZeroPolyOnCoset<F> {
         // Could also precompute the inverses using Montgomery.
         self.eval(i) * (self.n * (x - F::ONE)).inverse()
     }
+
+    /// Returns `L_1(x)` with `x = g * w^i`, reading the table precomputed by `new_with_l1` if
+    /// available, and falling back to `eval_l1` otherwise.
+    pub fn l1(&self, i: usize, x: F) -> F {
+        match &self.l1s {
+            Some(l1s) => l1s[i],
+            None => self.eval_l1(i, x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::field_types::Field;
+    use crate::goldilocks_field::GoldilocksField;
+    use crate::zero_poly_coset::ZeroPolyOnCoset;
+
+    #[test]
+    fn test_l1_table() {
+        type F = GoldilocksField;
+        let n_log = 4;
+        let rate_bits = 2;
+
+        let z_h_on_coset = ZeroPolyOnCoset::<F>::new_with_l1(n_log, rate_bits);
+        let points = F::two_adic_subgroup(n_log + rate_bits);
+        for (i, &x) in points.iter().enumerate() {
+            let shifted_x = F::coset_shift() * x;
+            assert_eq!(
+                z_h_on_coset.l1(i, shifted_x),
+                z_h_on_coset.eval_l1(i, shifted_x)
+            );
+        }
+    }
+
+    #[test]
+    fn test_reuse() {
+        type F = GoldilocksField;
+
+        let a = ZeroPolyOnCoset::<F>::new_with_l1(4, 2);
+        let b = a.clone();
+        assert_eq!(a, b);
+        assert!(Arc::ptr_eq(&a.evals, &b.evals));
+        assert!(Arc::ptr_eq(&a.inverses, &b.inverses));
+        assert!(Arc::ptr_eq(
+            a.l1s.as_ref().unwrap(),
+            b.l1s.as_ref().unwrap()
+        ));
+
+        // Freshly built instances with the same parameters are equal too, with equal tables.
+        let c = ZeroPolyOnCoset::new_with_l1(4, 2);
+        assert_eq!(a, c);
+        assert_eq!(a.evals, c.evals);
+        assert_eq!(a.l1s, c.l1s);
+        assert_ne!(a, ZeroPolyOnCoset::new(4, 2));
+        assert_ne!(a, ZeroPolyOnCoset::new_with_l1(4, 3));
+        assert_ne!(a, ZeroPolyOnCoset::new_with_l1(5, 2));
+    }
 }
@@ -8,6 +8,7 @@ use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
 use crate::hash::hash_types::{MerkleCapTarget, RichField};
 use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::challenger::{Challenger, RecursiveChallenger};
+use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
@@ -22,6 +23,18 @@ impl<F: RichField, H: Hasher<F>> Challenger<F, H> {
         }
     }
 
+    /// Squeezes one scaling factor per oracle, for combining the polynomials of each oracle
+    /// separately. The prover and verifier must call this at the same point of the transcript.
+    pub fn fri_combination_alphas<const D: usize>(
+        &mut self,
+        num_oracles: usize,
+    ) -> Vec<F::Extension>
+    where
+        F: RichField + Extendable<D>,
+    {
+        self.get_n_extension_challenges::<D>(num_oracles)
+    }
+
     pub fn fri_challenges<C: GenericConfig<D, F = F>, const D: usize>(
         &mut self,
         commit_phase_merkle_caps: &[MerkleCap<F, C::Hasher>],
@@ -82,6 +95,17 @@ impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
         }
     }
 
+    /// Recursive version of `Challenger::fri_combination_alphas`.
+    pub fn fri_combination_alphas(
+        &mut self,
+        builder: &mut CircuitBuilder<F, D>,
+        num_oracles: usize,
+    ) -> Vec<ExtensionTarget<D>> {
+        (0..num_oracles)
+            .map(|_| self.get_extension_challenge(builder))
+            .collect()
+    }
+
     pub fn fri_challenges<C: GenericConfig<D, F = F>>(
         &mut self,
         builder: &mut CircuitBuilder<F, D>,
@@ -128,3 +152,56 @@ impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::field_types::Field;
+
+    use crate::iop::challenger::{Challenger, RecursiveChallenger};
+    use crate::iop::generator::generate_partial_witness;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_fri_combination_alphas() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::InnerHasher;
+        let num_oracles = 6;
+
+        let seed = F::rand_vec(4);
+        let alphas = |seed: &[F]| {
+            let mut challenger = Challenger::<F, H>::new();
+            challenger.observe_elements(seed);
+            challenger.fri_combination_alphas::<D>(num_oracles)
+        };
+
+        // The alphas only depend on the transcript, and are distinct.
+        let prover_alphas = alphas(&seed);
+        assert_eq!(prover_alphas.len(), num_oracles);
+        assert_eq!(prover_alphas, alphas(&seed));
+        let mut dedup = prover_alphas.clone();
+        dedup.dedup();
+        assert_eq!(dedup, prover_alphas);
+        assert_ne!(prover_alphas, alphas(&F::rand_vec(4)));
+
+        // A recursive verifier derives the same alphas.
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut recursive_challenger = RecursiveChallenger::<F, H, D>::new(&mut builder);
+        recursive_challenger.observe_elements(&builder.constants(&seed));
+        let recursive_alphas =
+            recursive_challenger.fri_combination_alphas(&mut builder, num_oracles);
+        let circuit = builder.build::<C>();
+        let witness =
+            generate_partial_witness(PartialWitness::new(), &circuit.prover_only, &circuit.common);
+        let recursive_alpha_values: Vec<_> = recursive_alphas
+            .into_iter()
+            .map(|alpha| witness.get_extension_target(alpha))
+            .collect();
+        assert_eq!(recursive_alpha_values, prover_alphas);
+    }
+}
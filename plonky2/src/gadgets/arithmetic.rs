@@ -212,6 +212,23 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             .unwrap_or_else(|| self.one())
     }
 
+    /// Returns `[1, x, x^2, ..., x^(n-1)]`. Unlike `powers`, which lazily yields powers of an
+    /// extension target, this computes them all upfront with a chain of multiplications.
+    pub fn first_powers(&mut self, x: Target, n: usize) -> Vec<Target> {
+        let mut powers = Vec::with_capacity(n);
+        if n == 0 {
+            return powers;
+        }
+
+        let mut current = self.one();
+        powers.push(current);
+        for _ in 1..n {
+            current = self.mul(current, x);
+            powers.push(current);
+        }
+        powers
+    }
+
     /// Exponentiate `base` to the power of `2^power_log`.
     pub fn exp_power_of_2(&mut self, base: Target, power_log: usize) -> Target {
         if power_log > self.num_base_arithmetic_ops_per_gate() {
@@ -323,6 +340,45 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let res = self.sub(one, b.target);
         BoolTarget::new_unsafe(res)
     }
+
+    /// Adds two little-endian bit vectors of equal length with a ripple-carry adder, returning the
+    /// sum bits and the final carry.
+    pub fn ripple_carry_add(
+        &mut self,
+        a_bits: &[BoolTarget],
+        b_bits: &[BoolTarget],
+    ) -> (Vec<BoolTarget>, BoolTarget) {
+        assert_eq!(a_bits.len(), b_bits.len());
+
+        let mut carry = self._false();
+        let mut sum_bits = Vec::with_capacity(a_bits.len());
+        for (&a, &b) in a_bits.iter().zip(b_bits) {
+            let (sum, carry_out) = self.full_add(a, b, carry);
+            sum_bits.push(sum);
+            carry = carry_out;
+        }
+
+        (sum_bits, carry)
+    }
+
+    /// Returns the sum bit and carry bit of `a + b + c`.
+    fn full_add(
+        &mut self,
+        a: BoolTarget,
+        b: BoolTarget,
+        c: BoolTarget,
+    ) -> (BoolTarget, BoolTarget) {
+        // `a ^ b = a + b - 2ab`. When `a ^ b` is set, `ab` is not, so the carry `ab | (a ^ b) c`
+        // is the sum `ab + (a ^ b) c`.
+        let ab = self.mul(a.target, b.target);
+        let a_plus_b = self.add(a.target, b.target);
+        let a_xor_b = self.mul_const_add(-F::TWO, ab, a_plus_b);
+        let a_xor_b_and_c = self.mul(a_xor_b, c.target);
+        let a_xor_b_plus_c = self.add(a_xor_b, c.target);
+        let sum = self.mul_const_add(-F::TWO, a_xor_b_and_c, a_xor_b_plus_c);
+        let carry = self.add(ab, a_xor_b_and_c);
+        (BoolTarget::new_unsafe(sum), BoolTarget::new_unsafe(carry))
+    }
 }
 
 /// Represents a base arithmetic operation in the circuit. Used to memoize results.
@@ -334,3 +390,85 @@ pub(crate) struct BaseArithmeticOperation<F: Field64> {
     multiplicand_1: Target,
     addend: Target,
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use rand::Rng;
+
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn test_first_powers() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let n = 10;
+        let x_value = F::rand();
+        let x = builder.add_virtual_target();
+        pw.set_target(x, x_value);
+
+        let powers = builder.first_powers(x, n);
+        assert_eq!(powers.len(), n);
+        for (i, &power) in powers.iter().enumerate() {
+            let expected = builder.constant(x_value.exp_u64(i as u64));
+            builder.connect(power, expected);
+        }
+        assert!(builder.first_powers(x, 0).is_empty());
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_ripple_carry_add() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let num_bits = 16;
+        let mut rng = rand::thread_rng();
+        let a_value = rng.gen::<u16>() as u64;
+        let b_value = rng.gen::<u16>() as u64;
+        let expected = a_value + b_value;
+
+        let a_bits: Vec<_> = (0..num_bits)
+            .map(|_| builder.add_virtual_bool_target())
+            .collect();
+        let b_bits: Vec<_> = (0..num_bits)
+            .map(|_| builder.add_virtual_bool_target())
+            .collect();
+        for i in 0..num_bits {
+            pw.set_bool_target(a_bits[i], (a_value >> i) & 1 == 1);
+            pw.set_bool_target(b_bits[i], (b_value >> i) & 1 == 1);
+        }
+
+        let (sum_bits, carry) = builder.ripple_carry_add(&a_bits, &b_bits);
+        assert_eq!(sum_bits.len(), num_bits);
+        for (i, &bit) in sum_bits.iter().enumerate() {
+            let expected_bit = builder.constant_bool((expected >> i) & 1 == 1);
+            builder.connect(bit.target, expected_bit.target);
+        }
+        let expected_carry = builder.constant_bool((expected >> num_bits) & 1 == 1);
+        builder.connect(carry.target, expected_carry.target);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
@@ -4,11 +4,12 @@ use plonky2_field::field_types::{Field, Field64};
 use plonky2_util::bits_u64;
 
 use crate::gates::arithmetic_extension::ArithmeticExtensionGate;
+use crate::gates::ext_equal::ExtEqualGate;
 use crate::gates::multiplication_extension::MulExtensionGate;
 use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::{ExtensionAlgebraTarget, ExtensionTarget};
 use crate::iop::generator::{GeneratedValues, SimpleGenerator};
-use crate::iop::target::Target;
+use crate::iop::target::{BoolTarget, Target};
 use crate::iop::witness::{PartitionWitness, Witness};
 use crate::plonk::circuit_builder::CircuitBuilder;
 
@@ -81,6 +82,23 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         ExtensionTarget::from_range(gate, ArithmeticExtensionGate::<D>::wires_ith_output(i))
     }
 
+    /// Returns a boolean which is true iff `x = y`.
+    pub fn is_equal_extension(
+        &mut self,
+        x: ExtensionTarget<D>,
+        y: ExtensionTarget<D>,
+    ) -> BoolTarget {
+        let gate = ExtEqualGate::new_from_config(&self.config);
+        let (gate_index, i) = self.find_slot(gate.clone(), &[], &[]);
+
+        let wires_x = ExtensionTarget::from_range(gate_index, gate.wires_ith_input_x(i));
+        let wires_y = ExtensionTarget::from_range(gate_index, gate.wires_ith_input_y(i));
+        self.connect_extension(x, wires_x);
+        self.connect_extension(y, wires_y);
+
+        BoolTarget::new_unsafe(Target::wire(gate_index, gate.wire_ith_output(i)))
+    }
+
     fn compute_mul_extension_operation(
         &mut self,
         operation: ExtensionArithmeticOperation<F, D>,
@@ -4,6 +4,7 @@ use plonky2_field::extension_field::Extendable;
 
 use crate::gates::add_many_u32::U32AddManyGate;
 use crate::gates::arithmetic_u32::U32ArithmeticGate;
+use crate::gates::gate::Gate;
 use crate::gates::subtraction_u32::U32SubtractionGate;
 use crate::hash::hash_types::RichField;
 use crate::iop::generator::{GeneratedValues, SimpleGenerator};
@@ -116,7 +117,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         match to_add.len() {
             0 => (self.zero_u32(), self.zero_u32()),
             1 => (to_add[0], self.zero_u32()),
-            2 => self.add_u32(to_add[0], to_add[1]),
+            2 if self.prefer_add_u32_for_pair() => self.add_u32(to_add[0], to_add[1]),
             _ => {
                 let num_addends = to_add.len();
                 let gate = U32AddManyGate::<F, D>::new_from_config(&self.config, num_addends);
@@ -142,6 +143,19 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 
+    /// Returns whether adding two limbs should use a `U32ArithmeticGate` rather than a
+    /// two-addend `U32AddManyGate`; see `prefer_gate`.
+    fn prefer_add_u32_for_pair(&self) -> bool {
+        let arithmetic_gate = U32ArithmeticGate::<F, D>::new_from_config(&self.config);
+        let add_many_gate = U32AddManyGate::<F, D>::new_from_config(&self.config, 2);
+        self.prefer_gate(
+            &arithmetic_gate,
+            &[],
+            &add_many_gate,
+            &[F::from_canonical_usize(2)],
+        )
+    }
+
     pub fn add_u32s_with_carry(
         &mut self,
         to_add: &[U32Target],
@@ -175,6 +189,21 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         self.mul_add_u32(a, b, zero)
     }
 
+    /// Routes the high half of the output of operation `prev_op` of the `U32ArithmeticGate` at
+    /// `prev_gate` directly into `next_addend`, typically the addend wire of a later
+    /// `U32ArithmeticGate` operation. This chains the limb products of a multi-limb multiplication
+    /// without materializing the carry in between.
+    pub fn chain_high_half(&mut self, prev_gate: usize, prev_op: usize, next_addend: Target) {
+        let gate = U32ArithmeticGate::<F, D>::new_from_config(&self.config);
+        debug_assert_eq!(
+            self.gate_instances[prev_gate].gate_ref.0.id(),
+            gate.id(),
+            "Expected a U32ArithmeticGate"
+        );
+        let high_half = Target::wire(prev_gate, gate.wire_ith_output_high_half(prev_op));
+        self.connect(high_half, next_addend);
+    }
+
     // Returns x - y - borrow, as a pair (result, borrow), where borrow is 0 or 1 depending on whether borrowing from the next digit is required (iff y + borrow > x).
     pub fn sub_u32(
         &mut self,
@@ -197,6 +226,86 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
         (output_result, output_borrow)
     }
+
+    /// Asserts that `borrow`, the output borrow of a `sub_u32` operation, is zero, i.e. that the
+    /// subtraction did not wrap around. If `borrow` is the output wire of a `U32SubtractionGate`, a
+    /// generator also checks the subtraction's inputs, so that a wrapped subtraction is reported
+    /// with a descriptive panic, rather than as a conflicting assignment of `borrow`, whenever
+    /// those inputs are known before the gate's own generator runs.
+    pub fn assert_no_borrow(&mut self, borrow: U32Target) {
+        if let Target::Wire(wire) = borrow.0 {
+            let gate = U32SubtractionGate::<F, D>::new_from_config(&self.config);
+            let is_subtraction = self.gate_instances[wire.gate].gate_ref.0.id() == gate.id();
+            let op = (0..gate.num_ops).find(|&i| gate.wire_ith_output_borrow(i) == wire.input);
+            if let Some(op) = op.filter(|_| is_subtraction) {
+                self.add_simple_generator(NoBorrowAssertionGenerator {
+                    x: Target::wire(wire.gate, gate.wire_ith_input_x(op)),
+                    y: Target::wire(wire.gate, gate.wire_ith_input_y(op)),
+                    borrow_in: Target::wire(wire.gate, gate.wire_ith_input_borrow(op)),
+                });
+            }
+        }
+        self.assert_zero(borrow.0);
+    }
+
+    /// Normalizes a list of limb sums, each a field element possibly exceeding 32 bits, into 32-bit
+    /// limbs by propagating carries from the least significant limb upwards. Returns the limbs
+    /// together with the final carry out.
+    pub fn propagate_carries_u32(&mut self, limb_sums: &[Target]) -> (Vec<U32Target>, U32Target) {
+        if limb_sums.is_empty() {
+            return (vec![], self.zero_u32());
+        }
+
+        let num_limbs = limb_sums.len();
+        let limbs = self.add_virtual_u32_targets(num_limbs);
+        let carries = self.add_virtual_u32_targets(num_limbs);
+        let carry_diff_inverses = self.add_virtual_targets(num_limbs);
+        self.add_simple_generator(CarryPropagateGenerator::<F, D> {
+            limb_sums: limb_sums.to_vec(),
+            limbs: limbs.clone(),
+            carries: carries.clone(),
+            carry_diff_inverses: carry_diff_inverses.clone(),
+            _phantom: PhantomData,
+        });
+
+        self.assert_carries_propagated_u32(limb_sums, &limbs, &carries, &carry_diff_inverses);
+
+        (limbs, carries[num_limbs - 1])
+    }
+
+    /// Constrains `limbs` and `carries` to be the witness computed by `CarryPropagateGenerator`.
+    ///
+    /// A 32-bit carry and limb can recombine to any value below `2^64`, so a total below
+    /// `2^32 - 1` would also be matched by the decomposition of the total plus the field order.
+    /// Since totals are canonical field elements, at most `(2^32 - 1) * 2^32`, the carry can only
+    /// be `2^32 - 1` if the limb is zero. We enforce this with a witnessed inverse of
+    /// `carry - (2^32 - 1)`, which must exist unless the limb is zero.
+    fn assert_carries_propagated_u32(
+        &mut self,
+        limb_sums: &[Target],
+        limbs: &[U32Target],
+        carries: &[U32Target],
+        carry_diff_inverses: &[Target],
+    ) {
+        let base = self.constant(F::from_canonical_u64(1 << 32));
+        let max_carry = F::from_canonical_u32(u32::MAX);
+        let one = self.one();
+        let mut carry = self.zero();
+        for i in 0..limb_sums.len() {
+            let total = self.add(limb_sums[i], carry);
+            let recombined = self.mul_add(carries[i].0, base, limbs[i].0);
+            self.connect(total, recombined);
+            self.range_check_u32(vec![limbs[i], carries[i]]);
+
+            let carry_diff = self.add_const(carries[i].0, -max_carry);
+            let not_max =
+                self.arithmetic(F::ONE, F::NEG_ONE, carry_diff, carry_diff_inverses[i], one);
+            let not_max_times_limb = self.mul(not_max, limbs[i].0);
+            self.assert_zero(not_max_times_limb);
+
+            carry = carries[i].0;
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -225,12 +334,95 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
     }
 }
 
+/// Checks that `x - y - borrow_in` does not borrow; see `assert_no_borrow`. This only reports a
+/// wrapped subtraction early and sets no targets.
+#[derive(Debug)]
+struct NoBorrowAssertionGenerator {
+    x: Target,
+    y: Target,
+    borrow_in: Target,
+}
+
+impl<F: RichField> SimpleGenerator<F> for NoBorrowAssertionGenerator {
+    fn dependencies(&self) -> Vec<Target> {
+        vec![self.x, self.y, self.borrow_in]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, _out_buffer: &mut GeneratedValues<F>) {
+        let x = witness.get_target(self.x).to_canonical_u64();
+        let y = witness.get_target(self.y).to_canonical_u64();
+        let borrow_in = witness.get_target(self.borrow_in).to_canonical_u64();
+        assert!(
+            y + borrow_in <= x,
+            "Subtraction borrowed, but its minuend was assumed to be at least its subtrahend"
+        );
+    }
+}
+
+/// Propagates carries through a list of limb sums, as produced by limb-wise addition or
+/// multiplication of multi-limb values. For each `i`, sets `limbs[i]` to
+/// `(limb_sums[i] + carry_in) mod 2^32` and `carries[i]` to the carry out, which is the carry in
+/// for limb `i + 1`. The first carry in is zero.
+///
+/// The sum of a limb sum and the carry in is taken in the field, so that it is canonical and each
+/// carry fits in 32 bits. For each `i`, also sets `carry_diff_inverses[i]` to the inverse of
+/// `carries[i] - (2^32 - 1)`, or zero if there is none. This generator only fills in the witness;
+/// see `assert_carries_propagated_u32` for the corresponding constraints.
+#[derive(Debug)]
+pub(crate) struct CarryPropagateGenerator<F: RichField + Extendable<D>, const D: usize> {
+    pub(crate) limb_sums: Vec<Target>,
+    pub(crate) limbs: Vec<U32Target>,
+    pub(crate) carries: Vec<U32Target>,
+    pub(crate) carry_diff_inverses: Vec<Target>,
+    pub(crate) _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
+    for CarryPropagateGenerator<F, D>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.limb_sums.clone()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        debug_assert_eq!(self.limb_sums.len(), self.limbs.len());
+        debug_assert_eq!(self.limb_sums.len(), self.carries.len());
+        debug_assert_eq!(self.limb_sums.len(), self.carry_diff_inverses.len());
+
+        let max_carry = F::from_canonical_u32(u32::MAX);
+        let mut carry = F::ZERO;
+        for (((&limb_sum, &limb), &carry_out), &carry_diff_inverse) in self
+            .limb_sums
+            .iter()
+            .zip(&self.limbs)
+            .zip(&self.carries)
+            .zip(&self.carry_diff_inverses)
+        {
+            let total = (witness.get_target(limb_sum) + carry).to_canonical_u64();
+            carry = F::from_canonical_u64(total >> 32);
+
+            out_buffer.set_u32_target(limb, total as u32);
+            out_buffer.set_target(carry_out.0, carry);
+            out_buffer.set_target(
+                carry_diff_inverse,
+                (carry - max_carry).try_inverse().unwrap_or(F::ZERO),
+            );
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
+    use plonky2_field::field_types::{Field, PrimeField64};
     use rand::{thread_rng, Rng};
 
-    use crate::iop::witness::PartialWitness;
+    use crate::gates::arithmetic_u32::U32ArithmeticGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::subtraction_u32::U32SubtractionGate;
+    use crate::iop::generator::generate_partial_witness;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
     use crate::plonk::circuit_builder::CircuitBuilder;
     use crate::plonk::circuit_data::CircuitConfig;
     use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
@@ -269,4 +461,287 @@ mod tests {
         let proof = data.prove(pw).unwrap();
         verify(proof, &data.verifier_only, &data.common)
     }
+
+    #[test]
+    fn test_add_pair_prefers_open_slot() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let mut rng = thread_rng();
+        let values: Vec<u32> = (0..3).map(|_| rng.gen()).collect();
+        let targets = builder.add_virtual_u32_targets(3);
+        for (&t, &v) in targets.iter().zip(&values) {
+            pw.set_u32_target(t, v);
+        }
+        builder.zero();
+
+        // This leaves a two-addend `U32AddManyGate` row with unused operations, which the next
+        // addition of a pair should use rather than starting a `U32ArithmeticGate` row.
+        builder.add_u32s_with_carry(&targets[..2], targets[2]);
+        let num_gates = builder.num_gates();
+        let (result_low, result_high) = builder.add_many_u32(&targets[1..]);
+        assert_eq!(builder.num_gates(), num_gates);
+
+        let sum = values[1] as u64 + values[2] as u64;
+        let expected_low = builder.constant_u32(sum as u32);
+        let expected_high = builder.constant_u32((sum >> 32) as u32);
+        builder.connect_u32(result_low, expected_low);
+        builder.connect_u32(result_high, expected_high);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_propagate_carries_u32() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        const NUM_LIMBS: usize = 8;
+
+        let config = CircuitConfig::standard_recursion_config();
+
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // Worst case: the first limb sum overflows, and every following limb is all ones, so the
+        // carry ripples through the whole chain. With the carry, the last total is maximal in the
+        // field, so its carry is `2^32 - 1` and its limb is zero.
+        let max_u32 = u32::MAX as u64;
+        let mut limb_sum_values = vec![max_u32 + 1];
+        limb_sum_values.extend([max_u32; NUM_LIMBS - 2]);
+        limb_sum_values.push(F::NEG_ONE.to_canonical_u64() - 1);
+
+        let limb_sums = builder.add_virtual_targets(NUM_LIMBS);
+        let (limbs, final_carry) = builder.propagate_carries_u32(&limb_sums);
+
+        let mut carry = 0u128;
+        for i in 0..NUM_LIMBS {
+            pw.set_target(limb_sums[i], F::from_canonical_u64(limb_sum_values[i]));
+
+            let total = limb_sum_values[i] as u128 + carry;
+            carry = total >> 32;
+            let expected_limb = builder.constant_u32(total as u32);
+            builder.connect_u32(limbs[i], expected_limb);
+        }
+        let expected_carry = builder.constant_u32(carry as u32);
+        builder.connect_u32(final_carry, expected_carry);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_propagate_carries_u32_non_canonical() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // A zero total is also matched by the limb `1` and the carry `2^32 - 1`, whose
+        // recombination `(2^32 - 1) * 2^32 + 1` is the field order.
+        let limb_sum = builder.add_virtual_target();
+        let limb = builder.add_virtual_u32_target();
+        let carry = builder.add_virtual_u32_target();
+        let carry_diff_inverse = builder.add_virtual_target();
+        builder.assert_carries_propagated_u32(
+            &[limb_sum],
+            &[limb],
+            &[carry],
+            &[carry_diff_inverse],
+        );
+
+        pw.set_target(limb_sum, F::ZERO);
+        pw.set_u32_target(limb, 1);
+        pw.set_u32_target(carry, u32::MAX);
+        pw.set_target(carry_diff_inverse, F::ZERO);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common).unwrap();
+    }
+
+    #[test]
+    fn test_sub_u32_unused_ops() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // Leave room for exactly three subtraction operations per gate.
+        let config = CircuitConfig {
+            num_wires: 63,
+            num_routed_wires: 15,
+            ..CircuitConfig::standard_recursion_config()
+        };
+        let gate = U32SubtractionGate::<F, D>::new_from_config(&config);
+        assert_eq!(gate.num_ops, 3);
+
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_u32(3);
+        let y = builder.constant_u32(5);
+        let borrow = builder.zero_u32();
+        let (result, output_borrow) = builder.sub_u32(x, y, borrow);
+
+        let expected_result = builder.constant_u32(u32::MAX - 1);
+        let expected_borrow = builder.one_u32();
+        builder.connect_u32(result, expected_result);
+        builder.connect_u32(output_borrow, expected_borrow);
+
+        let gate_index = match result.0 {
+            Target::Wire(wire) => wire.gate,
+            Target::VirtualTarget { .. } => unreachable!(),
+        };
+
+        let data = builder.build::<C>();
+
+        // Every wire of the unused operations should be assigned.
+        let witness = generate_partial_witness(pw.clone(), &data.prover_only, &data.common);
+        for (wire, _) in gate.wire_labels() {
+            assert!(witness
+                .try_get_target(Target::wire(gate_index, wire))
+                .is_some());
+        }
+
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_assert_no_borrow() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_u32(5);
+        let y = builder.constant_u32(5);
+        let borrow = builder.zero_u32();
+        let (_, output_borrow) = builder.sub_u32(x, y, borrow);
+        builder.assert_no_borrow(output_borrow);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_assert_no_borrow_virtual() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let borrow = builder.add_virtual_u32_target();
+        pw.set_u32_target(borrow, 0);
+        builder.assert_no_borrow(borrow);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    #[should_panic(expected = "Subtraction borrowed")]
+    fn test_assert_no_borrow_fails() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // The inputs are set directly, so that the check runs before the gate's generator.
+        let x = builder.add_virtual_u32_target();
+        let y = builder.add_virtual_u32_target();
+        let borrow = builder.add_virtual_u32_target();
+        pw.set_u32_target(x, 3);
+        pw.set_u32_target(y, 5);
+        pw.set_u32_target(borrow, 0);
+        let (_, output_borrow) = builder.sub_u32(x, y, borrow);
+        builder.assert_no_borrow(output_borrow);
+
+        let data = builder.build::<C>();
+        data.prove(pw).unwrap();
+    }
+
+    #[test]
+    fn test_chain_high_half() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // Multiply a 32-bit `a` by a 64-bit `b = b_0 + 2^32 b_1`, one limb of `b` at a time.
+        let mut rng = thread_rng();
+        let a_value: u32 = rng.gen();
+        let b_value: u64 = rng.gen();
+        let product_value = a_value as u128 * b_value as u128;
+
+        let a = builder.constant_u32(a_value);
+        let b_0 = builder.constant_u32(b_value as u32);
+        let b_1 = builder.constant_u32((b_value >> 32) as u32);
+        let zero = builder.zero();
+
+        let gate = U32ArithmeticGate::<F, D>::new_from_config(&builder.config);
+        let mul_slot = |builder: &mut CircuitBuilder<F, D>, x: Target, y: Target| {
+            let (gate_index, op) = builder.find_slot(gate, &[], &[]);
+            builder.connect(
+                x,
+                Target::wire(gate_index, gate.wire_ith_multiplicand_0(op)),
+            );
+            builder.connect(
+                y,
+                Target::wire(gate_index, gate.wire_ith_multiplicand_1(op)),
+            );
+            (gate_index, op)
+        };
+        let (gate_0, op_0) = mul_slot(&mut builder, a.0, b_0.0);
+        builder.connect(zero, Target::wire(gate_0, gate.wire_ith_addend(op_0)));
+        let (gate_1, op_1) = mul_slot(&mut builder, a.0, b_1.0);
+        builder.chain_high_half(
+            gate_0,
+            op_0,
+            Target::wire(gate_1, gate.wire_ith_addend(op_1)),
+        );
+
+        let limbs = [
+            Target::wire(gate_0, gate.wire_ith_output_low_half(op_0)),
+            Target::wire(gate_1, gate.wire_ith_output_low_half(op_1)),
+            Target::wire(gate_1, gate.wire_ith_output_high_half(op_1)),
+        ];
+        for (i, limb) in limbs.into_iter().enumerate() {
+            let expected =
+                builder.constant(F::from_canonical_u32((product_value >> (32 * i)) as u32));
+            builder.connect(limb, expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
 }
@@ -112,6 +112,18 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
     // Subtract two `BigUintTarget`s. We assume that the first is larger than the second.
     pub fn sub_biguint(&mut self, a: &BigUintTarget, b: &BigUintTarget) -> BigUintTarget {
+        let (diff, _borrow) = self.sub_biguint_with_borrow(a, b);
+        // Borrow should be zero here.
+        diff
+    }
+
+    /// Like `sub_biguint`, but also returns the final borrow, which is set iff `a < b`, so that
+    /// callers which know `a >= b` can check it with `assert_no_borrow`.
+    pub fn sub_biguint_with_borrow(
+        &mut self,
+        a: &BigUintTarget,
+        b: &BigUintTarget,
+    ) -> (BigUintTarget, U32Target) {
         let (a, b) = self.pad_biguints(a, b);
         let num_limbs = a.limbs.len();
 
@@ -123,11 +135,13 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             result_limbs.push(result);
             borrow = new_borrow;
         }
-        // Borrow should be zero here.
 
-        BigUintTarget {
-            limbs: result_limbs,
-        }
+        (
+            BigUintTarget {
+                limbs: result_limbs,
+            },
+            borrow,
+        )
     }
 
     pub fn mul_biguint(&mut self, a: &BigUintTarget, b: &BigUintTarget) -> BigUintTarget {
@@ -1,3 +1,6 @@
+pub mod bn254;
+pub mod pasta;
+
 use plonky2_field::extension_field::Extendable;
 use plonky2_field::field_types::Field;
 
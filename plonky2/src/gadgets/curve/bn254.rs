@@ -0,0 +1,145 @@
+use num::BigUint;
+use plonky2_field::bn254_base::Bn254Base;
+use plonky2_field::bn254_scalar::Bn254Scalar;
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+
+use crate::gadgets::nonnative::NonNativeTarget;
+use crate::hash::hash_types::RichField;
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+/// The order `q` of the BN254 base field.
+pub fn bn254_base_modulus() -> BigUint {
+    Bn254Base::order()
+}
+
+/// The order `r` of the BN254 scalar field, i.e. of the group of points on the curve.
+pub fn bn254_scalar_modulus() -> BigUint {
+    Bn254Scalar::order()
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    pub fn add_virtual_bn254_base_target(&mut self) -> NonNativeTarget<Bn254Base> {
+        self.add_virtual_nonnative_target()
+    }
+
+    pub fn add_virtual_bn254_scalar_target(&mut self) -> NonNativeTarget<Bn254Scalar> {
+        self.add_virtual_nonnative_target()
+    }
+
+    pub fn constant_bn254_base(&mut self, x: Bn254Base) -> NonNativeTarget<Bn254Base> {
+        self.constant_nonnative(x)
+    }
+
+    pub fn constant_bn254_scalar(&mut self, x: Bn254Scalar) -> NonNativeTarget<Bn254Scalar> {
+        self.constant_nonnative(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anyhow::Result;
+    use num::BigUint;
+    use plonky2_field::bn254_base::Bn254Base;
+    use plonky2_field::bn254_scalar::Bn254Scalar;
+    use plonky2_field::field_types::{Field, PrimeField};
+
+    use crate::gadgets::curve::bn254::{bn254_base_modulus, bn254_scalar_modulus};
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn test_bn254_moduli() {
+        let q = BigUint::from_str(
+            "21888242871839275222246405745257275088696311157297823662689037894645226208583",
+        )
+        .unwrap();
+        let r = BigUint::from_str(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+        )
+        .unwrap();
+
+        assert_eq!(bn254_base_modulus(), q);
+        assert_eq!(bn254_scalar_modulus(), r);
+
+        // Both moduli are 254-bit, so nonnative targets need eight 32-bit limbs.
+        for modulus in [q, r] {
+            assert_eq!(modulus.bits(), 254);
+        }
+
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_ecc_config());
+        assert_eq!(builder.add_virtual_bn254_base_target().value.num_limbs(), 8);
+        assert_eq!(
+            builder.add_virtual_bn254_scalar_target().value.num_limbs(),
+            8
+        );
+    }
+
+    #[test]
+    fn test_bn254_base_mul() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let x_ff = Bn254Base::rand();
+        let y_ff = Bn254Base::rand();
+        let product_ff = x_ff * y_ff;
+        assert_eq!(
+            product_ff.to_canonical_biguint(),
+            (x_ff.to_canonical_biguint() * y_ff.to_canonical_biguint()) % bn254_base_modulus()
+        );
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_bn254_base(x_ff);
+        let y = builder.constant_bn254_base(y_ff);
+        let product = builder.mul_nonnative(&x, &y);
+
+        let product_expected = builder.constant_bn254_base(product_ff);
+        builder.connect_nonnative(&product, &product_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_bn254_scalar_mul() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let x_ff = Bn254Scalar::rand();
+        let y_ff = Bn254Scalar::rand();
+        let product_ff = x_ff * y_ff;
+        assert_eq!(
+            product_ff.to_canonical_biguint(),
+            (x_ff.to_canonical_biguint() * y_ff.to_canonical_biguint()) % bn254_scalar_modulus()
+        );
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_bn254_scalar(x_ff);
+        let y = builder.constant_bn254_scalar(y_ff);
+        let product = builder.mul_nonnative(&x, &y);
+
+        let product_expected = builder.constant_bn254_scalar(product_ff);
+        builder.connect_nonnative(&product, &product_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
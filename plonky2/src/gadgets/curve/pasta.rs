@@ -0,0 +1,144 @@
+use num::BigUint;
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::pallas_base::PallasBase;
+use plonky2_field::vesta_base::VestaBase;
+
+use crate::gadgets::nonnative::NonNativeTarget;
+use crate::hash::hash_types::RichField;
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+/// The order of the Pallas base field, which is also the order of the Vesta scalar field.
+pub fn pallas_base_modulus() -> BigUint {
+    PallasBase::order()
+}
+
+/// The order of the Pallas scalar field, which is also the order of the Vesta base field.
+pub fn pallas_scalar_modulus() -> BigUint {
+    VestaBase::order()
+}
+
+/// The order of the Vesta base field, which is also the order of the Pallas scalar field.
+pub fn vesta_base_modulus() -> BigUint {
+    VestaBase::order()
+}
+
+/// The order of the Vesta scalar field, which is also the order of the Pallas base field.
+pub fn vesta_scalar_modulus() -> BigUint {
+    PallasBase::order()
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    pub fn add_virtual_pallas_base_target(&mut self) -> NonNativeTarget<PallasBase> {
+        self.add_virtual_nonnative_target()
+    }
+
+    pub fn add_virtual_vesta_base_target(&mut self) -> NonNativeTarget<VestaBase> {
+        self.add_virtual_nonnative_target()
+    }
+
+    pub fn constant_pallas_base(&mut self, x: PallasBase) -> NonNativeTarget<PallasBase> {
+        self.constant_nonnative(x)
+    }
+
+    pub fn constant_vesta_base(&mut self, x: VestaBase) -> NonNativeTarget<VestaBase> {
+        self.constant_nonnative(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anyhow::Result;
+    use num::BigUint;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::pallas_base::PallasBase;
+    use plonky2_field::vesta_base::VestaBase;
+
+    use crate::gadgets::curve::pasta::{
+        pallas_base_modulus, pallas_scalar_modulus, vesta_base_modulus, vesta_scalar_modulus,
+    };
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn test_pasta_moduli() {
+        let p = BigUint::from_str(
+            "28948022309329048855892746252171976963363056481941560715954676764349967630337",
+        )
+        .unwrap();
+        let q = BigUint::from_str(
+            "28948022309329048855892746252171976963363056481941647379679742748393362948097",
+        )
+        .unwrap();
+
+        assert_eq!(pallas_base_modulus(), p);
+        assert_eq!(vesta_scalar_modulus(), p);
+        assert_eq!(vesta_base_modulus(), q);
+        assert_eq!(pallas_scalar_modulus(), q);
+
+        // Both moduli are 255-bit primes with a two-adicity of 32.
+        for modulus in [p, q] {
+            assert_eq!(modulus.bits(), 255);
+            assert_eq!(modulus.trailing_zeros(), Some(0));
+            let modulus_minus_one = modulus - 1u32;
+            assert_eq!(modulus_minus_one.trailing_zeros(), Some(32));
+        }
+    }
+
+    #[test]
+    fn test_pallas_base_mul() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let x_ff = PallasBase::rand();
+        let y_ff = PallasBase::rand();
+        let product_ff = x_ff * y_ff;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_pallas_base(x_ff);
+        let y = builder.constant_pallas_base(y_ff);
+        let product = builder.mul_nonnative(&x, &y);
+
+        let product_expected = builder.constant_pallas_base(product_ff);
+        builder.connect_nonnative(&product, &product_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_vesta_base_mul() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let x_ff = VestaBase::rand();
+        let y_ff = VestaBase::rand();
+        let product_ff = x_ff * y_ff;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_vesta_base(x_ff);
+        let y = builder.constant_vesta_base(y_ff);
+        let product = builder.mul_nonnative(&x, &y);
+
+        let product_expected = builder.constant_vesta_base(product_ff);
+        builder.connect_nonnative(&product, &product_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
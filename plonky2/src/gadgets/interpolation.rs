@@ -1,12 +1,14 @@
 use std::ops::Range;
 
 use plonky2_field::extension_field::Extendable;
+use plonky2_util::log2_strict;
 
 use crate::gates::gate::Gate;
 use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
 use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::plonk_common::eval_l_i_recursively;
 
 /// Trait for gates which interpolate a polynomial, whose points are a (base field) coset of the multiplicative subgroup
 /// with the given size, and whose values are extension field elements, given by input wires.
@@ -103,6 +105,29 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
         ExtensionTarget::from_range(gate_index, gate.wires_evaluation_value())
     }
+
+    /// Interpolates a polynomial, whose points are the multiplicative subgroup of order
+    /// `values.len()`, and whose values are given. Returns the evaluation of the interpolant at
+    /// `evaluation_point`, as a sum of the values weighted by Lagrange basis polynomials.
+    ///
+    /// Requires `evaluation_point` to lie outside the subgroup. Otherwise one of the Lagrange basis
+    /// polynomials divides by zero, so no witness satisfies the circuit, even though the
+    /// interpolant is well defined there.
+    pub fn interpolate_subgroup(
+        &mut self,
+        values: &[ExtensionTarget<D>],
+        evaluation_point: ExtensionTarget<D>,
+    ) -> ExtensionTarget<D> {
+        let n = values.len();
+        let x_pow_n = self.exp_power_of_2_extension(evaluation_point, log2_strict(n));
+
+        let mut result = self.zero_extension();
+        for (i, &value) in values.iter().enumerate() {
+            let l_i = eval_l_i_recursively(self, n, i, evaluation_point, x_pow_n);
+            result = self.mul_add_extension(value, l_i, result);
+        }
+        result
+    }
 }
 
 #[cfg(test)]
@@ -111,6 +136,7 @@ mod tests {
     use plonky2_field::extension_field::FieldExtension;
     use plonky2_field::field_types::Field;
     use plonky2_field::interpolation::interpolant;
+    use plonky2_field::polynomial::PolynomialCoeffs;
 
     use crate::gates::interpolation::HighDegreeInterpolationGate;
     use crate::gates::low_degree_interpolation::LowDegreeInterpolationGate;
@@ -178,4 +204,41 @@ mod tests {
 
         verify(proof, &data.verifier_only, &data.common)
     }
+
+    #[test]
+    fn test_interpolate_subgroup() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        let config = CircuitConfig::standard_recursion_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let subgroup_bits = 3;
+        let len = 1 << subgroup_bits;
+        let g = F::primitive_root_of_unity(subgroup_bits);
+        let points = F::cyclic_subgroup_known_order(g, len);
+        let poly = PolynomialCoeffs::new(FF::rand_vec(len));
+
+        let value_targets = points
+            .iter()
+            .map(|&p| {
+                builder.constant_extension(poly.eval(<FF as FieldExtension<D>>::from_basefield(p)))
+            })
+            .collect::<Vec<_>>();
+
+        for _ in 0..4 {
+            let z = FF::rand();
+            let zt = builder.constant_extension(z);
+            let eval = builder.interpolate_subgroup(&value_targets, zt);
+            let true_eval_target = builder.constant_extension(poly.eval(z));
+            builder.connect_extension(eval, true_eval_target);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+
+        verify(proof, &data.verifier_only, &data.common)
+    }
 }
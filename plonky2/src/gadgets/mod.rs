@@ -8,9 +8,14 @@ pub mod hash;
 pub mod interpolation;
 pub mod multiple_comparison;
 pub mod nonnative;
+pub mod permutation;
 pub mod polynomial;
 pub mod random_access;
 pub mod range_check;
 pub mod select;
+pub mod sha256;
+pub mod small_prime;
 pub mod split_base;
 pub(crate) mod split_join;
+#[cfg(test)]
+pub(crate) mod test_utils;
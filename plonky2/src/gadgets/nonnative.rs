@@ -3,25 +3,87 @@ use std::marker::PhantomData;
 use num::{BigUint, Integer, One, Zero};
 use plonky2_field::field_types::PrimeField;
 use plonky2_field::{extension_field::Extendable, field_types::Field};
-use plonky2_util::ceil_div_usize;
+use plonky2_util::{ceil_div_usize, log2_ceil};
 
 use crate::gadgets::arithmetic_u32::U32Target;
 use crate::gadgets::biguint::BigUintTarget;
 use crate::hash::hash_types::RichField;
+use crate::hash::poseidon::PoseidonHash;
 use crate::iop::generator::{GeneratedValues, SimpleGenerator};
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::witness::{PartitionWitness, Witness};
 use crate::plonk::circuit_builder::CircuitBuilder;
 
+/// An element of the field `FF`, represented by `LIMB_BITS`-bit limbs. Limbs are 32 bits wide
+/// unless chosen otherwise; 16-bit limbs keep the columns of a product small enough to be summed
+/// natively, avoiding the 32-bit range checks and carries of `U32ArithmeticGate`s. Only some
+/// operations, such as `add_nonnative`, `sub_nonnative` and `mul_nonnative`, support both widths.
+///
+/// The representation is not necessarily canonical: limbs reconstructed from serialized data, or
+/// produced by some arithmetic, may encode a value at or above `|FF|`, or carry extra limbs. Such
+/// targets should be passed through `normalize_nonnative` before being hashed or compared.
 #[derive(Clone, Debug)]
-pub struct NonNativeTarget<FF: Field> {
+pub struct NonNativeTarget<FF: Field, const LIMB_BITS: usize = 32> {
     pub(crate) value: BigUintTarget,
     pub(crate) _phantom: PhantomData<FF>,
 }
 
+/// Data about a modulus which nonnative reductions use repeatedly: its 32-bit limbs and bit length,
+/// along with the Barrett and Montgomery constants. It is computed once per modulus, rather than
+/// for every operation.
+#[derive(Clone, Debug)]
+pub struct ModulusContext {
+    pub modulus: BigUint,
+    /// The 32-bit limbs of the modulus, least significant first.
+    pub limbs: Vec<u32>,
+    pub bits: usize,
+    /// The Barrett constant `floor(2^(64 n) / modulus)`, for `n` the number of limbs.
+    pub barrett_mu: BigUint,
+    /// The Montgomery radix `R = 2^(32 n)`.
+    pub montgomery_radix: BigUint,
+    /// `R^2 mod modulus`, which maps values to Montgomery form with a single Montgomery product.
+    pub montgomery_radix_squared: BigUint,
+}
+
+impl ModulusContext {
+    pub fn new(modulus: BigUint) -> Self {
+        assert!(!modulus.is_zero(), "The modulus must be nonzero");
+        let limbs = modulus.to_u32_digits();
+        let bits = modulus.bits() as usize;
+        let montgomery_radix = BigUint::one() << (32 * limbs.len());
+        let barrett_mu = montgomery_radix.pow(2) / &modulus;
+        let montgomery_radix_squared = montgomery_radix.pow(2) % &modulus;
+        Self {
+            modulus,
+            limbs,
+            bits,
+            barrett_mu,
+            montgomery_radix,
+            montgomery_radix_squared,
+        }
+    }
+
+    /// Returns the context of the order of `FF`.
+    pub fn for_field<FF: Field>() -> Self {
+        Self::new(FF::order())
+    }
+
+    pub fn num_limbs(&self) -> usize {
+        self.limbs.len()
+    }
+}
+
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     fn num_nonnative_limbs<FF: Field>() -> usize {
-        ceil_div_usize(FF::BITS, 32)
+        Self::num_nonnative_limbs_with_limb_bits::<FF, 32>()
+    }
+
+    fn num_nonnative_limbs_with_limb_bits<FF: Field, const LIMB_BITS: usize>() -> usize {
+        assert!(
+            LIMB_BITS == 16 || LIMB_BITS == 32,
+            "Nonnative limbs must be 16 or 32 bits wide"
+        );
+        ceil_div_usize(FF::BITS, LIMB_BITS)
     }
 
     pub fn biguint_to_nonnative<FF: Field>(&mut self, x: &BigUintTarget) -> NonNativeTarget<FF> {
@@ -36,25 +98,59 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 
     pub fn constant_nonnative<FF: PrimeField>(&mut self, x: FF) -> NonNativeTarget<FF> {
-        let x_biguint = self.constant_biguint(&x.to_canonical_biguint());
-        self.biguint_to_nonnative(&x_biguint)
+        self.constant_nonnative_with_limb_bits(x)
+    }
+
+    pub fn constant_nonnative_with_limb_bits<FF: PrimeField, const LIMB_BITS: usize>(
+        &mut self,
+        x: FF,
+    ) -> NonNativeTarget<FF, LIMB_BITS> {
+        NonNativeTarget {
+            value: self.constant_limbs::<LIMB_BITS>(&x.to_canonical_biguint()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Like `constant_biguint`, but splitting `value` into `LIMB_BITS`-bit limbs.
+    fn constant_limbs<const LIMB_BITS: usize>(&mut self, value: &BigUint) -> BigUintTarget {
+        let limbs = biguint_to_limbs(value, LIMB_BITS)
+            .into_iter()
+            .map(|l| self.constant_u32(l))
+            .collect();
+        BigUintTarget { limbs }
     }
 
     pub fn zero_nonnative<FF: PrimeField>(&mut self) -> NonNativeTarget<FF> {
         self.constant_nonnative(FF::ZERO)
     }
 
+    /// Returns a constant parsed from a hex literal, with or without a `0x` prefix, reduced modulo
+    /// `|FF|`. This is convenient for embedding constants such as curve parameters as they are
+    /// usually written.
+    pub fn nonnative_from_hex<FF: PrimeField>(&mut self, hex: &str) -> NonNativeTarget<FF> {
+        let digits = hex.strip_prefix("0x").unwrap_or(hex);
+        let value = BigUint::parse_bytes(digits.as_bytes(), 16)
+            .unwrap_or_else(|| panic!("Invalid hex literal {}", hex));
+        self.constant_nonnative(FF::from_biguint(value % FF::order()))
+    }
+
     // Assert that two NonNativeTarget's, both assumed to be in reduced form, are equal.
-    pub fn connect_nonnative<FF: Field>(
+    pub fn connect_nonnative<FF: Field, const LIMB_BITS: usize>(
         &mut self,
-        lhs: &NonNativeTarget<FF>,
-        rhs: &NonNativeTarget<FF>,
+        lhs: &NonNativeTarget<FF, LIMB_BITS>,
+        rhs: &NonNativeTarget<FF, LIMB_BITS>,
     ) {
         self.connect_biguint(&lhs.value, &rhs.value);
     }
 
     pub fn add_virtual_nonnative_target<FF: Field>(&mut self) -> NonNativeTarget<FF> {
-        let num_limbs = Self::num_nonnative_limbs::<FF>();
+        self.add_virtual_nonnative_target_with_limb_bits()
+    }
+
+    pub fn add_virtual_nonnative_target_with_limb_bits<FF: Field, const LIMB_BITS: usize>(
+        &mut self,
+    ) -> NonNativeTarget<FF, LIMB_BITS> {
+        let num_limbs = Self::num_nonnative_limbs_with_limb_bits::<FF, LIMB_BITS>();
         let value = self.add_virtual_biguint_target(num_limbs);
 
         NonNativeTarget {
@@ -63,15 +159,85 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 
-    pub fn add_nonnative<FF: PrimeField>(
+    /// Introduces a witness for `value mod |FF|`. Its limbs are range-checked, and it is
+    /// constrained to be reduced.
+    pub fn witness_nonnative<FF: PrimeField>(&mut self, value: BigUint) -> NonNativeTarget<FF> {
+        self.witness_nonnative_with_limb_bits(value)
+    }
+
+    pub fn witness_nonnative_with_limb_bits<FF: PrimeField, const LIMB_BITS: usize>(
         &mut self,
-        a: &NonNativeTarget<FF>,
-        b: &NonNativeTarget<FF>,
-    ) -> NonNativeTarget<FF> {
-        let sum = self.add_virtual_nonnative_target::<FF>();
+        value: BigUint,
+    ) -> NonNativeTarget<FF, LIMB_BITS> {
+        let x = self.add_virtual_nonnative_target_with_limb_bits::<FF, LIMB_BITS>();
+        self.add_simple_generator(NonNativeWitnessGenerator::<F, D, FF, LIMB_BITS> {
+            value: FF::from_biguint(value.mod_floor(&FF::order())),
+            x: x.clone(),
+            _phantom: PhantomData,
+        });
+
+        self.range_check_nonnative_limbs::<LIMB_BITS>(&x.value.limbs);
+        self.assert_nonnative_lt(&x, &FF::order());
+
+        x
+    }
+
+    fn range_check_nonnative_limbs<const LIMB_BITS: usize>(&mut self, limbs: &[U32Target]) {
+        if LIMB_BITS == 32 {
+            self.range_check_u32(limbs.to_vec());
+        } else {
+            for limb in limbs {
+                self.range_check(limb.0, LIMB_BITS);
+            }
+        }
+    }
+
+    /// Asserts that `sum_k columns[k] * 2^(LIMB_BITS * k) == 0` over the integers, where each
+    /// column is a signed combination of limbs. The carry out of a column is its sum with the
+    /// incoming carry, divided by `2^LIMB_BITS`; range-checking it to `carry_bits` bits plus a sign
+    /// ensures that the division was exact. `carry_bits` must bound the carries of an honest
+    /// witness, and be small enough that columns and carries cannot wrap around in `F`.
+    fn assert_limb_columns_zero<const LIMB_BITS: usize>(
+        &mut self,
+        columns: &[Target],
+        carry_bits: usize,
+    ) {
+        let base_inv = F::from_canonical_u64(1 << LIMB_BITS).inverse();
+        let carry_offset = F::from_canonical_u64(1 << carry_bits);
+        let (&last_column, columns) = columns.split_last().unwrap();
+        let mut carry = self.zero();
+        for &column in columns {
+            let total = self.add(column, carry);
+            carry = self.mul_const(base_inv, total);
+            let offset_carry = self.add_const(carry, carry_offset);
+            self.range_check(offset_carry, carry_bits + 1);
+        }
+        let total = self.add(last_column, carry);
+        self.assert_zero(total);
+    }
+
+    /// Returns `a + cond * modulus`, i.e. `a` with `modulus` added to its limbs when `cond` is
+    /// set. Checking `a + cond * modulus` against a witnessed value, rather than subtracting the
+    /// modulus from one, keeps the check free of underflow.
+    pub fn cond_add_modulus(
+        &mut self,
+        a: &BigUintTarget,
+        cond: BoolTarget,
+        modulus: &BigUintTarget,
+    ) -> BigUintTarget {
+        let mod_times_cond = self.mul_biguint_by_bool(modulus, cond);
+        self.add_biguint(a, &mod_times_cond)
+    }
+
+    pub fn add_nonnative<FF: PrimeField, const LIMB_BITS: usize>(
+        &mut self,
+        a: &NonNativeTarget<FF, LIMB_BITS>,
+        b: &NonNativeTarget<FF, LIMB_BITS>,
+    ) -> NonNativeTarget<FF, LIMB_BITS> {
+        let sum = self.add_virtual_nonnative_target_with_limb_bits::<FF, LIMB_BITS>();
         let overflow = self.add_virtual_bool_target();
 
-        self.add_simple_generator(NonNativeAdditionGenerator::<F, D, FF> {
+        self.add_simple_generator(NonNativeAdditionGenerator::<F, D, FF, LIMB_BITS> {
             a: a.clone(),
             b: b.clone(),
             sum: sum.clone(),
@@ -79,11 +245,35 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             _phantom: PhantomData,
         });
 
+        if LIMB_BITS != 32 {
+            // Check `a + b - sum - overflow * modulus == 0`. Each column is a sum of limbs with at
+            // most two of either sign, so carries are in `{-1, 0, 1}`.
+            self.range_check_nonnative_limbs::<LIMB_BITS>(&sum.value.limbs);
+            self.assert_bool(overflow);
+            let modulus = biguint_to_limbs(&FF::order(), LIMB_BITS);
+            let zero = self.zero();
+            let num_columns = a
+                .value
+                .num_limbs()
+                .max(b.value.num_limbs())
+                .max(modulus.len());
+            let columns: Vec<_> = (0..num_columns)
+                .map(|i| {
+                    let a_plus_b = self.add(limb_or(&a.value, i, zero), limb_or(&b.value, i, zero));
+                    let diff = self.sub(a_plus_b, limb_or(&sum.value, i, zero));
+                    let modulus_limb = F::from_canonical_u32(modulus.get(i).copied().unwrap_or(0));
+                    self.mul_const_add(-modulus_limb, overflow.target, diff)
+                })
+                .collect();
+            self.assert_limb_columns_zero::<LIMB_BITS>(&columns, 1);
+            self.assert_nonnative_lt(&sum, &FF::order());
+            return sum;
+        }
+
         let sum_expected = self.add_biguint(&a.value, &b.value);
 
         let modulus = self.constant_biguint(&FF::order());
-        let mod_times_overflow = self.mul_biguint_by_bool(&modulus, overflow);
-        let sum_actual = self.add_biguint(&sum.value, &mod_times_overflow);
+        let sum_actual = self.cond_add_modulus(&sum.value, overflow, &modulus);
         self.connect_biguint(&sum_expected, &sum_actual);
 
         // Range-check result.
@@ -95,6 +285,110 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         sum
     }
 
+    /// Returns `2 * a`. Each limb is shifted left by one bit, carrying the top bit into the next
+    /// limb, and the result is reduced once, which suffices since `2 * a < 2 * modulus`.
+    pub fn double_nonnative<FF: PrimeField>(
+        &mut self,
+        a: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let doubled = self.add_virtual_nonnative_target::<FF>();
+        let overflow = self.add_virtual_bool_target();
+
+        self.add_simple_generator(NonNativeAdditionGenerator::<F, D, FF, 32> {
+            a: a.clone(),
+            b: a.clone(),
+            sum: doubled.clone(),
+            overflow,
+            _phantom: PhantomData,
+        });
+
+        let two = self.constant_u32(2);
+        let mut carry = self.zero_u32();
+        let mut shifted_limbs = Vec::with_capacity(a.value.num_limbs() + 1);
+        for &limb in &a.value.limbs {
+            let (shifted_limb, new_carry) = self.mul_add_u32(limb, two, carry);
+            shifted_limbs.push(shifted_limb);
+            carry = new_carry;
+        }
+        shifted_limbs.push(carry);
+        let doubled_expected = BigUintTarget {
+            limbs: shifted_limbs,
+        };
+
+        let modulus = self.constant_biguint(&FF::order());
+        let doubled_actual = self.cond_add_modulus(&doubled.value, overflow, &modulus);
+        self.connect_biguint(&doubled_expected, &doubled_actual);
+
+        // Range-check result.
+        let cmp = self.cmp_biguint(&doubled.value, &modulus);
+        let one = self.one();
+        self.connect(cmp.target, one);
+
+        doubled
+    }
+
+    /// Returns `a / 2`, i.e. `a * 2^{-1}`, without a full inversion. Mirroring field halving, an
+    /// even `a` is shifted right, and an odd `a` has the (odd) modulus added first. We witness the
+    /// result and the parity of `a`, and check `2 * half = a + odd * |FF|`, doubling `half` with
+    /// the same limb shift as `double_nonnative`. `a` is assumed to be reduced.
+    pub fn half_nonnative<FF: PrimeField>(
+        &mut self,
+        a: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let half = self.add_virtual_nonnative_target::<FF>();
+        let odd = self.add_virtual_bool_target();
+
+        self.add_simple_generator(NonNativeHalvingGenerator::<F, D, FF> {
+            a: a.clone(),
+            half: half.clone(),
+            odd,
+            _phantom: PhantomData,
+        });
+
+        let two = self.constant_u32(2);
+        let mut carry = self.zero_u32();
+        let mut shifted_limbs = Vec::with_capacity(half.value.num_limbs() + 1);
+        for &limb in &half.value.limbs {
+            let (shifted_limb, new_carry) = self.mul_add_u32(limb, two, carry);
+            shifted_limbs.push(shifted_limb);
+            carry = new_carry;
+        }
+        shifted_limbs.push(carry);
+        let doubled_half = BigUintTarget {
+            limbs: shifted_limbs,
+        };
+
+        let modulus = self.constant_biguint(&FF::order());
+        let a_plus_mod = self.cond_add_modulus(&a.value, odd, &modulus);
+        self.connect_biguint(&doubled_half, &a_plus_mod);
+
+        // Range-check result.
+        let cmp = self.cmp_biguint(&half.value, &modulus);
+        let one = self.one();
+        self.connect(cmp.target, one);
+
+        half
+    }
+
+    /// Asserts that `a < bound`, where `bound` is a constant. This subtracts the limbs of `bound`
+    /// from those of `a` using a borrow chain, and requires the final borrow to be set. `a` is
+    /// assumed to have range-checked limbs.
+    pub fn assert_nonnative_lt<FF: Field, const LIMB_BITS: usize>(
+        &mut self,
+        a: &NonNativeTarget<FF, LIMB_BITS>,
+        bound: &BigUint,
+    ) {
+        let bound_target = self.constant_limbs::<LIMB_BITS>(bound);
+        let (a, bound_target) = self.pad_biguints(&a.value, &bound_target);
+
+        let mut borrow = self.zero_u32();
+        for i in 0..a.num_limbs() {
+            let (_, new_borrow) = self.sub_u32(a.get_limb(i), bound_target.get_limb(i), borrow);
+            borrow = new_borrow;
+        }
+        self.assert_one(borrow.0);
+    }
+
     pub fn mul_nonnative_by_bool<FF: Field>(
         &mut self,
         a: &NonNativeTarget<FF>,
@@ -150,17 +444,75 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 
     // Subtract two `NonNativeTarget`s.
-    pub fn sub_nonnative<FF: PrimeField>(
+    pub fn sub_nonnative<FF: PrimeField, const LIMB_BITS: usize>(
+        &mut self,
+        a: &NonNativeTarget<FF, LIMB_BITS>,
+        b: &NonNativeTarget<FF, LIMB_BITS>,
+    ) -> NonNativeTarget<FF, LIMB_BITS> {
+        let diff = self.add_virtual_nonnative_target_with_limb_bits::<FF, LIMB_BITS>();
+        let overflow = self.add_virtual_bool_target();
+
+        self.add_simple_generator(NonNativeSubtractionGenerator::<F, D, FF, LIMB_BITS> {
+            a: a.clone(),
+            b: b.clone(),
+            diff: diff.clone(),
+            overflow,
+            _phantom: PhantomData,
+        });
+
+        self.range_check_nonnative_limbs::<LIMB_BITS>(&diff.value.limbs);
+        self.assert_bool(overflow);
+
+        if LIMB_BITS != 32 {
+            // Check `a + overflow * modulus - b - diff == 0`, column by column as in
+            // `add_nonnative`.
+            let modulus = biguint_to_limbs(&FF::order(), LIMB_BITS);
+            let zero = self.zero();
+            let num_columns = a
+                .value
+                .num_limbs()
+                .max(b.value.num_limbs())
+                .max(modulus.len());
+            let columns: Vec<_> = (0..num_columns)
+                .map(|i| {
+                    let a_minus_b =
+                        self.sub(limb_or(&a.value, i, zero), limb_or(&b.value, i, zero));
+                    let diff = self.sub(a_minus_b, limb_or(&diff.value, i, zero));
+                    let modulus_limb = F::from_canonical_u32(modulus.get(i).copied().unwrap_or(0));
+                    self.mul_const_add(modulus_limb, overflow.target, diff)
+                })
+                .collect();
+            self.assert_limb_columns_zero::<LIMB_BITS>(&columns, 1);
+            self.assert_nonnative_lt(&diff, &FF::order());
+            return diff;
+        }
+
+        let diff_plus_b = self.add_biguint(&diff.value, &b.value);
+        let modulus = self.constant_biguint(&FF::order());
+        let a_plus_mod = self.cond_add_modulus(&a.value, overflow, &modulus);
+        self.connect_biguint(&a_plus_mod, &diff_plus_b);
+
+        diff
+    }
+
+    /// Returns `a - c` for a constant `c`, whose limbs are baked into the circuit as constants
+    /// rather than taken from a `NonNativeTarget`.
+    pub fn sub_nonnative_const<FF: PrimeField>(
         &mut self,
         a: &NonNativeTarget<FF>,
-        b: &NonNativeTarget<FF>,
+        c: BigUint,
     ) -> NonNativeTarget<FF> {
+        let c = c % FF::order();
+        if c.is_zero() {
+            return a.clone();
+        }
+
         let diff = self.add_virtual_nonnative_target::<FF>();
         let overflow = self.add_virtual_bool_target();
 
-        self.add_simple_generator(NonNativeSubtractionGenerator::<F, D, FF> {
+        self.add_simple_generator(NonNativeConstSubtractionGenerator::<F, D, FF> {
             a: a.clone(),
-            b: b.clone(),
+            c: c.clone(),
             diff: diff.clone(),
             overflow,
             _phantom: PhantomData,
@@ -169,105 +521,163 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         self.range_check_u32(diff.value.limbs.clone());
         self.assert_bool(overflow);
 
-        let diff_plus_b = self.add_biguint(&diff.value, &b.value);
+        let c_target = self.constant_biguint(&c);
+        let diff_plus_c = self.add_biguint(&diff.value, &c_target);
         let modulus = self.constant_biguint(&FF::order());
-        let mod_times_overflow = self.mul_biguint_by_bool(&modulus, overflow);
-        let diff_plus_b_reduced = self.sub_biguint(&diff_plus_b, &mod_times_overflow);
-        self.connect_biguint(&a.value, &diff_plus_b_reduced);
+        let a_plus_mod = self.cond_add_modulus(&a.value, overflow, &modulus);
+        self.connect_biguint(&a_plus_mod, &diff_plus_c);
 
         diff
     }
 
-    pub fn mul_nonnative<FF: PrimeField>(
+    /// Returns `(a / b, a mod b)`, dividing the values of `a` and `b` as integers rather than as
+    /// elements of `FF`. This constrains `a == q * b + r` and `r < b`, so it cannot be satisfied
+    /// when `b` is zero.
+    pub fn divmod_nonnative<FF: PrimeField>(
         &mut self,
         a: &NonNativeTarget<FF>,
         b: &NonNativeTarget<FF>,
-    ) -> NonNativeTarget<FF> {
-        let prod = self.add_virtual_nonnative_target::<FF>();
-        let modulus = self.constant_biguint(&FF::order());
-        let overflow = self.add_virtual_biguint_target(
-            a.value.num_limbs() + b.value.num_limbs() - modulus.num_limbs(),
-        );
+    ) -> (NonNativeTarget<FF>, NonNativeTarget<FF>) {
+        let quotient = self.add_virtual_nonnative_target::<FF>();
+        let remainder = self.add_virtual_nonnative_target::<FF>();
 
-        self.add_simple_generator(NonNativeMultiplicationGenerator::<F, D, FF> {
+        self.add_simple_generator(NonNativeDivRemGenerator::<F, D, FF> {
             a: a.clone(),
             b: b.clone(),
-            prod: prod.clone(),
-            overflow: overflow.clone(),
+            quotient: quotient.clone(),
+            remainder: remainder.clone(),
             _phantom: PhantomData,
         });
 
-        self.range_check_u32(prod.value.limbs.clone());
-        self.range_check_u32(overflow.limbs.clone());
+        self.range_check_u32(quotient.value.limbs.clone());
+        self.range_check_u32(remainder.value.limbs.clone());
 
-        let prod_expected = self.mul_biguint(&a.value, &b.value);
+        let quotient_times_b = self.mul_biguint(&quotient.value, &b.value);
+        let sum = self.add_biguint(&quotient_times_b, &remainder.value);
+        self.connect_biguint(&a.value, &sum);
 
-        let mod_times_overflow = self.mul_biguint(&modulus, &overflow);
-        let prod_actual = self.add_biguint(&prod.value, &mod_times_overflow);
-        self.connect_biguint(&prod_expected, &prod_actual);
+        // `cmp_biguint` is non-strict, so check that `b <= r` is false.
+        let b_le_remainder = self.cmp_biguint(&b.value, &remainder.value);
+        self.assert_zero(b_le_remainder.target);
 
-        prod
+        (quotient, remainder)
     }
 
-    pub fn mul_many_nonnative<FF: PrimeField>(
+    /// Returns `a * b`. With 32-bit limbs, the product is computed with `U32ArithmeticGate`s and
+    /// then reduced. Narrower limbs have products small enough to be summed natively, so instead
+    /// we witness the quotient and remainder and check `a * b - quotient * modulus - remainder == 0`
+    /// column by column.
+    pub fn mul_nonnative<FF: PrimeField, const LIMB_BITS: usize>(
         &mut self,
-        to_mul: &[NonNativeTarget<FF>],
-    ) -> NonNativeTarget<FF> {
-        if to_mul.len() == 1 {
-            return to_mul[0].clone();
+        a: &NonNativeTarget<FF, LIMB_BITS>,
+        b: &NonNativeTarget<FF, LIMB_BITS>,
+    ) -> NonNativeTarget<FF, LIMB_BITS> {
+        if LIMB_BITS != 32 {
+            return self.mul_nonnative_narrow(a, b);
         }
 
-        let mut accumulator = self.mul_nonnative(&to_mul[0], &to_mul[1]);
-        for i in 2..to_mul.len() {
-            accumulator = self.mul_nonnative(&accumulator, &to_mul[i]);
+        // The limbs are 32 bits wide here, so the targets can be treated as default-width ones.
+        let [a, b] = [a, b].map(|x| NonNativeTarget::<FF> {
+            value: x.value.clone(),
+            _phantom: PhantomData,
+        });
+        let product = self.mul_nonnative_with_context(&a, &b, &ModulusContext::for_field::<FF>());
+        NonNativeTarget {
+            value: product.value,
+            _phantom: PhantomData,
         }
-        accumulator
     }
 
-    pub fn neg_nonnative<FF: PrimeField>(
+    /// Like `mul_nonnative` with 32-bit limbs, but takes the modulus' context rather than deriving
+    /// it, so that circuits performing many multiplications can compute it once.
+    pub fn mul_nonnative_with_context<FF: PrimeField>(
         &mut self,
-        x: &NonNativeTarget<FF>,
+        a: &NonNativeTarget<FF>,
+        b: &NonNativeTarget<FF>,
+        modulus: &ModulusContext,
     ) -> NonNativeTarget<FF> {
-        let zero_target = self.constant_biguint(&BigUint::zero());
-        let zero_ff = self.biguint_to_nonnative(&zero_target);
+        debug_assert_eq!(modulus.modulus, FF::order());
+        let prod = self.mul_biguint(&a.value, &b.value);
+        let num_overflow_limbs = a.value.num_limbs() + b.value.num_limbs() - modulus.num_limbs();
+        let value = self.reduce_mul_result(&prod, modulus, num_overflow_limbs);
 
-        self.sub_nonnative(&zero_ff, x)
+        NonNativeTarget {
+            value,
+            _phantom: PhantomData,
+        }
     }
 
-    pub fn inv_nonnative<FF: PrimeField>(
+    fn mul_nonnative_narrow<FF: PrimeField, const LIMB_BITS: usize>(
         &mut self,
-        x: &NonNativeTarget<FF>,
-    ) -> NonNativeTarget<FF> {
-        let num_limbs = x.value.num_limbs();
-        let inv_biguint = self.add_virtual_biguint_target(num_limbs);
-        let div = self.add_virtual_biguint_target(num_limbs);
-
-        self.add_simple_generator(NonNativeInverseGenerator::<F, D, FF> {
-            x: x.clone(),
-            inv: inv_biguint.clone(),
-            div: div.clone(),
+        a: &NonNativeTarget<FF, LIMB_BITS>,
+        b: &NonNativeTarget<FF, LIMB_BITS>,
+    ) -> NonNativeTarget<FF, LIMB_BITS> {
+        let modulus = biguint_to_limbs(&FF::order(), LIMB_BITS);
+        // `a * b < 2^(LIMB_BITS * (n_a + n_b))` and `modulus >= 2^(FF::BITS - 1)` bound the quotient.
+        let product_bits = LIMB_BITS * (a.value.num_limbs() + b.value.num_limbs());
+        let num_quotient_limbs = ceil_div_usize(product_bits + 1 - FF::BITS, LIMB_BITS);
+        let quotient = self.add_virtual_biguint_target(num_quotient_limbs);
+        let remainder = self.add_virtual_nonnative_target_with_limb_bits::<FF, LIMB_BITS>();
+
+        self.add_simple_generator(NonNativeMultiplicationGenerator::<F, D, FF, LIMB_BITS> {
+            a: a.clone(),
+            b: b.clone(),
+            quotient: quotient.clone(),
+            remainder: remainder.clone(),
             _phantom: PhantomData,
         });
 
-        let product = self.mul_biguint(&x.value, &inv_biguint);
+        self.range_check_nonnative_limbs::<LIMB_BITS>(&quotient.limbs);
+        self.range_check_nonnative_limbs::<LIMB_BITS>(&remainder.value.limbs);
+
+        let num_columns = (a.value.num_limbs() + b.value.num_limbs())
+            .max(num_quotient_limbs + modulus.len())
+            .max(remainder.value.num_limbs());
+        let mut columns = vec![self.zero(); num_columns];
+        for (i, &a_limb) in a.value.limbs.iter().enumerate() {
+            for (j, &b_limb) in b.value.limbs.iter().enumerate() {
+                columns[i + j] = self.mul_add(a_limb.0, b_limb.0, columns[i + j]);
+            }
+        }
+        for (i, &quotient_limb) in quotient.limbs.iter().enumerate() {
+            for (j, &modulus_limb) in modulus.iter().enumerate() {
+                let neg_modulus_limb = -F::from_canonical_u32(modulus_limb);
+                columns[i + j] =
+                    self.mul_const_add(neg_modulus_limb, quotient_limb.0, columns[i + j]);
+            }
+        }
+        for (i, &remainder_limb) in remainder.value.limbs.iter().enumerate() {
+            columns[i] = self.sub(columns[i], remainder_limb.0);
+        }
 
-        let modulus = self.constant_biguint(&FF::order());
-        let mod_times_div = self.mul_biguint(&modulus, &div);
-        let one = self.constant_biguint(&BigUint::one());
-        let expected_product = self.add_biguint(&mod_times_div, &one);
-        self.connect_biguint(&product, &expected_product);
+        // A column has at most `num_terms` products, each below `2^(2 * LIMB_BITS)` in absolute
+        // value, which bounds the carries.
+        let num_terms = a.value.num_limbs().min(b.value.num_limbs())
+            + num_quotient_limbs.min(modulus.len())
+            + 1;
+        let carry_bits = LIMB_BITS + log2_ceil(num_terms) + 1;
+        self.assert_limb_columns_zero::<LIMB_BITS>(&columns, carry_bits);
+        self.assert_nonnative_lt(&remainder, &FF::order());
 
-        NonNativeTarget::<FF> {
-            value: inv_biguint,
-            _phantom: PhantomData,
-        }
+        remainder
     }
 
-    /// Returns `x % |FF|` as a `NonNativeTarget`.
-    fn reduce<FF: Field>(&mut self, x: &BigUintTarget) -> NonNativeTarget<FF> {
-        let modulus = FF::order();
-        let order_target = self.constant_biguint(&modulus);
-        let value = self.rem_biguint(x, &order_target);
+    /// Returns `a * b + c`, reducing the wide result only once instead of after both the
+    /// multiplication and the addition.
+    pub fn mul_add_nonnative<FF: PrimeField>(
+        &mut self,
+        a: &NonNativeTarget<FF>,
+        b: &NonNativeTarget<FF>,
+        c: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let result = self.mul_add_biguint(&a.value, &b.value, &c.value);
+        let num_overflow_limbs =
+            a.value.num_limbs() + b.value.num_limbs() - Self::num_nonnative_limbs::<FF>();
+        let value = self.reduce_mul_result(
+            &result,
+            &ModulusContext::for_field::<FF>(),
+            num_overflow_limbs,
+        );
 
         NonNativeTarget {
             value,
@@ -275,271 +685,2296 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 
-    pub fn reduce_nonnative<FF: Field>(&mut self, x: &NonNativeTarget<FF>) -> NonNativeTarget<FF> {
-        let x_biguint = self.nonnative_to_biguint(x);
-        self.reduce(&x_biguint)
+    /// Asserts that `a[i] * b[i] == c[i]` in `FF` for each `i`. Rather than reducing each product,
+    /// the equations are combined with coefficients `r_i`, independent challenges squeezed from a
+    /// hash of all the inputs, and only `sum_i r_i (a[i] * b[i] - c[i]) == 0 mod |FF|` is checked,
+    /// with a single reduction.
+    ///
+    /// If the `j`th equation is false, then for any fixed values of the other coefficients, the
+    /// check only passes if `r_j` lies in a single residue class mod `|FF|`, since `|FF|` is prime.
+    /// At most `ceil(|F| / |FF|)` of the `|F|` possible values of `r_j` do, so a false equation
+    /// passes with probability at most about `1 / min(|F|, |FF|)`, whatever the number of
+    /// equations.
+    pub fn batch_assert_mul_nonnative<FF: PrimeField>(
+        &mut self,
+        a: &[NonNativeTarget<FF>],
+        b: &[NonNativeTarget<FF>],
+        c: &[NonNativeTarget<FF>],
+    ) {
+        assert_eq!(a.len(), b.len());
+        assert_eq!(a.len(), c.len());
+
+        let hash_inputs = a
+            .iter()
+            .chain(b)
+            .chain(c)
+            .flat_map(|x| x.value.limbs.iter().map(|l| l.0))
+            .collect();
+        let challenges = self.hash_n_to_m_no_pad::<PoseidonHash>(hash_inputs, a.len());
+
+        let modulus = ModulusContext::for_field::<FF>();
+        let mut lhs = self.zero_biguint();
+        let mut rhs = self.zero_biguint();
+        // An upper bound on `rhs`, given that each coefficient is below `2^64`.
+        let mut rhs_bound = BigUint::zero();
+        for (((a, b), c), challenge) in a.iter().zip(b).zip(c).zip(challenges) {
+            let (low, high) = self.split_low_high(challenge, 32, 64);
+            let coeff = BigUintTarget {
+                limbs: vec![U32Target(low), U32Target(high)],
+            };
+
+            let product = self.mul_biguint(&a.value, &b.value);
+            let weighted_product = self.mul_biguint(&product, &coeff);
+            lhs = self.add_biguint(&lhs, &weighted_product);
+            let weighted_c = self.mul_biguint(&c.value, &coeff);
+            rhs = self.add_biguint(&rhs, &weighted_c);
+            rhs_bound += BigUint::one() << (64 + 32 * c.value.num_limbs());
+        }
+
+        // Add a multiple of the modulus exceeding `rhs`, so that the difference is non-negative,
+        // and check that the difference reduces to zero.
+        let offset = rhs_bound.div_ceil(&modulus.modulus) * &modulus.modulus;
+        let offset = self.constant_biguint(&offset);
+        let lhs_plus_offset = self.add_biguint(&lhs, &offset);
+        let (diff, borrow) = self.sub_biguint_with_borrow(&lhs_plus_offset, &rhs);
+        self.assert_no_borrow(borrow);
+        let num_overflow_limbs = diff.num_limbs() + 1 - modulus.num_limbs();
+        let remainder = self.reduce_mul_result(&diff, &modulus, num_overflow_limbs);
+        let zero = self.zero_biguint();
+        self.connect_biguint(&remainder, &zero);
     }
 
-    pub fn bool_to_nonnative<FF: Field>(&mut self, b: &BoolTarget) -> NonNativeTarget<FF> {
-        let limbs = vec![U32Target(b.target)];
-        let value = BigUintTarget { limbs };
+    /// Converts `a`, which is assumed to be reduced, to Montgomery form, i.e. returns `a * R`
+    /// where `R = 2^(32 * n)` for `n` the number of limbs of `FF`. Chains of multiplications can
+    /// then use `mont_mul_nonnative`, converting back with `from_montgomery_nonnative` at the end.
+    pub fn to_montgomery_nonnative<FF: PrimeField>(
+        &mut self,
+        a: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let modulus = ModulusContext::for_field::<FF>();
+        let r_squared = self.constant_biguint(&modulus.montgomery_radix_squared);
+        let r_squared = self.biguint_to_nonnative(&r_squared);
+        self.mont_mul_nonnative(a, &r_squared)
+    }
 
-        NonNativeTarget {
-            value,
-            _phantom: PhantomData,
-        }
+    /// Converts `a` back from Montgomery form, i.e. returns `a * R^{-1}`.
+    pub fn from_montgomery_nonnative<FF: PrimeField>(
+        &mut self,
+        a: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let one = self.constant_nonnative(FF::ONE);
+        self.mont_mul_nonnative(a, &one)
     }
 
-    // Split a nonnative field element to bits.
-    pub fn split_nonnative_to_bits<FF: Field>(
+    /// Returns the Montgomery product `a * b * R^{-1}`, so that the product of two values in
+    /// Montgomery form is again in Montgomery form. `a` and `b` are assumed to be reduced.
+    ///
+    /// Rather than dividing `a * b` by the modulus, this witnesses `m` and `u` with
+    /// `a * b + m * |FF| == u * R`. The division by `R` only shifts limbs, and since `u < 2 |FF|`
+    /// a single conditional subtraction reduces it.
+    pub fn mont_mul_nonnative<FF: PrimeField>(
         &mut self,
-        x: &NonNativeTarget<FF>,
-    ) -> Vec<BoolTarget> {
-        let num_limbs = x.value.num_limbs();
-        let mut result = Vec::with_capacity(num_limbs * 32);
+        a: &NonNativeTarget<FF>,
+        b: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let num_limbs = Self::num_nonnative_limbs::<FF>();
+        let m = self.add_virtual_biguint_target(num_limbs + 1);
+        let u = self.add_virtual_biguint_target(num_limbs + 1);
 
-        for i in 0..num_limbs {
-            let limb = x.value.get_limb(i);
-            let bit_targets = self.split_le_base::<2>(limb.0, 32);
-            let mut bits: Vec<_> = bit_targets
-                .iter()
-                .map(|&t| BoolTarget::new_unsafe(t))
-                .collect();
+        self.add_simple_generator(NonNativeMontgomeryMulGenerator::<F, D, FF> {
+            a: a.clone(),
+            b: b.clone(),
+            m: m.clone(),
+            u: u.clone(),
+            _phantom: PhantomData,
+        });
 
-            result.append(&mut bits);
-        }
+        self.range_check_u32([m.limbs.clone(), u.limbs.clone()].concat());
 
-        result
+        let modulus = self.constant_biguint(&FF::order());
+        let prod = self.mul_biguint(&a.value, &b.value);
+        let m_times_modulus = self.mul_biguint(&m, &modulus);
+        let sum = self.add_biguint(&prod, &m_times_modulus);
+
+        let zero = self.zero_u32();
+        let u_times_r = BigUintTarget {
+            limbs: [vec![zero; num_limbs], u.limbs.clone()].concat(),
+        };
+        self.connect_biguint(&sum, &u_times_r);
+
+        let u = self.biguint_to_nonnative(&u);
+        self.clamp_nonnative(&u, 1)
     }
-}
 
-#[derive(Debug)]
-struct NonNativeAdditionGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> {
-    a: NonNativeTarget<FF>,
-    b: NonNativeTarget<FF>,
-    sum: NonNativeTarget<FF>,
-    overflow: BoolTarget,
-    _phantom: PhantomData<F>,
-}
+    /// Returns `sum_i coeffs[i] * terms[i]`. All the wide products are summed before a single
+    /// reduction, rather than reducing after each multiplication and addition.
+    pub fn lincomb_nonnative<FF: PrimeField>(
+        &mut self,
+        coeffs: &[NonNativeTarget<FF>],
+        terms: &[NonNativeTarget<FF>],
+    ) -> NonNativeTarget<FF> {
+        assert_eq!(coeffs.len(), terms.len());
+        if coeffs.is_empty() {
+            return self.zero_nonnative();
+        }
 
-impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
-    for NonNativeAdditionGenerator<F, D, FF>
-{
-    fn dependencies(&self) -> Vec<Target> {
-        self.a
-            .value
+        let mut max_product_limbs = 0;
+        let mut sum = self.zero_biguint();
+        for (coeff, term) in coeffs.iter().zip(terms) {
+            max_product_limbs =
+                max_product_limbs.max(coeff.value.num_limbs() + term.value.num_limbs());
+            let product = self.mul_biguint(&coeff.value, &term.value);
+            sum = self.add_biguint(&sum, &product);
+        }
+
+        // The sum of up to `2^32` products fits in `max_product_limbs + 1` limbs, and since the
+        // modulus has more than `32 * (num_limbs - 1)` bits, the quotient fits in
+        // `max_product_limbs + 2 - num_limbs` limbs.
+        let num_overflow_limbs =
+            (max_product_limbs + 2).saturating_sub(Self::num_nonnative_limbs::<FF>());
+        let value =
+            self.reduce_mul_result(&sum, &ModulusContext::for_field::<FF>(), num_overflow_limbs);
+
+        NonNativeTarget {
+            value,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the inner product of `coeffs` with a constant `basis`, such as the powers of a fixed
+    /// point. The basis constants are reduced on the host, and the products share a single
+    /// reduction as in `lincomb_nonnative`.
+    pub fn nonnative_dot<FF: PrimeField>(
+        &mut self,
+        coeffs: &[NonNativeTarget<FF>],
+        basis: &[BigUint],
+    ) -> NonNativeTarget<FF> {
+        assert_eq!(coeffs.len(), basis.len());
+        let modulus = FF::order();
+        let basis: Vec<_> = basis
+            .iter()
+            .map(|b| {
+                let b = self.constant_biguint(&(b % &modulus));
+                self.biguint_to_nonnative(&b)
+            })
+            .collect();
+        self.lincomb_nonnative(coeffs, &basis)
+    }
+
+    /// Returns the modulus of `context` as a constant, built from its cached limbs.
+    pub fn constant_modulus(&mut self, context: &ModulusContext) -> BigUintTarget {
+        let limbs = context
             .limbs
             .iter()
-            .cloned()
-            .chain(self.b.value.limbs.clone())
-            .map(|l| l.0)
-            .collect()
+            .map(|&l| self.constant_u32(l))
+            .collect();
+        BigUintTarget { limbs }
     }
 
-    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
-        let a = witness.get_nonnative_target(self.a.clone());
-        let b = witness.get_nonnative_target(self.b.clone());
-        let a_biguint = a.to_canonical_biguint();
-        let b_biguint = b.to_canonical_biguint();
-        let sum_biguint = a_biguint + b_biguint;
-        let modulus = FF::order();
-        let (overflow, sum_reduced) = if sum_biguint > modulus {
-            (true, sum_biguint - modulus)
+    /// Reduces `x`, the wide result of a multiplication, modulo `modulus`. The quotient, which
+    /// must fit in `num_overflow_limbs` limbs, and the remainder are witnessed.
+    ///
+    /// The remainder is range-checked limb by limb; if the bit length of `modulus` is not a
+    /// multiple of 32, its top limb is only allowed as many bits as the modulus' top limb. We
+    /// also enforce that the remainder is strictly less than `modulus`.
+    pub(crate) fn reduce_mul_result(
+        &mut self,
+        x: &BigUintTarget,
+        modulus: &ModulusContext,
+        num_overflow_limbs: usize,
+    ) -> BigUintTarget {
+        let num_limbs = modulus.num_limbs();
+        let remainder = self.add_virtual_biguint_target(num_limbs);
+        let overflow = self.add_virtual_biguint_target(num_overflow_limbs);
+
+        self.add_simple_generator(NonNativeReductionGenerator::<F, D> {
+            x: x.clone(),
+            modulus: modulus.modulus.clone(),
+            remainder: remainder.clone(),
+            overflow: overflow.clone(),
+            _phantom: PhantomData,
+        });
+
+        self.range_check_u32(remainder.limbs.clone());
+        self.range_check_u32(overflow.limbs.clone());
+        let top_limb_bits = modulus.bits % 32;
+        if top_limb_bits != 0 {
+            self.range_check(remainder.limbs[num_limbs - 1].0, top_limb_bits);
+        }
+
+        let modulus_target = self.constant_modulus(modulus);
+        let mod_times_overflow = self.mul_biguint(&modulus_target, &overflow);
+        let x_actual = self.add_biguint(&remainder, &mod_times_overflow);
+        self.connect_biguint(x, &x_actual);
+
+        // Check `remainder <= modulus - 1`.
+        let max_remainder = self.constant_biguint(&(&modulus.modulus - BigUint::one()));
+        let cmp = self.cmp_biguint(&remainder, &max_remainder);
+        self.assert_one(cmp.target);
+
+        remainder
+    }
+
+    pub fn square_nonnative<FF: PrimeField>(
+        &mut self,
+        a: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        self.mul_nonnative(a, a)
+    }
+
+    /// Returns a square root of `a`, which must be a quadratic residue and is assumed to be
+    /// reduced. The root is supplied by the prover as a hint, and constrained to be reduced and to
+    /// square to `a`. Unless `canonical` is set, either of the two roots may be returned; see
+    /// `nonnative_sign`. If it is set, the root is negated if odd, so that the even root is always
+    /// returned, e.g. for deterministic hash-to-curve.
+    pub fn sqrt_nonnative<FF: PrimeField>(
+        &mut self,
+        a: &NonNativeTarget<FF>,
+        canonical: bool,
+    ) -> NonNativeTarget<FF> {
+        let root = self.add_virtual_nonnative_target::<FF>();
+
+        self.add_simple_generator(NonNativeSqrtGenerator::<F, D, FF> {
+            a: a.clone(),
+            root: root.clone(),
+            _phantom: PhantomData,
+        });
+
+        self.range_check_u32(root.value.limbs.clone());
+        self.assert_nonnative_lt(&root, &FF::order());
+
+        let square = self.square_nonnative(&root);
+        self.connect_nonnative(&square, a);
+
+        if canonical {
+            // Exactly one of `root` and `-root` is even, unless both are zero.
+            let is_odd = self.nonnative_sign(&root);
+            let neg_root = self.neg_nonnative(&root);
+            self.select_nonnative(is_odd, &neg_root, &root)
         } else {
-            (false, sum_biguint)
+            root
+        }
+    }
+
+    /// Returns the sign of `x`, defined as its least significant bit, so that for nonzero `x`
+    /// exactly one of `x` and `-x` has sign zero. `x` is assumed to be reduced.
+    pub fn nonnative_sign<FF: Field>(&mut self, x: &NonNativeTarget<FF>) -> BoolTarget {
+        self.split_le(x.value.get_limb(0).0, 32)[0]
+    }
+
+    pub fn mul_many_nonnative<FF: PrimeField>(
+        &mut self,
+        to_mul: &[NonNativeTarget<FF>],
+    ) -> NonNativeTarget<FF> {
+        if to_mul.len() == 1 {
+            return to_mul[0].clone();
+        }
+
+        let mut accumulator = self.mul_nonnative(&to_mul[0], &to_mul[1]);
+        for i in 2..to_mul.len() {
+            accumulator = self.mul_nonnative(&accumulator, &to_mul[i]);
+        }
+        accumulator
+    }
+
+    /// Selects `x` if `b` is true, and `y` otherwise. The shorter of the two values is padded with
+    /// zero limbs.
+    pub fn select_nonnative<FF: Field>(
+        &mut self,
+        b: BoolTarget,
+        x: &NonNativeTarget<FF>,
+        y: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let num_limbs = x.value.num_limbs().max(y.value.num_limbs());
+        let zero = self.zero();
+        let get_limb = |v: &NonNativeTarget<FF>, i| {
+            if i < v.value.num_limbs() {
+                v.value.get_limb(i).0
+            } else {
+                zero
+            }
         };
 
-        out_buffer.set_biguint_target(self.sum.value.clone(), sum_reduced);
-        out_buffer.set_bool_target(self.overflow, overflow);
+        let limbs = (0..num_limbs)
+            .map(|i| U32Target(self.select(b, get_limb(x, i), get_limb(y, i))))
+            .collect();
+
+        NonNativeTarget {
+            value: BigUintTarget { limbs },
+            _phantom: PhantomData,
+        }
     }
-}
 
-#[derive(Debug)]
-struct NonNativeMultipleAddsGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField>
-{
-    summands: Vec<NonNativeTarget<FF>>,
-    sum: NonNativeTarget<FF>,
-    overflow: U32Target,
-    _phantom: PhantomData<F>,
-}
+    /// Exponentiates `base` to the power of `exponent`, given by its little-endian bits.
+    pub fn exp_nonnative<FF: PrimeField>(
+        &mut self,
+        base: &NonNativeTarget<FF>,
+        exponent_bits: &[BoolTarget],
+    ) -> NonNativeTarget<FF> {
+        let one = self.constant_nonnative(FF::ONE);
+        let (&top_bit, rest_bits) = match exponent_bits.split_last() {
+            Some(split) => split,
+            None => return one,
+        };
 
-impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
-    for NonNativeMultipleAddsGenerator<F, D, FF>
-{
-    fn dependencies(&self) -> Vec<Target> {
-        self.summands
-            .iter()
-            .flat_map(|summand| summand.value.limbs.iter().map(|limb| limb.0))
-            .collect()
+        // Selecting between `base` and one also pads one to full width, which `mul_nonnative`
+        // expects of its inputs.
+        let mut result = self.select_nonnative(top_bit, base, &one);
+        for &bit in rest_bits.iter().rev() {
+            let squared = self.square_nonnative(&result);
+            let multiplied = self.mul_nonnative(&squared, base);
+            result = self.select_nonnative(bit, &multiplied, &squared);
+        }
+        result
     }
 
-    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
-        let summands: Vec<_> = self
-            .summands
+    /// Exponentiates `base` to the power of the nonnative `exponent`. The exponent is decomposed
+    /// into all the bits of its limbs, so the size of the circuit only depends on `EF`.
+    pub fn exp_nonnative_by_nonnative<FF: PrimeField, EF: Field>(
+        &mut self,
+        base: &NonNativeTarget<FF>,
+        exponent: &NonNativeTarget<EF>,
+    ) -> NonNativeTarget<FF> {
+        let exponent_bits = self.split_nonnative_to_bits(exponent);
+        self.exp_nonnative(base, &exponent_bits)
+    }
+
+    pub fn neg_nonnative<FF: PrimeField>(
+        &mut self,
+        x: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let zero_target = self.constant_biguint(&BigUint::zero());
+        let zero_ff = self.biguint_to_nonnative(&zero_target);
+
+        self.sub_nonnative(&zero_ff, x)
+    }
+
+    /// Asserts that `x` is nonzero modulo `|FF|`, including when `x` is an unreduced multiple of
+    /// `|FF|`. This witnesses an inverse of `x`, which only exists for nonzero values.
+    pub fn assert_nonzero_nonnative<FF: PrimeField>(&mut self, x: &NonNativeTarget<FF>) {
+        self.inv_nonnative(x);
+    }
+
+    pub fn inv_nonnative<FF: PrimeField>(
+        &mut self,
+        x: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let num_limbs = x.value.num_limbs();
+        let inv_biguint = self.add_virtual_biguint_target(num_limbs);
+        let div = self.add_virtual_biguint_target(num_limbs);
+
+        self.add_simple_generator(NonNativeInverseGenerator::<F, D, FF> {
+            x: x.clone(),
+            inv: inv_biguint.clone(),
+            div: div.clone(),
+            _phantom: PhantomData,
+        });
+
+        self.assert_nonnative_inverse(x, &inv_biguint, &div)
+    }
+
+    /// Asserts that `x * inv == div * |FF| + 1`, with `inv` and `div` as witnessed by
+    /// `inv_nonnative`, and returns `inv`. The limbs of both are range-checked, as `mul_biguint`
+    /// assumes, and `inv` must be reduced.
+    fn assert_nonnative_inverse<FF: PrimeField>(
+        &mut self,
+        x: &NonNativeTarget<FF>,
+        inv_biguint: &BigUintTarget,
+        div: &BigUintTarget,
+    ) -> NonNativeTarget<FF> {
+        self.range_check_u32(inv_biguint.limbs.clone());
+        self.range_check_u32(div.limbs.clone());
+        let inv = NonNativeTarget::<FF> {
+            value: inv_biguint.clone(),
+            _phantom: PhantomData,
+        };
+        self.assert_nonnative_lt(&inv, &FF::order());
+
+        let product = self.mul_biguint(&x.value, &inv.value);
+
+        let modulus = self.constant_biguint(&FF::order());
+        let mod_times_div = self.mul_biguint(&modulus, div);
+        let one = self.constant_biguint(&BigUint::one());
+        let expected_product = self.add_biguint(&mod_times_div, &one);
+        self.connect_biguint(&product, &expected_product);
+
+        inv
+    }
+
+    /// Returns `x^{-1}` if `x` is nonzero, and zero otherwise.
+    ///
+    /// We witness `inv`, quotients `div` and `x_div`, and a flag `is_zero`, and enforce
+    /// `x * inv = |FF| * div + (1 - is_zero)`, `x * is_zero = |FF| * x_div` and `inv * is_zero = 0`.
+    /// If `x` is nonzero modulo `|FF|`, the second constraint forces `is_zero = 0`, and the first
+    /// then makes `inv` the inverse of `x`. Otherwise `x * inv` is a multiple of `|FF|`, so the first
+    /// forces `is_zero = 1`, and the third `inv = 0`.
+    pub fn inv_or_zero_nonnative<FF: PrimeField>(
+        &mut self,
+        x: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let num_limbs = x.value.num_limbs();
+        let inv_biguint = self.add_virtual_biguint_target(Self::num_nonnative_limbs::<FF>());
+        let div = self.add_virtual_biguint_target(num_limbs);
+        let x_div = self.add_virtual_biguint_target(num_limbs);
+        let is_zero = self.add_virtual_bool_target_safe();
+
+        self.add_simple_generator(NonNativeInverseOrZeroGenerator::<F, D, FF> {
+            x: x.clone(),
+            inv: inv_biguint.clone(),
+            div: div.clone(),
+            x_div: x_div.clone(),
+            is_zero,
+            _phantom: PhantomData,
+        });
+
+        self.assert_nonnative_inverse_or_zero(x, &inv_biguint, &div, &x_div, is_zero)
+    }
+
+    /// Enforces the constraints of `inv_or_zero_nonnative` on its witnessed values, and returns
+    /// `inv`. The limbs of `inv`, `div` and `x_div` are range-checked, and `inv` must be reduced.
+    fn assert_nonnative_inverse_or_zero<FF: PrimeField>(
+        &mut self,
+        x: &NonNativeTarget<FF>,
+        inv_biguint: &BigUintTarget,
+        div: &BigUintTarget,
+        x_div: &BigUintTarget,
+        is_zero: BoolTarget,
+    ) -> NonNativeTarget<FF> {
+        self.range_check_u32(inv_biguint.limbs.clone());
+        self.range_check_u32(div.limbs.clone());
+        self.range_check_u32(x_div.limbs.clone());
+        let inv = NonNativeTarget::<FF> {
+            value: inv_biguint.clone(),
+            _phantom: PhantomData,
+        };
+        self.assert_nonnative_lt(&inv, &FF::order());
+
+        let product = self.mul_biguint(&x.value, &inv.value);
+
+        let modulus = self.constant_biguint(&FF::order());
+        let mod_times_div = self.mul_biguint(&modulus, div);
+        let is_nonzero = self.not(is_zero);
+        let is_nonzero_biguint = BigUintTarget {
+            limbs: vec![U32Target(is_nonzero.target)],
+        };
+        let expected_product = self.add_biguint(&mod_times_div, &is_nonzero_biguint);
+        self.connect_biguint(&product, &expected_product);
+
+        let x_if_zero = self.mul_biguint_by_bool(&x.value, is_zero);
+        let mod_times_x_div = self.mul_biguint(&modulus, x_div);
+        self.connect_biguint(&x_if_zero, &mod_times_x_div);
+
+        for limb in &inv.value.limbs {
+            let masked_limb = self.mul(limb.0, is_zero.target);
+            self.assert_zero(masked_limb);
+        }
+
+        inv
+    }
+
+    /// Returns `x % |FF|` as a `NonNativeTarget`.
+    fn reduce<FF: Field>(&mut self, x: &BigUintTarget) -> NonNativeTarget<FF> {
+        let modulus = FF::order();
+        let order_target = self.constant_biguint(&modulus);
+        let value = self.rem_biguint(x, &order_target);
+
+        NonNativeTarget {
+            value,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn reduce_nonnative<FF: Field>(&mut self, x: &NonNativeTarget<FF>) -> NonNativeTarget<FF> {
+        let x_biguint = self.nonnative_to_biguint(x);
+        self.reduce(&x_biguint)
+    }
+
+    /// Like `reduce_nonnative`, but also returns the quotient `k`, i.e. the number of times `|FF|`
+    /// was subtracted, so that `x == k * |FF| + reduced`. The quotient is returned as is, without
+    /// being reduced itself.
+    pub fn reduce_nonnative_with_quotient<FF: Field>(
+        &mut self,
+        x: &NonNativeTarget<FF>,
+    ) -> (NonNativeTarget<FF>, NonNativeTarget<FF>) {
+        let order_target = self.constant_biguint(&FF::order());
+        let (quotient, reduced) = self.div_rem_biguint(&x.value, &order_target);
+        let reduced = self.biguint_to_nonnative(&reduced);
+        // `div_rem_biguint` allows a remainder equal to the modulus, which would leave the quotient
+        // off by one for multiples of the modulus.
+        self.assert_nonnative_lt(&reduced, &FF::order());
+
+        (reduced, self.biguint_to_nonnative(&quotient))
+    }
+
+    /// Reduces each of `xs` in place. Compared to calling `reduce_nonnative` on each, the
+    /// quotients and remainders of the whole batch are witnessed by a single generator, the
+    /// modulus constants are shared, and all limbs are range-checked together so that range-check
+    /// rows are fully packed.
+    pub fn reduce_all_nonnative<FF: Field>(&mut self, xs: &mut [NonNativeTarget<FF>]) {
+        let modulus = FF::order();
+        let num_limbs = Self::num_nonnative_limbs::<FF>();
+        let modulus_target = self.constant_biguint(&modulus);
+        let max_remainder = self.constant_biguint(&(&modulus - BigUint::one()));
+
+        // The modulus has `num_limbs` limbs, so a quotient has at most one limb more than the
+        // excess of `x` over that.
+        let quotients: Vec<_> = xs
             .iter()
-            .map(|summand| witness.get_nonnative_target(summand.clone()))
+            .map(|x| {
+                let num_quotient_limbs = x.value.num_limbs().saturating_sub(num_limbs) + 1;
+                self.add_virtual_biguint_target(num_quotient_limbs)
+            })
             .collect();
-        let summand_biguints: Vec<_> = summands
+        let remainders: Vec<_> = xs
             .iter()
-            .map(|summand| summand.to_canonical_biguint())
+            .map(|_| self.add_virtual_biguint_target(num_limbs))
             .collect();
 
-        let sum_biguint = summand_biguints
-            .iter()
-            .fold(BigUint::zero(), |a, b| a + b.clone());
+        self.add_simple_generator(NonNativeBatchReductionGenerator::<F, D> {
+            xs: xs.iter().map(|x| x.value.clone()).collect(),
+            modulus,
+            quotients: quotients.clone(),
+            remainders: remainders.clone(),
+            _phantom: PhantomData,
+        });
 
-        let modulus = FF::order();
-        let (overflow_biguint, sum_reduced) = sum_biguint.div_rem(&modulus);
-        let overflow = overflow_biguint.to_u64_digits()[0] as u32;
+        self.range_check_u32(
+            quotients
+                .iter()
+                .chain(&remainders)
+                .flat_map(|t| t.limbs.clone())
+                .collect(),
+        );
+
+        for ((x, quotient), remainder) in xs.iter_mut().zip(quotients).zip(remainders) {
+            let mod_times_quotient = self.mul_biguint(&modulus_target, &quotient);
+            let x_actual = self.add_biguint(&remainder, &mod_times_quotient);
+            self.connect_biguint(&x.value, &x_actual);
+
+            // Check `remainder <= modulus - 1`.
+            let cmp = self.cmp_biguint(&remainder, &max_remainder);
+            self.assert_one(cmp.target);
+
+            x.value = remainder;
+        }
+    }
+
+    /// Returns the canonical form of `x`, i.e. `x % |FF|` with exactly as many limbs as `|FF|`
+    /// and each limb range-checked, regardless of how `x` was produced.
+    pub fn normalize_nonnative<FF: Field>(
+        &mut self,
+        x: &NonNativeTarget<FF>,
+    ) -> NonNativeTarget<FF> {
+        let reduced = self.reduce_nonnative(x);
+        // The general reduction only enforces `rem <= |FF|`.
+        self.assert_nonnative_lt(&reduced, &FF::order());
+        reduced
+    }
+
+    /// Reduces `x`, which is assumed to be below `(max_overflow + 1) * |FF|`, by conditionally
+    /// subtracting `|FF|` up to `max_overflow` times. For a small overflow, such as after adding a
+    /// few reduced values, this is cheaper than the general reduction. The circuit is unsatisfiable
+    /// if `x` exceeds the bound.
+    pub fn clamp_nonnative<FF: Field>(
+        &mut self,
+        x: &NonNativeTarget<FF>,
+        max_overflow: usize,
+    ) -> NonNativeTarget<FF> {
+        let modulus = self.constant_biguint(&FF::order());
+        let (mut value, modulus) = self.pad_biguints(&x.value, &modulus);
+
+        for _ in 0..max_overflow {
+            // Subtract the modulus unless that borrows, i.e. unless `value` is already reduced.
+            let mut borrow = self.zero_u32();
+            let mut diff_limbs = Vec::with_capacity(value.num_limbs());
+            for i in 0..value.num_limbs() {
+                let (diff, new_borrow) =
+                    self.sub_u32(value.get_limb(i), modulus.get_limb(i), borrow);
+                diff_limbs.push(diff);
+                borrow = new_borrow;
+            }
+            let borrow = BoolTarget::new_unsafe(borrow.0);
+            let limbs = value
+                .limbs
+                .iter()
+                .zip(diff_limbs)
+                .map(|(&v, d)| U32Target(self.select(borrow, v.0, d.0)))
+                .collect();
+            value = BigUintTarget { limbs };
+        }
+
+        let mut clamped = NonNativeTarget {
+            value,
+            _phantom: PhantomData,
+        };
+        self.assert_nonnative_lt(&clamped, &FF::order());
+        // Being below the modulus, any extra limbs are zero.
+        clamped
+            .value
+            .limbs
+            .truncate(Self::num_nonnative_limbs::<FF>());
+        clamped
+    }
+
+    pub fn bool_to_nonnative<FF: Field>(&mut self, b: &BoolTarget) -> NonNativeTarget<FF> {
+        let limbs = vec![U32Target(b.target)];
+        let value = BigUintTarget { limbs };
+
+        NonNativeTarget {
+            value,
+            _phantom: PhantomData,
+        }
+    }
+
+    // Split a nonnative field element to bits.
+    pub fn split_nonnative_to_bits<FF: Field>(
+        &mut self,
+        x: &NonNativeTarget<FF>,
+    ) -> Vec<BoolTarget> {
+        let num_limbs = x.value.num_limbs();
+        let mut result = Vec::with_capacity(num_limbs * 32);
+
+        for i in 0..num_limbs {
+            let limb = x.value.get_limb(i);
+            let bit_targets = self.split_le_base::<2>(limb.0, 32);
+            let mut bits: Vec<_> = bit_targets
+                .iter()
+                .map(|&t| BoolTarget::new_unsafe(t))
+                .collect();
+
+            result.append(&mut bits);
+        }
+
+        result
+    }
+
+    /// Builds a nonnative field element from its little-endian bits, reducing the result modulo
+    /// `|FF|`. The bits are packed into 32-bit limbs, so any number of bits may be given.
+    pub fn nonnative_from_bool_slice<FF: Field>(
+        &mut self,
+        bits: &[BoolTarget],
+    ) -> NonNativeTarget<FF> {
+        let limbs = bits
+            .chunks(32)
+            .map(|chunk| U32Target(self.le_sum(chunk.iter())))
+            .collect();
+        let value = self.biguint_to_nonnative(&BigUintTarget { limbs });
+
+        self.normalize_nonnative(&value)
+    }
+
+    /// Builds a nonnative field element from its little-endian bytes, e.g. a hash output or an
+    /// encoded signature, reducing the result modulo `|FF|`. Each byte is range-checked to 8 bits,
+    /// and every four bytes are packed into a 32-bit limb, so any number of bytes may be given.
+    pub fn nonnative_from_le_bytes<FF: Field>(&mut self, bytes: &[Target]) -> NonNativeTarget<FF> {
+        let two_8 = F::from_canonical_u64(1 << 8);
+        let limbs = bytes
+            .chunks(4)
+            .map(|chunk| {
+                let mut limb = self.zero();
+                for &byte in chunk.iter().rev() {
+                    self.range_check(byte, 8);
+                    limb = self.mul_const_add(two_8, limb, byte);
+                }
+                U32Target(limb)
+            })
+            .collect();
+        let value = self.biguint_to_nonnative(&BigUintTarget { limbs });
+
+        self.normalize_nonnative(&value)
+    }
+
+    /// Packs the limbs of a nonnative field element into fewer field elements, e.g. for hashing.
+    /// Two 32-bit limbs don't fit in one element, since `2^64 > |F|`, so every three limbs are
+    /// packed into two 48-bit elements instead, splitting the middle limb in halves. Packed
+    /// elements are thus below the modulus, and the packing is injective. This is a simple packing
+    /// rather than a minimal one: a 256-bit value takes 6 elements, where 63-bit elements would
+    /// take 5.
+    pub fn pack_nonnative_for_hash<FF: Field>(&mut self, a: &NonNativeTarget<FF>) -> Vec<Target> {
+        let two_16 = self.constant(F::from_canonical_u64(1 << 16));
+        let two_32 = self.constant(F::from_canonical_u64(1 << 32));
+
+        let mut packed = Vec::with_capacity(ceil_div_usize(2 * a.value.num_limbs(), 3));
+        for chunk in a.value.limbs.chunks(3) {
+            if let [low, mid, high] = *chunk {
+                let (mid_low, mid_high) = self.split_low_high(mid.0, 16, 32);
+                packed.push(self.mul_add(mid_low, two_32, low.0));
+                packed.push(self.mul_add(high.0, two_16, mid_high));
+            } else {
+                // Up to two limbs are left over, and take up one element each.
+                packed.extend(chunk.iter().map(|limb| limb.0));
+            }
+        }
+
+        packed
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeWitnessGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    FF: PrimeField,
+    const LIMB_BITS: usize,
+> {
+    value: FF,
+    x: NonNativeTarget<FF, LIMB_BITS>,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField, const LIMB_BITS: usize>
+    SimpleGenerator<F> for NonNativeWitnessGenerator<F, D, FF, LIMB_BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        Vec::new()
+    }
+
+    fn run_once(&self, _witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        out_buffer.set_nonnative_target(self.x.clone(), self.value);
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeAdditionGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    FF: PrimeField,
+    const LIMB_BITS: usize,
+> {
+    a: NonNativeTarget<FF, LIMB_BITS>,
+    b: NonNativeTarget<FF, LIMB_BITS>,
+    sum: NonNativeTarget<FF, LIMB_BITS>,
+    overflow: BoolTarget,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField, const LIMB_BITS: usize>
+    SimpleGenerator<F> for NonNativeAdditionGenerator<F, D, FF, LIMB_BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.a
+            .value
+            .limbs
+            .iter()
+            .cloned()
+            .chain(self.b.value.limbs.clone())
+            .map(|l| l.0)
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let a = witness.get_nonnative_target(self.a.clone());
+        let b = witness.get_nonnative_target(self.b.clone());
+        let a_biguint = a.to_canonical_biguint();
+        let b_biguint = b.to_canonical_biguint();
+        let sum_biguint = a_biguint + b_biguint;
+        let modulus = FF::order();
+        let (overflow, sum_reduced) = if sum_biguint > modulus {
+            (true, sum_biguint - modulus)
+        } else {
+            (false, sum_biguint)
+        };
+
+        out_buffer.set_nonnative_target(self.sum.clone(), FF::from_biguint(sum_reduced));
+        out_buffer.set_bool_target(self.overflow, overflow);
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeHalvingGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> {
+    a: NonNativeTarget<FF>,
+    half: NonNativeTarget<FF>,
+    odd: BoolTarget,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
+    for NonNativeHalvingGenerator<F, D, FF>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.a.value.limbs.iter().map(|&l| l.0).collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let a = witness.get_biguint_target(self.a.value.clone());
+        let odd = a.is_odd();
+        let half = if odd { a + FF::order() } else { a } >> 1;
+
+        out_buffer.set_biguint_target(self.half.value.clone(), half);
+        out_buffer.set_bool_target(self.odd, odd);
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeMultipleAddsGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField>
+{
+    summands: Vec<NonNativeTarget<FF>>,
+    sum: NonNativeTarget<FF>,
+    overflow: U32Target,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
+    for NonNativeMultipleAddsGenerator<F, D, FF>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.summands
+            .iter()
+            .flat_map(|summand| summand.value.limbs.iter().map(|limb| limb.0))
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let summands: Vec<_> = self
+            .summands
+            .iter()
+            .map(|summand| witness.get_nonnative_target(summand.clone()))
+            .collect();
+        let summand_biguints: Vec<_> = summands
+            .iter()
+            .map(|summand| summand.to_canonical_biguint())
+            .collect();
+
+        let sum_biguint = summand_biguints
+            .iter()
+            .fold(BigUint::zero(), |a, b| a + b.clone());
+
+        let modulus = FF::order();
+        let (overflow_biguint, sum_reduced) = sum_biguint.div_rem(&modulus);
+        let overflow = overflow_biguint.to_u64_digits()[0] as u32;
+
+        out_buffer.set_biguint_target(self.sum.value.clone(), sum_reduced);
+        out_buffer.set_u32_target(self.overflow, overflow);
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeSubtractionGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    FF: Field,
+    const LIMB_BITS: usize,
+> {
+    a: NonNativeTarget<FF, LIMB_BITS>,
+    b: NonNativeTarget<FF, LIMB_BITS>,
+    diff: NonNativeTarget<FF, LIMB_BITS>,
+    overflow: BoolTarget,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField, const LIMB_BITS: usize>
+    SimpleGenerator<F> for NonNativeSubtractionGenerator<F, D, FF, LIMB_BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.a
+            .value
+            .limbs
+            .iter()
+            .cloned()
+            .chain(self.b.value.limbs.clone())
+            .map(|l| l.0)
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let a = witness.get_nonnative_target(self.a.clone());
+        let b = witness.get_nonnative_target(self.b.clone());
+        let a_biguint = a.to_canonical_biguint();
+        let b_biguint = b.to_canonical_biguint();
+
+        let modulus = FF::order();
+        let (diff_biguint, overflow) = if a_biguint >= b_biguint {
+            (a_biguint - b_biguint, false)
+        } else {
+            (modulus + a_biguint - b_biguint, true)
+        };
+
+        out_buffer.set_nonnative_target(self.diff.clone(), FF::from_biguint(diff_biguint));
+        out_buffer.set_bool_target(self.overflow, overflow);
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeMultiplicationGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    FF: Field,
+    const LIMB_BITS: usize,
+> {
+    a: NonNativeTarget<FF, LIMB_BITS>,
+    b: NonNativeTarget<FF, LIMB_BITS>,
+    quotient: BigUintTarget,
+    remainder: NonNativeTarget<FF, LIMB_BITS>,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField, const LIMB_BITS: usize>
+    SimpleGenerator<F> for NonNativeMultiplicationGenerator<F, D, FF, LIMB_BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.a
+            .value
+            .limbs
+            .iter()
+            .chain(&self.b.value.limbs)
+            .map(|&l| l.0)
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let a = limbs_to_biguint(witness, &self.a.value, LIMB_BITS);
+        let b = limbs_to_biguint(witness, &self.b.value, LIMB_BITS);
+        let (quotient, remainder) = (a * b).div_rem(&FF::order());
+
+        let mut quotient_limbs = biguint_to_limbs(&quotient, LIMB_BITS);
+        assert!(quotient_limbs.len() <= self.quotient.num_limbs());
+        quotient_limbs.resize(self.quotient.num_limbs(), 0);
+        for (&target, limb) in self.quotient.limbs.iter().zip(quotient_limbs) {
+            out_buffer.set_u32_target(target, limb);
+        }
+        out_buffer.set_nonnative_target(self.remainder.clone(), FF::from_biguint(remainder));
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeConstSubtractionGenerator<F: RichField + Extendable<D>, const D: usize, FF: Field> {
+    a: NonNativeTarget<FF>,
+    c: BigUint,
+    diff: NonNativeTarget<FF>,
+    overflow: BoolTarget,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
+    for NonNativeConstSubtractionGenerator<F, D, FF>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.a.value.limbs.iter().map(|&l| l.0).collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let a = witness.get_nonnative_target(self.a.clone());
+        let a_biguint = a.to_canonical_biguint();
+
+        let modulus = FF::order();
+        let (diff_biguint, overflow) = if a_biguint >= self.c {
+            (a_biguint - &self.c, false)
+        } else {
+            (modulus + a_biguint - &self.c, true)
+        };
+
+        out_buffer.set_biguint_target(self.diff.value.clone(), diff_biguint);
+        out_buffer.set_bool_target(self.overflow, overflow);
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeDivRemGenerator<F: RichField + Extendable<D>, const D: usize, FF: Field> {
+    a: NonNativeTarget<FF>,
+    b: NonNativeTarget<FF>,
+    quotient: NonNativeTarget<FF>,
+    remainder: NonNativeTarget<FF>,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
+    for NonNativeDivRemGenerator<F, D, FF>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.a
+            .value
+            .limbs
+            .iter()
+            .chain(&self.b.value.limbs)
+            .map(|&l| l.0)
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let a = witness.get_biguint_target(self.a.value.clone());
+        let b = witness.get_biguint_target(self.b.value.clone());
+        let (quotient, remainder) = a.div_rem(&b);
+
+        out_buffer.set_biguint_target(self.quotient.value.clone(), quotient);
+        out_buffer.set_biguint_target(self.remainder.value.clone(), remainder);
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeBatchReductionGenerator<F: RichField + Extendable<D>, const D: usize> {
+    xs: Vec<BigUintTarget>,
+    modulus: BigUint,
+    quotients: Vec<BigUintTarget>,
+    remainders: Vec<BigUintTarget>,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
+    for NonNativeBatchReductionGenerator<F, D>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.xs
+            .iter()
+            .flat_map(|x| x.limbs.iter().map(|&l| l.0))
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        for ((x, quotient), remainder) in self.xs.iter().zip(&self.quotients).zip(&self.remainders)
+        {
+            let x = witness.get_biguint_target(x.clone());
+            let (quotient_value, remainder_value) = x.div_rem(&self.modulus);
+
+            out_buffer.set_biguint_target(quotient.clone(), quotient_value);
+            out_buffer.set_biguint_target(remainder.clone(), remainder_value);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeReductionGenerator<F: RichField + Extendable<D>, const D: usize> {
+    x: BigUintTarget,
+    modulus: BigUint,
+    remainder: BigUintTarget,
+    overflow: BigUintTarget,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
+    for NonNativeReductionGenerator<F, D>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.x.limbs.iter().map(|&l| l.0).collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let x = witness.get_biguint_target(self.x.clone());
+        let (overflow, remainder) = x.div_rem(&self.modulus);
+
+        out_buffer.set_biguint_target(self.remainder.clone(), remainder);
+        out_buffer.set_biguint_target(self.overflow.clone(), overflow);
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeInverseGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> {
+    x: NonNativeTarget<FF>,
+    inv: BigUintTarget,
+    div: BigUintTarget,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
+    for NonNativeInverseGenerator<F, D, FF>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.x.value.limbs.iter().map(|&l| l.0).collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        // `x` may be unreduced, so the quotient is computed from its actual value.
+        let x_biguint = witness.get_biguint_target(self.x.value.clone());
+        let inv = FF::from_biguint(x_biguint.clone()).inverse();
+
+        let inv_biguint = inv.to_canonical_biguint();
+        let prod = x_biguint * &inv_biguint;
+        let modulus = FF::order();
+        let (div, _rem) = prod.div_rem(&modulus);
+
+        out_buffer.set_biguint_target(self.div.clone(), div);
+        out_buffer.set_biguint_target(self.inv.clone(), inv_biguint);
+    }
+}
+
+#[derive(Debug)]
+struct NonNativeInverseOrZeroGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField>
+{
+    x: NonNativeTarget<FF>,
+    inv: BigUintTarget,
+    div: BigUintTarget,
+    x_div: BigUintTarget,
+    is_zero: BoolTarget,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
+    for NonNativeInverseOrZeroGenerator<F, D, FF>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.x.value.limbs.iter().map(|&l| l.0).collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let x_biguint = witness.get_biguint_target(self.x.value.clone());
+        let x = FF::from_biguint(x_biguint.clone());
+        let inv = x.try_inverse().unwrap_or(FF::ZERO);
+
+        let inv_biguint = inv.to_canonical_biguint();
+        let prod = &x_biguint * &inv_biguint;
+        let modulus = FF::order();
+        let (div, _rem) = prod.div_rem(&modulus);
+        let x_div = if x.is_zero() {
+            x_biguint / &modulus
+        } else {
+            BigUint::zero()
+        };
+
+        out_buffer.set_biguint_target(self.div.clone(), div);
+        out_buffer.set_biguint_target(self.x_div.clone(), x_div);
+        out_buffer.set_biguint_target(self.inv.clone(), inv_biguint);
+        out_buffer.set_bool_target(self.is_zero, x.is_zero());
+    }
+}
+
+/// Computes a square root of `x` using the Tonelli-Shanks algorithm, or returns `None` if `x` is
+/// not a quadratic residue.
+fn sqrt<FF: PrimeField>(x: FF) -> Option<FF> {
+    if x.is_zero() {
+        return Some(x);
+    }
+
+    // Write `p - 1 = q * 2^s` with `q` odd.
+    let s = FF::TWO_ADICITY;
+    let q = (FF::order() - BigUint::one()) >> s;
+
+    let mut m = s;
+    let mut c = FF::POWER_OF_TWO_GENERATOR;
+    let mut t = x.exp_biguint(&q);
+    let mut r = x.exp_biguint(&((q + BigUint::one()) >> 1usize));
+    while !t.is_one() {
+        // Find the least `i` such that `t^(2^i) = 1`.
+        let mut i = 0;
+        let mut t_pow = t;
+        while !t_pow.is_one() {
+            t_pow = t_pow.square();
+            i += 1;
+            if i == m {
+                return None;
+            }
+        }
+
+        let b = c.exp_power_of_2(m - i - 1);
+        m = i;
+        c = b.square();
+        t *= c;
+        r *= b;
+    }
+    Some(r)
+}
+
+#[derive(Debug)]
+struct NonNativeSqrtGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> {
+    a: NonNativeTarget<FF>,
+    root: NonNativeTarget<FF>,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
+    for NonNativeSqrtGenerator<F, D, FF>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.a.value.limbs.iter().map(|&l| l.0).collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let a = witness.get_nonnative_target(self.a.clone());
+        let root = sqrt(a).expect("Input is not a quadratic residue");
+
+        out_buffer.set_biguint_target(self.root.value.clone(), root.to_canonical_biguint());
+    }
+}
+
+/// Splits `x` into `limb_bits`-bit limbs, least significant first, with no leading zero limbs.
+pub(crate) fn biguint_to_limbs(x: &BigUint, limb_bits: usize) -> Vec<u32> {
+    let mask = BigUint::from(u32::MAX >> (32 - limb_bits));
+    let num_limbs = ceil_div_usize(x.bits() as usize, limb_bits);
+    (0..num_limbs)
+        .map(|i| {
+            let limb = (x >> (limb_bits * i)) & &mask;
+            limb.to_u32_digits().first().copied().unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Reads the value of `x`, whose limbs are `limb_bits` bits wide.
+pub(crate) fn limbs_to_biguint<F: PrimeField>(
+    witness: &impl Witness<F>,
+    x: &BigUintTarget,
+    limb_bits: usize,
+) -> BigUint {
+    x.limbs.iter().rev().fold(BigUint::zero(), |acc, limb| {
+        (acc << limb_bits) + witness.get_target(limb.0).to_canonical_biguint()
+    })
+}
+
+fn limb_or(x: &BigUintTarget, i: usize, zero: Target) -> Target {
+    x.limbs.get(i).map_or(zero, |l| l.0)
+}
+
+/// Returns `R^{-1} mod p` for the Montgomery radix `R`, given a context whose modulus `p` is prime.
+fn montgomery_radix_inv(modulus: &ModulusContext) -> BigUint {
+    let exponent = &modulus.modulus - BigUint::from(2u32);
+    modulus.montgomery_radix.modpow(&exponent, &modulus.modulus)
+}
+
+#[derive(Debug)]
+struct NonNativeMontgomeryMulGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField>
+{
+    a: NonNativeTarget<FF>,
+    b: NonNativeTarget<FF>,
+    m: BigUintTarget,
+    u: BigUintTarget,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
+    for NonNativeMontgomeryMulGenerator<F, D, FF>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        self.a
+            .value
+            .limbs
+            .iter()
+            .chain(&self.b.value.limbs)
+            .map(|&l| l.0)
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let a = witness.get_biguint_target(self.a.value.clone());
+        let b = witness.get_biguint_target(self.b.value.clone());
+        let context = ModulusContext::for_field::<FF>();
+        let modulus = &context.modulus;
+        let radix = &context.montgomery_radix;
+
+        let prod = a * b;
+        let reduced = &prod % modulus * montgomery_radix_inv(&context) % modulus;
+        // `u` must satisfy `u * R >= a * b` for `m` to be non-negative, which `reduced + |FF|`
+        // always does.
+        let u = if &reduced * radix >= prod {
+            reduced
+        } else {
+            reduced + modulus
+        };
+        let m = (&u * radix - prod) / modulus;
+
+        out_buffer.set_biguint_target(self.m.clone(), m);
+        out_buffer.set_biguint_target(self.u.clone(), u);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+    use std::str::FromStr;
+
+    use anyhow::Result;
+    use num::bigint::RandBigInt;
+    use num::{BigUint, Integer, One, Zero};
+    use plonky2_field::field_types::{Field, PrimeField, PrimeField64};
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use plonky2_field::ops::Square;
+    use plonky2_field::secp256k1_base::Secp256K1Base;
+    use plonky2_field::secp256k1_scalar::Secp256K1Scalar;
+    use rand::{thread_rng, Rng};
+
+    use crate::curve::curve_types::Curve;
+    use crate::curve::secp256k1::Secp256K1;
+    use crate::gadgets::nonnative::{biguint_to_limbs, sqrt, ModulusContext, NonNativeTarget};
+    use crate::gadgets::test_utils::assert_nonnative_eq;
+    use crate::iop::generator::generate_partial_witness;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn test_nonnative_add() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let x_ff = FF::rand();
+        let y_ff = FF::rand();
+        let sum_ff = x_ff + y_ff;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_nonnative(x_ff);
+        let y = builder.constant_nonnative(y_ff);
+        let sum = builder.add_nonnative(&x, &y);
+
+        let data = builder.build::<C>();
+        let witness = generate_partial_witness(pw.clone(), &data.prover_only, &data.common);
+        assert_nonnative_eq(&witness, &sum, sum_ff.to_canonical_biguint());
+
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_double() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // Include values whose double does and does not exceed the modulus.
+        for x_ff in [FF::rand(), FF::ONE, FF::NEG_ONE] {
+            let x = builder.constant_nonnative(x_ff);
+            let doubled = builder.double_nonnative(&x);
+            let sum = builder.add_nonnative(&x, &x);
+            builder.connect_nonnative(&doubled, &sum);
+
+            let doubled_expected = builder.constant_nonnative(x_ff.double());
+            builder.connect_nonnative(&doubled, &doubled_expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_half() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // Include even and odd values; `-1` is even as the modulus is odd.
+        for x_ff in [FF::rand(), FF::ZERO, FF::ONE, FF::TWO, FF::NEG_ONE] {
+            let x = builder.constant_nonnative(x_ff);
+            let half = builder.half_nonnative(&x);
+            let doubled = builder.double_nonnative(&half);
+            builder.connect_nonnative(&doubled, &x);
+
+            let half_expected = builder.constant_nonnative(x_ff * FF::TWO.inverse());
+            builder.connect_nonnative(&half, &half_expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_cond_add_modulus() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let a_biguint = FF::rand().to_canonical_biguint();
+        let a = builder.constant_biguint(&a_biguint);
+        let modulus = builder.constant_biguint(&FF::order());
+        for cond_value in [false, true] {
+            let cond = builder.add_virtual_bool_target();
+            pw.set_bool_target(cond, cond_value);
+
+            let result = builder.cond_add_modulus(&a, cond, &modulus);
+            let expected_biguint = if cond_value {
+                &a_biguint + FF::order()
+            } else {
+                a_biguint.clone()
+            };
+            let expected = builder.constant_biguint(&expected_biguint);
+            builder.connect_biguint(&result, &expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_sqrt() {
+        type FF = Secp256K1Base;
+        for x in [FF::ZERO, FF::ONE, FF::rand(), FF::NEG_ONE] {
+            let root = sqrt(x.square()).unwrap();
+            assert!(root == x || root == -x);
+        }
+        // -1 is not a square modulo a prime congruent to 3 mod 4.
+        assert!(sqrt(FF::NEG_ONE).is_none());
+    }
+
+    #[test]
+    fn test_nonnative_sqrt() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let x_ff = FF::rand();
+        let square_ff = x_ff.square();
+        let root_ff = sqrt(square_ff).unwrap();
+        let root_is_odd = root_ff.to_canonical_biguint().bit(0);
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let square = builder.constant_nonnative(square_ff);
+        let root = builder.sqrt_nonnative(&square, false);
+        let sign = builder.nonnative_sign(&root);
+
+        let root_expected = builder.constant_nonnative(root_ff);
+        builder.connect_nonnative(&root, &root_expected);
+        let sign_expected = builder.constant_bool(root_is_odd);
+        builder.connect(sign.target, sign_expected.target);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_sqrt_canonical() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        for x_ff in [
+            FF::rand(),
+            FF::rand(),
+            FF::rand(),
+            FF::ZERO,
+            FF::ONE,
+            FF::NEG_ONE,
+        ] {
+            let square_ff = x_ff.square();
+            let even_root_ff = if x_ff.to_canonical_biguint().bit(0) {
+                -x_ff
+            } else {
+                x_ff
+            };
+
+            let square = builder.constant_nonnative(square_ff);
+            let root = builder.sqrt_nonnative(&square, true);
+            let sign = builder.nonnative_sign(&root);
+            builder.assert_zero(sign.target);
+
+            let root_expected = builder.constant_nonnative(even_root_ff);
+            builder.connect_nonnative(&root, &root_expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_witness_nonnative() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let x_ff = FF::rand();
+        // A value above the modulus, which should be reduced to `x_ff`.
+        let value = x_ff.to_canonical_biguint() + FF::order();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.witness_nonnative::<FF>(value);
+        let x_expected = builder.constant_nonnative(x_ff);
+        builder.connect_nonnative(&x, &x_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_normalize_nonnative() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let x_ff = FF::rand();
+        // A non-canonical encoding of `x_ff`, which needs an extra limb.
+        let value = x_ff.to_canonical_biguint() + FF::order();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let num_limbs = CircuitBuilder::<F, D>::num_nonnative_limbs::<FF>();
+        let x_biguint = builder.add_virtual_biguint_target(num_limbs + 1);
+        pw.set_biguint_target(&x_biguint, &value);
+        let x = builder.biguint_to_nonnative::<FF>(&x_biguint);
+
+        let normalized = builder.normalize_nonnative(&x);
+        assert_eq!(normalized.value.num_limbs(), num_limbs);
+        let x_expected = builder.constant_nonnative(x_ff);
+        builder.connect_nonnative(&normalized, &x_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    /// Reduces `x_ff + k * |FF|`, and checks that the result is `x_ff` with quotient `k`.
+    fn test_reduce_nonnative_with_quotient_with(x_ff: Secp256K1Base, k: u32) -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let k_value = BigUint::from(k);
+        let value = x_ff.to_canonical_biguint() + &k_value * FF::order();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let num_limbs = CircuitBuilder::<F, D>::num_nonnative_limbs::<FF>();
+        let x_biguint = builder.add_virtual_biguint_target(num_limbs + 1);
+        pw.set_biguint_target(&x_biguint, &value);
+        let x = builder.biguint_to_nonnative::<FF>(&x_biguint);
+
+        let (reduced, k) = builder.reduce_nonnative_with_quotient(&x);
+        let x_expected = builder.constant_nonnative(x_ff);
+        builder.connect_nonnative(&reduced, &x_expected);
+        let k_expected = builder.constant_biguint(&k_value);
+        builder.connect_biguint(&k.value, &k_expected);
+
+        // Check `x == k * modulus + reduced`.
+        let modulus = builder.constant_biguint(&FF::order());
+        let recomposed = builder.mul_add_biguint(&k.value, &modulus, &reduced.value);
+        builder.connect_biguint(&x.value, &recomposed);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_reduce_nonnative_with_quotient() -> Result<()> {
+        test_reduce_nonnative_with_quotient_with(Secp256K1Base::rand(), 5)
+    }
+
+    #[test]
+    fn test_reduce_nonnative_with_quotient_multiple() -> Result<()> {
+        // The remainder must be zero rather than the modulus, with the full quotient.
+        test_reduce_nonnative_with_quotient_with(Secp256K1Base::ZERO, 5)
+    }
+
+    #[test]
+    fn test_clamp_nonnative() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // Values up to `3 * |FF|`, which need an extra limb.
+        for overflow in 0u32..3 {
+            let x_ff = FF::rand();
+            let x_value = x_ff.to_canonical_biguint() + FF::order() * overflow;
+            let x = NonNativeTarget::<FF> {
+                value: builder.constant_biguint(&x_value),
+                _phantom: PhantomData,
+            };
+            let clamped = builder.clamp_nonnative(&x, 2);
+            let expected = builder.constant_nonnative(x_ff);
+            builder.connect_nonnative(&clamped, &expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_reduce_all_nonnative() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // Unreduced values of various widths, up to that of a product.
+        let mut rng = thread_rng();
+        let mut xs: Vec<_> = [8 * 32, 9 * 32, 12 * 32, 16 * 32]
+            .into_iter()
+            .map(|bits| NonNativeTarget::<FF> {
+                value: builder.constant_biguint(&rng.gen_biguint(bits)),
+                _phantom: PhantomData,
+            })
+            .collect();
+        let individually_reduced: Vec<_> = xs.iter().map(|x| builder.reduce_nonnative(x)).collect();
+
+        builder.reduce_all_nonnative(&mut xs);
+        for (x, expected) in xs.iter().zip(&individually_reduced) {
+            builder.connect_nonnative(x, expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_many_adds() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let a_ff = FF::rand();
+        let b_ff = FF::rand();
+        let c_ff = FF::rand();
+        let d_ff = FF::rand();
+        let e_ff = FF::rand();
+        let f_ff = FF::rand();
+        let g_ff = FF::rand();
+        let h_ff = FF::rand();
+        let sum_ff = a_ff + b_ff + c_ff + d_ff + e_ff + f_ff + g_ff + h_ff;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let a = builder.constant_nonnative(a_ff);
+        let b = builder.constant_nonnative(b_ff);
+        let c = builder.constant_nonnative(c_ff);
+        let d = builder.constant_nonnative(d_ff);
+        let e = builder.constant_nonnative(e_ff);
+        let f = builder.constant_nonnative(f_ff);
+        let g = builder.constant_nonnative(g_ff);
+        let h = builder.constant_nonnative(h_ff);
+        let all = [a, b, c, d, e, f, g, h];
+        let sum = builder.add_many_nonnative(&all);
+
+        let sum_expected = builder.constant_nonnative(sum_ff);
+        builder.connect_nonnative(&sum, &sum_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_sub() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let x_ff = FF::rand();
+        let mut y_ff = FF::rand();
+        while y_ff.to_canonical_biguint() > x_ff.to_canonical_biguint() {
+            y_ff = FF::rand();
+        }
+        let diff_ff = x_ff - y_ff;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_nonnative(x_ff);
+        let y = builder.constant_nonnative(y_ff);
+        let diff = builder.sub_nonnative(&x, &y);
+
+        let diff_expected = builder.constant_nonnative(diff_ff);
+        builder.connect_nonnative(&diff, &diff_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_sub_const() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // `c` exceeds `x`, so the subtraction wraps around the modulus.
+        let x_ff = FF::from_canonical_u64(5);
+        let c_ff = FF::from_canonical_u64(7);
+        let diff_ff = x_ff - c_ff;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_nonnative(x_ff);
+        let diff = builder.sub_nonnative_const(&x, c_ff.to_canonical_biguint());
+        let diff_expected = builder.constant_nonnative(diff_ff);
+        builder.connect_nonnative(&diff, &diff_expected);
+
+        // Constants at or above the modulus are reduced first.
+        let y_ff = FF::rand();
+        let y = builder.constant_nonnative(y_ff);
+        let diff = builder.sub_nonnative_const(&y, FF::order() + 3u32);
+        let diff_expected = builder.constant_nonnative(y_ff - FF::from_canonical_u64(3));
+        builder.connect_nonnative(&diff, &diff_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_divmod() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let x_ff = FF::rand();
+        let y_ff = FF::from_canonical_u64(thread_rng().gen());
+        let (quotient_value, remainder_value) = x_ff
+            .to_canonical_biguint()
+            .div_rem(&y_ff.to_canonical_biguint());
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_nonnative(x_ff);
+        let y = builder.constant_nonnative(y_ff);
+        let (quotient, remainder) = builder.divmod_nonnative(&x, &y);
+
+        let expected_quotient = builder.constant_biguint(&quotient_value);
+        let expected_remainder = builder.constant_biguint(&remainder_value);
+        builder.connect_biguint(&quotient.value, &expected_quotient);
+        builder.connect_biguint(&remainder.value, &expected_remainder);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_mul() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let x_ff = FF::rand();
+        let y_ff = FF::rand();
+        let product_ff = x_ff * y_ff;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_nonnative(x_ff);
+        let y = builder.constant_nonnative(y_ff);
+        let product = builder.mul_nonnative(&x, &y);
+
+        let product_expected = builder.constant_nonnative(product_ff);
+        builder.connect_nonnative(&product, &product_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_limb_bits() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let x_ff = FF::rand();
+        let y_ff = FF::rand();
+
+        /// Checks `add_nonnative`, `sub_nonnative` (in both directions, so that one of them wraps
+        /// around) and `mul_nonnative` with `LIMB_BITS`-bit limbs against the expected values.
+        fn check_ops<const LIMB_BITS: usize>(
+            builder: &mut CircuitBuilder<F, D>,
+            x_ff: FF,
+            y_ff: FF,
+        ) {
+            let x = builder
+                .witness_nonnative_with_limb_bits::<FF, LIMB_BITS>(x_ff.to_canonical_biguint());
+            let y = builder.constant_nonnative_with_limb_bits::<FF, LIMB_BITS>(y_ff);
+            let results = [
+                (builder.add_nonnative(&x, &y), x_ff + y_ff),
+                (builder.sub_nonnative(&x, &y), x_ff - y_ff),
+                (builder.sub_nonnative(&y, &x), y_ff - x_ff),
+                (builder.mul_nonnative(&x, &y), x_ff * y_ff),
+            ];
+            for (result, expected_ff) in results {
+                let expected = builder.constant_nonnative_with_limb_bits(expected_ff);
+                builder.connect_nonnative(&result, &expected);
+            }
+        }
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        check_ops::<16>(&mut builder, x_ff, y_ff);
+        check_ops::<32>(&mut builder, x_ff, y_ff);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    /// Checks `n` products with `batch_assert_mul_nonnative`, with the product at `wrong_index`,
+    /// if any, off by one.
+    fn test_batch_assert_mul_nonnative_with(n: usize, wrong_index: Option<usize>) -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let mut a = Vec::new();
+        let mut b = Vec::new();
+        let mut c = Vec::new();
+        for i in 0..n {
+            let a_ff = FF::rand();
+            let b_ff = FF::rand();
+            let mut c_ff = a_ff * b_ff;
+            if wrong_index == Some(i) {
+                c_ff += FF::ONE;
+            }
+            a.push(builder.constant_nonnative(a_ff));
+            b.push(builder.constant_nonnative(b_ff));
+            c.push(builder.witness_nonnative(c_ff.to_canonical_biguint()));
+        }
+        builder.batch_assert_mul_nonnative(&a, &b, &c);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_batch_assert_mul_nonnative() -> Result<()> {
+        test_batch_assert_mul_nonnative_with(4, None)
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_batch_assert_mul_nonnative_wrong_product() {
+        test_batch_assert_mul_nonnative_with(4, Some(2)).unwrap()
+    }
+
+    #[test]
+    fn test_montgomery_round_trip() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let x_ff = FF::rand();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_nonnative(x_ff);
+        let x_mont = builder.to_montgomery_nonnative(&x);
+        let x_round_trip = builder.from_montgomery_nonnative(&x_mont);
+        builder.connect_nonnative(&x_round_trip, &x);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_mont_mul() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let x_ff = FF::rand();
+        let y_ff = FF::rand();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_nonnative(x_ff);
+        let y = builder.constant_nonnative(y_ff);
+        let x_mont = builder.to_montgomery_nonnative(&x);
+        let y_mont = builder.to_montgomery_nonnative(&y);
+        let product_mont = builder.mont_mul_nonnative(&x_mont, &y_mont);
+        let product = builder.from_montgomery_nonnative(&product_mont);
+
+        let product_expected = builder.mul_nonnative(&x, &y);
+        builder.connect_nonnative(&product, &product_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_mul_add() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let x_ff = FF::rand();
+        let y_ff = FF::rand();
+        let z_ff = FF::rand();
+        let result_ff = x_ff * y_ff + z_ff;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_nonnative(x_ff);
+        let y = builder.constant_nonnative(y_ff);
+        let z = builder.constant_nonnative(z_ff);
+        let result = builder.mul_add_nonnative(&x, &y, &z);
+
+        let product = builder.mul_nonnative(&x, &y);
+        let sequential_result = builder.add_nonnative(&product, &z);
+        builder.connect_nonnative(&result, &sequential_result);
+
+        let result_expected = builder.constant_nonnative(result_ff);
+        builder.connect_nonnative(&result, &result_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_lincomb() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let coeffs_ff: Vec<_> = (0..3).map(|_| FF::rand()).collect();
+        let terms_ff: Vec<_> = (0..3).map(|_| FF::rand()).collect();
+        let result_ff: FF = coeffs_ff.iter().zip(&terms_ff).map(|(&c, &t)| c * t).sum();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let coeffs: Vec<_> = coeffs_ff
+            .iter()
+            .map(|&c| builder.constant_nonnative(c))
+            .collect();
+        let terms: Vec<_> = terms_ff
+            .iter()
+            .map(|&t| builder.constant_nonnative(t))
+            .collect();
+        let result = builder.lincomb_nonnative(&coeffs, &terms);
+
+        let mut sequential_result = builder.zero_nonnative();
+        for (coeff, term) in coeffs.iter().zip(&terms) {
+            sequential_result = builder.mul_add_nonnative(coeff, term, &sequential_result);
+        }
+        builder.connect_nonnative(&result, &sequential_result);
+
+        let result_expected = builder.constant_nonnative(result_ff);
+        builder.connect_nonnative(&result, &result_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_dot() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let coeffs_ff: Vec<_> = (0..4).map(|_| FF::rand()).collect();
+        // Unreduced powers of a point.
+        let point = FF::rand().to_canonical_biguint();
+        let basis: Vec<_> = (0..4).map(|i| point.pow(i)).collect();
+        let result_biguint = coeffs_ff
+            .iter()
+            .zip(&basis)
+            .map(|(c, b)| c.to_canonical_biguint() * b)
+            .sum::<BigUint>()
+            % FF::order();
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let coeffs: Vec<_> = coeffs_ff
+            .iter()
+            .map(|&c| builder.constant_nonnative(c))
+            .collect();
+        let result = builder.nonnative_dot(&coeffs, &basis);
+
+        let result_expected = builder.constant_nonnative(FF::from_biguint(result_biguint));
+        builder.connect_nonnative(&result, &result_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_modulus_context() {
+        // The Secp256k1 base field order, and the BN254 scalar field order, whose top 32-bit limb
+        // only uses 30 bits.
+        let moduli = [
+            Secp256K1Base::order(),
+            BigUint::from_str(
+                "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+            )
+            .unwrap(),
+        ];
+        for modulus in moduli {
+            let context = ModulusContext::new(modulus.clone());
+            let limbs = biguint_to_limbs(&modulus, 32);
+            assert_eq!(context.limbs, limbs);
+            assert_eq!(context.num_limbs(), limbs.len());
+            assert_eq!(context.bits, modulus.bits() as usize);
+
+            let radix = BigUint::one() << (32 * limbs.len());
+            assert_eq!(context.montgomery_radix, radix);
+            assert_eq!(context.montgomery_radix_squared, radix.pow(2) % &modulus);
+            assert_eq!(context.barrett_mu, radix.pow(2) / &modulus);
+        }
+    }
+
+    #[test]
+    fn test_reduce_mul_result_254_bit_modulus() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // The BN254 scalar field order, whose top 32-bit limb only uses 30 bits.
+        let modulus = BigUint::from_str(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+        )
+        .unwrap();
+        assert_eq!(modulus.bits(), 254);
+
+        let mut rng = thread_rng();
+        let x_value = rng.gen_biguint_below(&modulus);
+        let y_value = rng.gen_biguint_below(&modulus);
+        let expected_value = (&x_value * &y_value) % &modulus;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.constant_biguint(&x_value);
+        let y = builder.constant_biguint(&y_value);
+        let product = builder.mul_biguint(&x, &y);
+        let result = builder.reduce_mul_result(&product, &ModulusContext::new(modulus), 8);
+
+        let expected = builder.constant_biguint(&expected_value);
+        builder.connect_biguint(&result, &expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    fn test_nonnative_lt(a_value: BigUint, bound: BigUint) -> Result<()> {
+        type FF = Secp256K1Scalar;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let a = builder.constant_nonnative(FF::from_biguint(a_value));
+        builder.assert_nonnative_lt(&a, &bound);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_nonnative_lt_just_below_bound() -> Result<()> {
+        let half_order = Secp256K1Scalar::order() >> 1usize;
+        test_nonnative_lt(&half_order - BigUint::one(), half_order)
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_nonnative_lt_at_bound() {
+        let half_order = Secp256K1Scalar::order() >> 1usize;
+        test_nonnative_lt(half_order.clone(), half_order).unwrap()
+    }
+
+    #[test]
+    fn test_nonnative_neg() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let x_ff = FF::rand();
+        let neg_x_ff = -x_ff;
 
-        out_buffer.set_biguint_target(self.sum.value.clone(), sum_reduced);
-        out_buffer.set_u32_target(self.overflow, overflow);
-    }
-}
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
 
-#[derive(Debug)]
-struct NonNativeSubtractionGenerator<F: RichField + Extendable<D>, const D: usize, FF: Field> {
-    a: NonNativeTarget<FF>,
-    b: NonNativeTarget<FF>,
-    diff: NonNativeTarget<FF>,
-    overflow: BoolTarget,
-    _phantom: PhantomData<F>,
-}
+        let x = builder.constant_nonnative(x_ff);
+        let neg_x = builder.neg_nonnative(&x);
 
-impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
-    for NonNativeSubtractionGenerator<F, D, FF>
-{
-    fn dependencies(&self) -> Vec<Target> {
-        self.a
-            .value
-            .limbs
-            .iter()
-            .cloned()
-            .chain(self.b.value.limbs.clone())
-            .map(|l| l.0)
-            .collect()
+        let neg_x_expected = builder.constant_nonnative(neg_x_ff);
+        builder.connect_nonnative(&neg_x, &neg_x_expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
     }
 
-    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
-        let a = witness.get_nonnative_target(self.a.clone());
-        let b = witness.get_nonnative_target(self.b.clone());
-        let a_biguint = a.to_canonical_biguint();
-        let b_biguint = b.to_canonical_biguint();
+    #[test]
+    fn test_nonnative_inv() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let x_ff = FF::rand();
+        let inv_x_ff = x_ff.inverse();
 
-        let modulus = FF::order();
-        let (diff_biguint, overflow) = if a_biguint > b_biguint {
-            (a_biguint - b_biguint, false)
-        } else {
-            (modulus + a_biguint - b_biguint, true)
-        };
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
 
-        out_buffer.set_biguint_target(self.diff.value.clone(), diff_biguint);
-        out_buffer.set_bool_target(self.overflow, overflow);
-    }
-}
+        let x = builder.constant_nonnative(x_ff);
+        let inv_x = builder.inv_nonnative(&x);
 
-#[derive(Debug)]
-struct NonNativeMultiplicationGenerator<F: RichField + Extendable<D>, const D: usize, FF: Field> {
-    a: NonNativeTarget<FF>,
-    b: NonNativeTarget<FF>,
-    prod: NonNativeTarget<FF>,
-    overflow: BigUintTarget,
-    _phantom: PhantomData<F>,
-}
+        let inv_x_expected = builder.constant_nonnative(inv_x_ff);
+        builder.connect_nonnative(&inv_x, &inv_x_expected);
 
-impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
-    for NonNativeMultiplicationGenerator<F, D, FF>
-{
-    fn dependencies(&self) -> Vec<Target> {
-        self.a
-            .value
-            .limbs
-            .iter()
-            .cloned()
-            .chain(self.b.value.limbs.clone())
-            .map(|l| l.0)
-            .collect()
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
     }
 
-    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
-        let a = witness.get_nonnative_target(self.a.clone());
-        let b = witness.get_nonnative_target(self.b.clone());
-        let a_biguint = a.to_canonical_biguint();
-        let b_biguint = b.to_canonical_biguint();
+    /// Builds a circuit asserting that the (possibly unreduced) `value` is nonzero modulo `|FF|`.
+    fn test_assert_nonzero_nonnative_with(value: BigUint) -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
 
-        let prod_biguint = a_biguint * b_biguint;
+        let config = CircuitConfig::standard_ecc_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
 
-        let modulus = FF::order();
-        let (overflow_biguint, prod_reduced) = prod_biguint.div_rem(&modulus);
+        let x_biguint = builder.constant_biguint(&value);
+        let x = builder.biguint_to_nonnative::<FF>(&x_biguint);
+        builder.assert_nonzero_nonnative(&x);
 
-        out_buffer.set_biguint_target(self.prod.value.clone(), prod_reduced);
-        out_buffer.set_biguint_target(self.overflow.clone(), overflow_biguint);
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common)
     }
-}
 
-#[derive(Debug)]
-struct NonNativeInverseGenerator<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> {
-    x: NonNativeTarget<FF>,
-    inv: BigUintTarget,
-    div: BigUintTarget,
-    _phantom: PhantomData<F>,
-}
+    #[test]
+    fn test_assert_nonzero_nonnative() -> Result<()> {
+        test_assert_nonzero_nonnative_with(Secp256K1Base::rand().to_canonical_biguint())?;
+        test_assert_nonzero_nonnative_with(Secp256K1Base::order() + BigUint::one())
+    }
 
-impl<F: RichField + Extendable<D>, const D: usize, FF: PrimeField> SimpleGenerator<F>
-    for NonNativeInverseGenerator<F, D, FF>
-{
-    fn dependencies(&self) -> Vec<Target> {
-        self.x.value.limbs.iter().map(|&l| l.0).collect()
+    #[test]
+    #[should_panic]
+    fn test_assert_nonzero_nonnative_zero() {
+        test_assert_nonzero_nonnative_with(BigUint::zero()).unwrap()
     }
 
-    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
-        let x = witness.get_nonnative_target(self.x.clone());
-        let inv = x.inverse();
+    #[test]
+    #[should_panic]
+    fn test_assert_nonzero_nonnative_modulus() {
+        test_assert_nonzero_nonnative_with(Secp256K1Base::order()).unwrap()
+    }
 
-        let x_biguint = x.to_canonical_biguint();
-        let inv_biguint = inv.to_canonical_biguint();
-        let prod = x_biguint * &inv_biguint;
-        let modulus = FF::order();
-        let (div, _rem) = prod.div_rem(&modulus);
+    #[test]
+    #[should_panic]
+    fn test_assert_nonzero_nonnative_bogus_inverse() {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
 
-        out_buffer.set_biguint_target(self.div.clone(), div);
-        out_buffer.set_biguint_target(self.inv.clone(), inv_biguint);
-    }
-}
+        let config = CircuitConfig::standard_ecc_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
 
-#[cfg(test)]
-mod tests {
-    use anyhow::Result;
-    use plonky2_field::field_types::{Field, PrimeField};
-    use plonky2_field::secp256k1_base::Secp256K1Base;
+        // Skip the honest generator, which would panic on zero, and witness an inverse directly.
+        let x = builder.zero_nonnative::<FF>();
+        let num_limbs = CircuitBuilder::<F, D>::num_nonnative_limbs::<FF>();
+        let inv = builder.add_virtual_biguint_target(num_limbs);
+        let div = builder.add_virtual_biguint_target(num_limbs);
+        builder.assert_nonnative_inverse(&x, &inv, &div);
+
+        let inv_value = FF::order() - BigUint::one();
+        let mut inv_limbs = inv_value.to_u32_digits();
+        inv_limbs.resize(num_limbs, 0);
+        for (&limb, &value) in inv.limbs.iter().zip(&inv_limbs) {
+            pw.set_u32_target(limb, value);
+        }
+        for &limb in &div.limbs {
+            pw.set_u32_target(limb, 0);
+        }
 
-    use crate::iop::witness::PartialWitness;
-    use crate::plonk::circuit_builder::CircuitBuilder;
-    use crate::plonk::circuit_data::CircuitConfig;
-    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
-    use crate::plonk::verifier::verify;
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common).unwrap();
+    }
 
     #[test]
-    fn test_nonnative_add() -> Result<()> {
+    fn test_nonnative_from_bool_slice() -> Result<()> {
         type FF = Secp256K1Base;
         const D: usize = 2;
         type C = PoseidonGoldilocksConfig;
         type F = <C as GenericConfig<D>>::F;
 
         let x_ff = FF::rand();
-        let y_ff = FF::rand();
-        let sum_ff = x_ff + y_ff;
 
         let config = CircuitConfig::standard_ecc_config();
         let pw = PartialWitness::new();
         let mut builder = CircuitBuilder::<F, D>::new(config);
 
         let x = builder.constant_nonnative(x_ff);
-        let y = builder.constant_nonnative(y_ff);
-        let sum = builder.add_nonnative(&x, &y);
-
-        let sum_expected = builder.constant_nonnative(sum_ff);
-        builder.connect_nonnative(&sum, &sum_expected);
+        let bits = builder.split_nonnative_to_bits(&x);
+        let x_from_bits = builder.nonnative_from_bool_slice::<FF>(&bits);
+        builder.connect_nonnative(&x, &x_from_bits);
 
         let data = builder.build::<C>();
         let proof = data.prove(pw).unwrap();
@@ -547,39 +2982,24 @@ mod tests {
     }
 
     #[test]
-    fn test_nonnative_many_adds() -> Result<()> {
+    fn test_nonnative_from_bool_slice_modulus() -> Result<()> {
         type FF = Secp256K1Base;
         const D: usize = 2;
         type C = PoseidonGoldilocksConfig;
         type F = <C as GenericConfig<D>>::F;
 
-        let a_ff = FF::rand();
-        let b_ff = FF::rand();
-        let c_ff = FF::rand();
-        let d_ff = FF::rand();
-        let e_ff = FF::rand();
-        let f_ff = FF::rand();
-        let g_ff = FF::rand();
-        let h_ff = FF::rand();
-        let sum_ff = a_ff + b_ff + c_ff + d_ff + e_ff + f_ff + g_ff + h_ff;
-
         let config = CircuitConfig::standard_ecc_config();
         let pw = PartialWitness::new();
         let mut builder = CircuitBuilder::<F, D>::new(config);
 
-        let a = builder.constant_nonnative(a_ff);
-        let b = builder.constant_nonnative(b_ff);
-        let c = builder.constant_nonnative(c_ff);
-        let d = builder.constant_nonnative(d_ff);
-        let e = builder.constant_nonnative(e_ff);
-        let f = builder.constant_nonnative(f_ff);
-        let g = builder.constant_nonnative(g_ff);
-        let h = builder.constant_nonnative(h_ff);
-        let all = [a, b, c, d, e, f, g, h];
-        let sum = builder.add_many_nonnative(&all);
-
-        let sum_expected = builder.constant_nonnative(sum_ff);
-        builder.connect_nonnative(&sum, &sum_expected);
+        // The modulus itself must reduce to zero.
+        let modulus = FF::order();
+        let bits: Vec<_> = (0..modulus.bits())
+            .map(|i| builder.constant_bool(modulus.bit(i)))
+            .collect();
+        let x = builder.nonnative_from_bool_slice::<FF>(&bits);
+        let zero = builder.zero_nonnative();
+        builder.connect_nonnative(&x, &zero);
 
         let data = builder.build::<C>();
         let proof = data.prove(pw).unwrap();
@@ -587,29 +3007,81 @@ mod tests {
     }
 
     #[test]
-    fn test_nonnative_sub() -> Result<()> {
+    fn test_nonnative_from_hex() {
         type FF = Secp256K1Base;
         const D: usize = 2;
         type C = PoseidonGoldilocksConfig;
         type F = <C as GenericConfig<D>>::F;
 
-        let x_ff = FF::rand();
-        let mut y_ff = FF::rand();
-        while y_ff.to_canonical_biguint() > x_ff.to_canonical_biguint() {
-            y_ff = FF::rand();
-        }
-        let diff_ff = x_ff - y_ff;
-
         let config = CircuitConfig::standard_ecc_config();
-        let pw = PartialWitness::new();
         let mut builder = CircuitBuilder::<F, D>::new(config);
 
-        let x = builder.constant_nonnative(x_ff);
-        let y = builder.constant_nonnative(y_ff);
-        let diff = builder.sub_nonnative(&x, &y);
+        let limb_values = |builder: &CircuitBuilder<F, D>, x: &NonNativeTarget<FF>| -> Vec<u64> {
+            x.value
+                .limbs
+                .iter()
+                .map(|l| builder.target_as_constant(l.0).unwrap().to_canonical_u64())
+                .collect()
+        };
 
-        let diff_expected = builder.constant_nonnative(diff_ff);
-        builder.connect_nonnative(&diff, &diff_expected);
+        // The x-coordinate of the secp256k1 generator.
+        let g_x = builder.nonnative_from_hex::<FF>(
+            "0x79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+        );
+        assert_eq!(
+            limb_values(&builder, &g_x),
+            vec![
+                0x16F81798, 0x59F2815B, 0x2DCE28D9, 0x029BFCDB, 0xCE870B07, 0x55A06295, 0xF9DCBBAC,
+                0x79BE667E,
+            ]
+        );
+        let expected = builder.constant_nonnative(Secp256K1::GENERATOR_AFFINE.x);
+        assert_eq!(
+            limb_values(&builder, &g_x),
+            limb_values(&builder, &expected)
+        );
+
+        // Literals are reduced, and the prefix is optional.
+        let p_plus_one = format!("{:x}", FF::order() + BigUint::one());
+        let one = builder.nonnative_from_hex::<FF>(&p_plus_one);
+        assert_eq!(limb_values(&builder, &one), vec![1]);
+    }
+
+    #[test]
+    fn test_nonnative_from_le_bytes() -> Result<()> {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let mut canonical_bytes = FF::rand().to_canonical_biguint().to_bytes_le();
+        canonical_bytes.resize(32, 0);
+        // All ones exceed the modulus, the modulus itself must reduce to zero, and 33 bytes don't
+        // fill the last limb.
+        let unreduced_bytes = vec![0xff; 32];
+        let modulus_bytes = FF::order().to_bytes_le();
+        let counting_bytes: Vec<u8> = (0..33).collect();
+
+        for bytes in [
+            canonical_bytes,
+            unreduced_bytes,
+            modulus_bytes,
+            counting_bytes,
+        ] {
+            let byte_targets = builder.add_virtual_targets(bytes.len());
+            for (&t, &b) in byte_targets.iter().zip(&bytes) {
+                pw.set_target(t, F::from_canonical_u64(b as u64));
+            }
+            let x = builder.nonnative_from_le_bytes::<FF>(&byte_targets);
+
+            let expected_ff = FF::from_biguint(BigUint::from_bytes_le(&bytes) % FF::order());
+            let expected = builder.constant_nonnative(expected_ff);
+            builder.connect_nonnative(&x, &expected);
+        }
 
         let data = builder.build::<C>();
         let proof = data.prove(pw).unwrap();
@@ -617,25 +3089,37 @@ mod tests {
     }
 
     #[test]
-    fn test_nonnative_mul() -> Result<()> {
+    fn test_pack_nonnative_for_hash() -> Result<()> {
         type FF = Secp256K1Base;
         const D: usize = 2;
         type C = PoseidonGoldilocksConfig;
         type F = <C as GenericConfig<D>>::F;
+
         let x_ff = FF::rand();
-        let y_ff = FF::rand();
-        let product_ff = x_ff * y_ff;
 
         let config = CircuitConfig::standard_ecc_config();
         let pw = PartialWitness::new();
         let mut builder = CircuitBuilder::<F, D>::new(config);
 
         let x = builder.constant_nonnative(x_ff);
-        let y = builder.constant_nonnative(y_ff);
-        let product = builder.mul_nonnative(&x, &y);
+        let packed = builder.pack_nonnative_for_hash(&x);
+        // Eight limbs pack into six elements: two groups of three, then two leftover limbs.
+        assert_eq!(packed.len(), 6);
+
+        // Unpack each pair of 48-bit elements back into three limbs.
+        let mut limbs = Vec::new();
+        for pair in packed[..4].chunks(2) {
+            let (low, mid_low) = builder.split_low_high(pair[0], 32, 48);
+            let (mid_high, high) = builder.split_low_high(pair[1], 16, 48);
+            let two_16 = builder.constant(F::from_canonical_u64(1 << 16));
+            let mid = builder.mul_add(mid_high, two_16, mid_low);
+            limbs.extend([low, mid, high]);
+        }
+        limbs.extend(&packed[4..]);
 
-        let product_expected = builder.constant_nonnative(product_ff);
-        builder.connect_nonnative(&product, &product_expected);
+        for (i, limb) in limbs.into_iter().enumerate() {
+            builder.connect(limb, x.value.get_limb(i).0);
+        }
 
         let data = builder.build::<C>();
         let proof = data.prove(pw).unwrap();
@@ -643,44 +3127,46 @@ mod tests {
     }
 
     #[test]
-    fn test_nonnative_neg() -> Result<()> {
+    fn test_nonnative_exp_by_nonnative() -> Result<()> {
         type FF = Secp256K1Base;
+        type EF = GoldilocksField;
         const D: usize = 2;
         type C = PoseidonGoldilocksConfig;
         type F = <C as GenericConfig<D>>::F;
-        let x_ff = FF::rand();
-        let neg_x_ff = -x_ff;
+
+        let base_ff = FF::rand();
+        let exponent_ef = EF::rand();
+        let expected_ff = base_ff.exp_biguint(&exponent_ef.to_canonical_biguint());
 
         let config = CircuitConfig::standard_ecc_config();
         let pw = PartialWitness::new();
         let mut builder = CircuitBuilder::<F, D>::new(config);
 
-        let x = builder.constant_nonnative(x_ff);
-        let neg_x = builder.neg_nonnative(&x);
+        let base = builder.constant_nonnative(base_ff);
+        let exponent = builder.constant_nonnative(exponent_ef);
+        let power = builder.exp_nonnative_by_nonnative(&base, &exponent);
 
-        let neg_x_expected = builder.constant_nonnative(neg_x_ff);
-        builder.connect_nonnative(&neg_x, &neg_x_expected);
+        let expected = builder.constant_nonnative(expected_ff);
+        builder.connect_nonnative(&power, &expected);
 
         let data = builder.build::<C>();
         let proof = data.prove(pw).unwrap();
         verify(proof, &data.verifier_only, &data.common)
     }
 
-    #[test]
-    fn test_nonnative_inv() -> Result<()> {
+    fn test_nonnative_inv_or_zero(x_ff: Secp256K1Base) -> Result<()> {
         type FF = Secp256K1Base;
         const D: usize = 2;
         type C = PoseidonGoldilocksConfig;
         type F = <C as GenericConfig<D>>::F;
-        let x_ff = FF::rand();
-        let inv_x_ff = x_ff.inverse();
+        let inv_x_ff = x_ff.try_inverse().unwrap_or(FF::ZERO);
 
         let config = CircuitConfig::standard_ecc_config();
         let pw = PartialWitness::new();
         let mut builder = CircuitBuilder::<F, D>::new(config);
 
         let x = builder.constant_nonnative(x_ff);
-        let inv_x = builder.inv_nonnative(&x);
+        let inv_x = builder.inv_or_zero_nonnative(&x);
 
         let inv_x_expected = builder.constant_nonnative(inv_x_ff);
         builder.connect_nonnative(&inv_x, &inv_x_expected);
@@ -689,4 +3175,45 @@ mod tests {
         let proof = data.prove(pw).unwrap();
         verify(proof, &data.verifier_only, &data.common)
     }
+
+    #[test]
+    fn test_nonnative_inv_or_zero_nonzero() -> Result<()> {
+        test_nonnative_inv_or_zero(Secp256K1Base::rand())
+    }
+
+    #[test]
+    fn test_nonnative_inv_or_zero_zero() -> Result<()> {
+        test_nonnative_inv_or_zero(Secp256K1Base::ZERO)
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_nonnative_inv_or_zero_forged_zero_flag() {
+        type FF = Secp256K1Base;
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // Claim that a nonzero `x` is zero, with an output of zero, bypassing the honest generator.
+        let x = builder.constant_nonnative(FF::ONE);
+        let num_limbs = x.value.num_limbs();
+        let inv = builder.add_virtual_biguint_target(num_limbs);
+        let div = builder.add_virtual_biguint_target(num_limbs);
+        let x_div = builder.add_virtual_biguint_target(num_limbs);
+        let is_zero = builder.add_virtual_bool_target_safe();
+        builder.assert_nonnative_inverse_or_zero(&x, &inv, &div, &x_div, is_zero);
+
+        for limb in inv.limbs.iter().chain(&div.limbs).chain(&x_div.limbs) {
+            pw.set_u32_target(*limb, 0);
+        }
+        pw.set_bool_target(is_zero, true);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        verify(proof, &data.verifier_only, &data.common).unwrap();
+    }
 }
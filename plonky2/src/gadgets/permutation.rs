@@ -0,0 +1,105 @@
+use plonky2_field::extension_field::Extendable;
+
+use crate::hash::hash_types::RichField;
+use crate::iop::challenger::RecursiveChallenger;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::Target;
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::AlgebraicHasher;
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Asserts that `b` is a permutation of `a`, i.e. that they are equal as multisets.
+    ///
+    /// Both lists are hashed to derive a random extension field challenge `gamma`, and we check that
+    /// `prod_i (gamma - a_i) = prod_i (gamma - b_i)`. If `b` is not a permutation of `a`, these two
+    /// polynomials in `gamma` are distinct, so the check passes with probability at most
+    /// `a.len() / |F^D|`.
+    pub fn assert_permutation<H: AlgebraicHasher<F>>(&mut self, a: &[Target], b: &[Target]) {
+        assert_eq!(
+            a.len(),
+            b.len(),
+            "Permutation must have same number of inputs and outputs"
+        );
+
+        let mut challenger = RecursiveChallenger::<F, H, D>::new(self);
+        challenger.observe_elements(a);
+        challenger.observe_elements(b);
+        let gamma = challenger.get_extension_challenge(self);
+
+        let a_product = self.grand_product(gamma, a);
+        let b_product = self.grand_product(gamma, b);
+        self.connect_extension(a_product, b_product);
+    }
+
+    /// Computes `prod_i (gamma - values_i)`.
+    fn grand_product(
+        &mut self,
+        gamma: ExtensionTarget<D>,
+        values: &[Target],
+    ) -> ExtensionTarget<D> {
+        let terms: Vec<_> = values
+            .iter()
+            .map(|&v| {
+                let v_ext = self.convert_to_ext(v);
+                self.sub_extension(gamma, v_ext)
+            })
+            .collect();
+        self.mul_many_extension(&terms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use rand::seq::SliceRandom;
+    use rand::thread_rng;
+
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    fn test_permutation(change_element: bool) -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        const SIZE: usize = 16;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let a_values = F::rand_vec(SIZE);
+        let mut b_values = a_values.clone();
+        b_values.shuffle(&mut thread_rng());
+        if change_element {
+            b_values[0] += F::ONE;
+        }
+
+        let a = builder.add_virtual_targets(SIZE);
+        let b = builder.add_virtual_targets(SIZE);
+        for i in 0..SIZE {
+            pw.set_target(a[i], a_values[i]);
+            pw.set_target(b[i], b_values[i]);
+        }
+
+        builder.assert_permutation::<<C as GenericConfig<D>>::InnerHasher>(&a, &b);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_valid_permutation() -> Result<()> {
+        test_permutation(false)
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_permutation() {
+        test_permutation(true).unwrap()
+    }
+}
@@ -1,8 +1,11 @@
 use plonky2_field::extension_field::Extendable;
 
 use crate::gadgets::arithmetic_u32::U32Target;
+use crate::gates::gate::Gate;
 use crate::gates::range_check_u32::U32RangeCheckGate;
+use crate::gates::subgroup_index::SubgroupIndexGate;
 use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::generator::{GeneratedValues, SimpleGenerator};
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::witness::{PartitionWitness, Witness};
@@ -14,6 +17,13 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         self.split_le(x, n_log);
     }
 
+    /// Checks that each of the `D` base field coordinates of `x` is less than `2^n_log`.
+    pub fn range_check_ext(&mut self, x: ExtensionTarget<D>, n_log: usize) {
+        for t in x.to_target_array() {
+            self.range_check(t, n_log);
+        }
+    }
+
     /// Returns the first `num_low_bits` little-endian bits of `x`.
     pub fn low_bits(&mut self, x: Target, num_low_bits: usize, num_bits: usize) -> Vec<BoolTarget> {
         let mut res = self.split_le(x, num_bits);
@@ -44,16 +54,34 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         (low, high)
     }
 
-    pub fn range_check_u32(&mut self, vals: Vec<U32Target>) {
-        let num_input_limbs = vals.len();
-        let gate = U32RangeCheckGate::<F, D>::new(num_input_limbs);
+    /// Returns `x mod 2^log_n`, i.e. the index of `g^x` in a subgroup of order `2^log_n` with
+    /// generator `g`. `x` is range-checked to `num_bits` bits.
+    pub fn subgroup_index(&mut self, x: Target, log_n: usize, num_bits: usize) -> Target {
+        let gate = SubgroupIndexGate::<F, D>::new(num_bits, log_n);
         let gate_index = self.add_gate(gate, vec![]);
+        self.connect(
+            x,
+            Target::wire(gate_index, SubgroupIndexGate::<F, D>::WIRE_INPUT),
+        );
+        Target::wire(gate_index, SubgroupIndexGate::<F, D>::WIRE_OUTPUT)
+    }
 
-        for i in 0..num_input_limbs {
-            self.connect(
-                Target::wire(gate_index, gate.wire_ith_input_limb(i)),
-                vals[i].0,
-            );
+    /// Checks that each of `vals` fits in 32 bits, using as many `U32RangeCheckGate`s as needed
+    /// to fit the config.
+    pub fn range_check_u32(&mut self, vals: Vec<U32Target>) {
+        let max_limbs_per_gate =
+            self.config.num_wires / U32RangeCheckGate::<F, D>::new(1).num_wires();
+        for chunk in vals.chunks(max_limbs_per_gate) {
+            let num_input_limbs = chunk.len();
+            let gate = U32RangeCheckGate::<F, D>::new(num_input_limbs);
+            let gate_index = self.add_gate(gate, vec![]);
+
+            for i in 0..num_input_limbs {
+                self.connect(
+                    Target::wire(gate_index, gate.wire_ith_input_limb(i)),
+                    chunk[i].0,
+                );
+            }
         }
     }
 
@@ -86,3 +114,50 @@ impl<F: RichField> SimpleGenerator<F> for LowHighGenerator {
         out_buffer.set_target(self.high, F::from_canonical_u64(high));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::extension_field::FieldExtension;
+    use plonky2_field::field_types::Field;
+
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    /// Range-checks an extension target with the given coordinates to 16 bits.
+    fn test_range_check_ext_with(coordinates: [u64; 2]) -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.add_virtual_extension_target();
+        builder.range_check_ext(x, 16);
+        pw.set_extension_target(
+            x,
+            FF::from_basefield_array(coordinates.map(F::from_canonical_u64)),
+        );
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_range_check_ext() -> Result<()> {
+        test_range_check_ext_with([0, (1 << 16) - 1])
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_range_check_ext_out_of_range() {
+        test_range_check_ext_with([12345, 1 << 16]).unwrap()
+    }
+}
@@ -0,0 +1,104 @@
+use plonky2_field::extension_field::Extendable;
+
+use crate::hash::hash_types::RichField;
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+/// The number of bits in a SHA-256 word.
+const WORD_BITS: usize = 32;
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Computes `ROTR^rot_a(x) ^ ROTR^rot_b(x) ^ SHR^shr_c(x)` for a 32-bit word `x`, as in the
+    /// SHA-256 message schedule functions `σ0` and `σ1`. `x` is split into bits once, and all three
+    /// transforms are read off that decomposition.
+    pub fn sha_sigma(&mut self, x: Target, rot_a: usize, rot_b: usize, shr_c: usize) -> Target {
+        let bits = self.split_le(x, WORD_BITS);
+        let output_bits: Vec<_> = (0..WORD_BITS)
+            .map(|j| {
+                let a = bits[(j + rot_a) % WORD_BITS];
+                let b = bits[(j + rot_b) % WORD_BITS];
+                let a_xor_b = self.xor_bits(a, b);
+                match bits.get(j + shr_c) {
+                    Some(&c) => self.xor_bits(a_xor_b, c),
+                    None => a_xor_b,
+                }
+            })
+            .collect();
+        self.le_sum(output_bits.into_iter())
+    }
+
+    /// Computes `ROTR^rot_a(x) ^ ROTR^rot_b(x) ^ ROTR^rot_c(x)` for a 32-bit word `x`, as in the
+    /// SHA-256 compression functions `Σ0` and `Σ1`. See `sha_sigma`.
+    pub fn sha_big_sigma(&mut self, x: Target, rot_a: usize, rot_b: usize, rot_c: usize) -> Target {
+        let bits = self.split_le(x, WORD_BITS);
+        let output_bits: Vec<_> = (0..WORD_BITS)
+            .map(|j| {
+                let a = bits[(j + rot_a) % WORD_BITS];
+                let b = bits[(j + rot_b) % WORD_BITS];
+                let c = bits[(j + rot_c) % WORD_BITS];
+                let a_xor_b = self.xor_bits(a, b);
+                self.xor_bits(a_xor_b, c)
+            })
+            .collect();
+        self.le_sum(output_bits.into_iter())
+    }
+
+    /// Returns `a ^ b`, computed as `a + b - 2ab`.
+    fn xor_bits(&mut self, a: BoolTarget, b: BoolTarget) -> BoolTarget {
+        let a_plus_b = self.add(a.target, b.target);
+        let res = self.arithmetic(-F::TWO, F::ONE, a.target, b.target, a_plus_b);
+        BoolTarget::new_unsafe(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use rand::Rng;
+
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn test_sha_sigmas() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x_value: u32 = rand::thread_rng().gen();
+        let x = builder.add_virtual_target();
+        pw.set_target(x, F::from_canonical_u32(x_value));
+
+        let rotr = |n| x_value.rotate_right(n);
+        let expected = [
+            // σ0, σ1.
+            rotr(7) ^ rotr(18) ^ (x_value >> 3),
+            rotr(17) ^ rotr(19) ^ (x_value >> 10),
+            // Σ0, Σ1.
+            rotr(2) ^ rotr(13) ^ rotr(22),
+            rotr(6) ^ rotr(11) ^ rotr(25),
+        ];
+        let outputs = [
+            builder.sha_sigma(x, 7, 18, 3),
+            builder.sha_sigma(x, 17, 19, 10),
+            builder.sha_big_sigma(x, 2, 13, 22),
+            builder.sha_big_sigma(x, 6, 11, 25),
+        ];
+        for (output, expected) in outputs.into_iter().zip(expected) {
+            let expected = builder.constant(F::from_canonical_u32(expected));
+            builder.connect(output, expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
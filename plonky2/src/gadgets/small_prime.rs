@@ -0,0 +1,149 @@
+use plonky2_field::extension_field::Extendable;
+
+use crate::hash::hash_types::RichField;
+use crate::iop::generator::{GeneratedValues, NonzeroTestGenerator, SimpleGenerator};
+use crate::iop::target::Target;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Returns `x mod p`, where `x` is interpreted as its canonical integer representative and `p`
+    /// is a prime below `2^32`. The quotient `q` and remainder `r` are witnessed, and we enforce
+    /// `x = q * p + r` together with range checks ensuring `r < p` and that `q * p + r` does not
+    /// wrap around the field order.
+    pub fn field_mod_small_prime(&mut self, x: Target, p: u64) -> Target {
+        assert!((2..1 << 32).contains(&p), "Modulus must lie in [2, 2^32)");
+
+        // The largest quotient and, for that quotient, the largest remainder which still give a
+        // value below the field order.
+        let q_max = (F::ORDER - 1) / p;
+        let r_max_at_q_max = (F::ORDER - 1) % p;
+        let q_bits = 64 - q_max.leading_zeros() as usize;
+
+        let q = self.add_virtual_target();
+        let r = self.add_virtual_target();
+        self.add_simple_generator(SmallPrimeReductionGenerator { x, p, q, r });
+
+        let p_target = self.constant(F::from_canonical_u64(p));
+        let computed_x = self.mul_add(q, p_target, r);
+        self.connect(x, computed_x);
+
+        // Check `q <= q_max`.
+        self.range_check(q, q_bits);
+        let q_max_target = self.constant(F::from_canonical_u64(q_max));
+        let q_slack = self.sub(q_max_target, q);
+        self.range_check(q_slack, q_bits);
+
+        // `is_q_max` is 1 iff `q_slack == 0`, in which case the remainder must be at most
+        // `r_max_at_q_max` rather than `p - 1`.
+        let q_slack_inv = self.add_virtual_target();
+        self.add_simple_generator(NonzeroTestGenerator {
+            to_test: q_slack,
+            dummy: q_slack_inv,
+        });
+        let one = self.one();
+        let is_q_max = self.arithmetic(F::NEG_ONE, F::ONE, q_slack, q_slack_inv, one);
+        let should_be_zero = self.mul(q_slack, is_q_max);
+        self.assert_zero(should_be_zero);
+
+        // Check `r <= r_bound`, where `r_bound` is `p - 1` or `r_max_at_q_max` as above.
+        let p_minus_one = self.constant(F::from_canonical_u64(p - 1));
+        let r_bound = self.arithmetic(
+            -F::from_canonical_u64(p - 1 - r_max_at_q_max),
+            F::ONE,
+            is_q_max,
+            one,
+            p_minus_one,
+        );
+        let r_slack = self.sub(r_bound, r);
+        self.range_check(r, 32);
+        self.range_check(r_slack, 32);
+
+        r
+    }
+}
+
+#[derive(Debug)]
+struct SmallPrimeReductionGenerator {
+    x: Target,
+    p: u64,
+    q: Target,
+    r: Target,
+}
+
+impl<F: RichField> SimpleGenerator<F> for SmallPrimeReductionGenerator {
+    fn dependencies(&self) -> Vec<Target> {
+        vec![self.x]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let x = witness.get_target(self.x).to_canonical_u64();
+
+        out_buffer.set_target(self.q, F::from_canonical_u64(x / self.p));
+        out_buffer.set_target(self.r, F::from_canonical_u64(x % self.p));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::field_types::{Field, PrimeField64};
+    use rand::{thread_rng, Rng};
+
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    fn test_field_mod_small_prime(p: u64) -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut rng = thread_rng();
+        let mut inputs = vec![
+            0,
+            1,
+            p - 1,
+            p,
+            p + 1,
+            u32::MAX as u64 - 1,
+            u32::MAX as u64,
+            F::NEG_ONE.to_canonical_u64(),
+        ];
+        inputs.extend((0..8).map(|_| rng.gen::<u32>() as u64));
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        for x_value in inputs {
+            let x = builder.add_virtual_target();
+            let r = builder.field_mod_small_prime(x, p);
+            let expected_r = builder.constant(F::from_canonical_u64(x_value % p));
+            builder.connect(r, expected_r);
+
+            pw.set_target(x, F::from_canonical_u64(x_value));
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_field_mod_small_prime_mersenne_31() -> Result<()> {
+        test_field_mod_small_prime((1 << 31) - 1)
+    }
+
+    #[test]
+    fn test_field_mod_small_prime_largest_u32_prime() -> Result<()> {
+        test_field_mod_small_prime(4294967291)
+    }
+
+    #[test]
+    fn test_field_mod_small_prime_tiny() -> Result<()> {
+        test_field_mod_small_prime(3)
+    }
+}
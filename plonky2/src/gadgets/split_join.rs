@@ -13,10 +13,14 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     /// bit of the integer, with little-endian ordering.
     /// Verifies that the decomposition is correct by using `k` `BaseSum<2>` gates
     /// with `k` such that `k * num_routed_wires >= num_bits`.
+    /// Decompositions are memoized, so splitting the same integer again adds no gates.
     pub(crate) fn split_le(&mut self, integer: Target, num_bits: usize) -> Vec<BoolTarget> {
         if num_bits == 0 {
             return Vec::new();
         }
+        if let Some(bits) = self.split_le_results.get(&(integer, num_bits)) {
+            return bits.clone();
+        }
         let gate_type = BaseSumGate::<2>::new_from_config::<F>(&self.config);
         let k = ceil_div_usize(num_bits, gate_type.num_limbs);
         let gates = (0..k)
@@ -49,6 +53,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             num_limbs: gate_type.num_limbs,
         });
 
+        self.split_le_results
+            .insert((integer, num_bits), bits.clone());
         bits
     }
 }
@@ -119,3 +125,48 @@ impl<F: RichField> SimpleGenerator<F> for WireSplitGenerator {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+
+    use crate::iop::target::BoolTarget;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn test_split_le_memoized() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.add_virtual_target();
+        pw.set_target(x, F::from_canonical_u32(0xDEAD_BEEF));
+
+        let bits = builder.split_le(x, 32);
+        let num_gates = builder.num_gates();
+        let targets =
+            |bits: Vec<BoolTarget>| bits.into_iter().map(|b| b.target).collect::<Vec<_>>();
+        assert_eq!(targets(builder.split_le(x, 32)), targets(bits.clone()));
+        assert_eq!(builder.num_gates(), num_gates);
+
+        // A different width is a different decomposition.
+        let low_bits = builder.split_le(x, 40);
+        assert!(builder.num_gates() > num_gates);
+        for (&bit, &low_bit) in bits.iter().zip(&low_bits) {
+            builder.connect(bit.target, low_bit.target);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
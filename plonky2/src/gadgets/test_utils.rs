@@ -0,0 +1,22 @@
+use num::BigUint;
+use plonky2_field::field_types::Field;
+
+use crate::gadgets::nonnative::NonNativeTarget;
+use crate::hash::hash_types::RichField;
+use crate::iop::witness::Witness;
+
+/// Asserts that the limbs of `target` in `witness` encode `expected`. Unlike connecting `target`
+/// to a constant, this reports both values on a mismatch, and also catches non-canonical limbs
+/// which would reduce to the expected field element.
+pub(crate) fn assert_nonnative_eq<F: RichField, FF: Field, W: Witness<F>>(
+    witness: &W,
+    target: &NonNativeTarget<FF>,
+    expected: BigUint,
+) {
+    let actual = witness.get_biguint_target(target.value.clone());
+    assert_eq!(
+        actual, expected,
+        "Nonnative target has value {}, expected {}",
+        actual, expected
+    );
+}
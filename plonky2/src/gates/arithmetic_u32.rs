@@ -64,6 +64,16 @@ impl<F: RichField + Extendable<D>, const D: usize> U32ArithmeticGate<F, D> {
         5 * i + 4
     }
 
+    /// The high half of the product is a single routed 32-bit limb, so it can feed the next limb
+    /// position of a multi-precision multiplication without being decomposed again.
+    pub fn num_output_high_limbs() -> usize {
+        1
+    }
+    pub fn wire_ith_output_high_limb(&self, i: usize, k: usize) -> usize {
+        debug_assert!(k < Self::num_output_high_limbs());
+        self.wire_ith_output_high_half(i)
+    }
+
     pub fn limb_bits() -> usize {
         2
     }
@@ -199,26 +209,36 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for U32ArithmeticG
         gate_index: usize,
         _local_constants: &[F],
     ) -> Vec<Box<dyn WitnessGenerator<F>>> {
-        (0..self.num_ops)
-            .map(|i| {
-                let g: Box<dyn WitnessGenerator<F>> = Box::new(
-                    U32ArithmeticGenerator {
-                        gate: *self,
-                        gate_index,
-                        i,
-                        _phantom: PhantomData,
-                    }
-                    .adapter(),
-                );
-                g
-            })
-            .collect()
+        vec![Box::new(U32ArithmeticBatchGenerator {
+            ops: (0..self.num_ops)
+                .map(|i| U32ArithmeticGenerator {
+                    gate: *self,
+                    gate_index,
+                    i,
+                    _phantom: PhantomData,
+                })
+                .collect(),
+        })]
+    }
+
+    // The batched generator only finishes once every operation of the row has been generated.
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(vec![
+            (self.wire_ith_multiplicand_0(op), F::ZERO),
+            (self.wire_ith_multiplicand_1(op), F::ZERO),
+            (self.wire_ith_addend(op), F::ZERO),
+        ])
     }
 
     fn num_wires(&self) -> usize {
         self.num_ops * (5 + Self::num_limbs())
     }
 
+    // All operations share a single batched generator, so the default can't count them.
+    fn num_ops(&self) -> usize {
+        self.num_ops
+    }
+
     fn num_constants(&self) -> usize {
         0
     }
@@ -230,6 +250,36 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for U32ArithmeticG
     fn num_constraints(&self) -> usize {
         self.num_ops * (3 + Self::num_limbs())
     }
+
+    fn wire_labels(&self) -> Vec<(usize, String)> {
+        let mut labels = Vec::new();
+        for i in 0..self.num_ops {
+            labels.push((
+                self.wire_ith_multiplicand_0(i),
+                format!("multiplicand_0[{}]", i),
+            ));
+            labels.push((
+                self.wire_ith_multiplicand_1(i),
+                format!("multiplicand_1[{}]", i),
+            ));
+            labels.push((self.wire_ith_addend(i), format!("addend[{}]", i)));
+            labels.push((
+                self.wire_ith_output_low_half(i),
+                format!("output_low_half[{}]", i),
+            ));
+            labels.push((
+                self.wire_ith_output_high_half(i),
+                format!("output_high_half[{}]", i),
+            ));
+            for j in 0..Self::num_limbs() {
+                labels.push((
+                    self.wire_ith_output_jth_limb(i, j),
+                    format!("output_limb[{}][{}]", i, j),
+                ));
+            }
+        }
+        labels
+    }
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D>
@@ -344,21 +394,67 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
     }
 }
 
+/// Generates the outputs of all operations of a `U32ArithmeticGate` row, so that a single
+/// generator is dispatched per row rather than one per operation.
+///
+/// An operation's inputs may be wired to the outputs of an earlier operation in the same row, e.g.
+/// by `chain_high_half`, so operations are generated as soon as their own inputs are known, over
+/// possibly several runs. The generator is finished once every operation has been generated, so
+/// unused operations must be given inputs; see `Gate::unused_op_inputs`.
+#[derive(Debug)]
+struct U32ArithmeticBatchGenerator<F: RichField + Extendable<D>, const D: usize> {
+    ops: Vec<U32ArithmeticGenerator<F, D>>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> WitnessGenerator<F>
+    for U32ArithmeticBatchGenerator<F, D>
+{
+    fn watch_list(&self) -> Vec<Target> {
+        self.ops.iter().flat_map(|op| op.dependencies()).collect()
+    }
+
+    fn run(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) -> bool {
+        let mut finished = true;
+        for op in &self.ops {
+            // The limbs aren't routed, so they are only set once the operation has been generated.
+            // The outputs may already be known through copy constraints.
+            let limb = Target::wire(op.gate_index, op.gate.wire_ith_output_jth_limb(op.i, 0));
+            if witness.contains(limb) {
+                continue;
+            }
+            if witness.contains_all(&op.dependencies()) {
+                op.run_once(witness, out_buffer);
+            } else {
+                finished = false;
+            }
+        }
+        finished
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::marker::PhantomData;
 
     use anyhow::Result;
-    use plonky2_field::field_types::Field;
+    use plonky2_field::field_types::{Field, PrimeField64};
     use plonky2_field::goldilocks_field::GoldilocksField;
     use rand::Rng;
 
-    use crate::gates::arithmetic_u32::U32ArithmeticGate;
+    use crate::gadgets::arithmetic_u32::U32Target;
+    use crate::gates::arithmetic_u32::{U32ArithmeticGate, U32ArithmeticGenerator};
     use crate::gates::gate::Gate;
     use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
     use crate::hash::hash_types::HashOut;
+    use crate::iop::generator::{GeneratedValues, SimpleGenerator};
+    use crate::iop::target::Target;
+    use crate::iop::wire::Wire;
+    use crate::iop::witness::{PartialWitness, PartitionWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
     use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
     use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
 
     #[test]
     fn low_degree() {
@@ -455,4 +551,131 @@ mod tests {
             "Gate constraints are not satisfied."
         );
     }
+
+    #[test]
+    fn test_batch_generator() {
+        type F = GoldilocksField;
+        const D: usize = 4;
+
+        let gate = U32ArithmeticGate::<F, D> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        };
+        let local_target = |input| Target::wire(0, input);
+        let mut rng = rand::thread_rng();
+        let mut inputs = Vec::new();
+        for i in 0..gate.num_ops {
+            inputs.push((gate.wire_ith_multiplicand_0(i), rng.gen::<u32>()));
+            inputs.push((gate.wire_ith_multiplicand_1(i), rng.gen::<u32>()));
+            inputs.push((gate.wire_ith_addend(i), rng.gen::<u32>()));
+        }
+
+        // Values generated by running each operation's generator on its own.
+        let identity_map: Vec<_> = (0..gate.num_wires()).collect();
+        let mut witness = PartitionWitness::new(gate.num_wires(), 1, 0, &identity_map);
+        for &(input, value) in &inputs {
+            witness.set_target(local_target(input), F::from_canonical_u32(value));
+        }
+        let mut expected = GeneratedValues::empty();
+        for i in 0..gate.num_ops {
+            U32ArithmeticGenerator {
+                gate,
+                gate_index: 0,
+                i,
+                _phantom: PhantomData,
+            }
+            .run_once(&witness, &mut expected);
+        }
+
+        let generators = gate.generators(0, &[]);
+        assert_eq!(generators.len(), 1);
+        let mut batched = GeneratedValues::empty();
+        assert!(generators[0].run(&witness, &mut batched));
+        assert_eq!(batched.target_values, expected.target_values);
+
+        // Chain the high half of the first operation into the addend of the second, which can
+        // then only be generated by a later run.
+        let mut chained_map = identity_map.clone();
+        chained_map[gate.wire_ith_addend(1)] = gate.wire_ith_output_high_half(0);
+        let mut witness = PartitionWitness::new(gate.num_wires(), 1, 0, &chained_map);
+        for &(input, value) in &inputs {
+            if input != gate.wire_ith_addend(1) {
+                witness.set_target(local_target(input), F::from_canonical_u32(value));
+            }
+        }
+        let mut buffer = GeneratedValues::empty();
+        assert!(!generators[0].run(&witness, &mut buffer));
+        for (target, value) in buffer.target_values.drain(..) {
+            witness.set_target(target, value);
+        }
+        assert!(generators[0].run(&witness, &mut buffer));
+        for (target, value) in buffer.target_values.drain(..) {
+            witness.set_target(target, value);
+        }
+
+        let high_half = witness.get_wire(Wire {
+            gate: 0,
+            input: gate.wire_ith_output_high_half(0),
+        });
+        let output = |i| {
+            let low = witness.get_wire(Wire {
+                gate: 0,
+                input: gate.wire_ith_output_low_half(i),
+            });
+            let high = witness.get_wire(Wire {
+                gate: 0,
+                input: gate.wire_ith_output_high_half(i),
+            });
+            (high.to_canonical_u64() << 32) + low.to_canonical_u64()
+        };
+        let (m0, m1) = (inputs[3].1 as u64, inputs[4].1 as u64);
+        assert_eq!(output(1), m0 * m1 + high_half.to_canonical_u64());
+    }
+
+    #[test]
+    fn test_high_limb_chaining() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut rng = rand::thread_rng();
+        let [a, b, c, d] = [(); 4].map(|_| rng.gen::<u32>() as u64);
+        // The carry of `a * b + c` into the next limb position, plus `d`.
+        let expected = ((a * b + c) >> 32) + d;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+
+        let gate = U32ArithmeticGate::<F, D>::new_from_config(&config);
+        let (gate_index, copy) = builder.find_slot(gate, &[], &[]);
+        for (wire, value) in [
+            (gate.wire_ith_multiplicand_0(copy), a),
+            (gate.wire_ith_multiplicand_1(copy), b),
+            (gate.wire_ith_addend(copy), c),
+        ] {
+            let value = builder.constant(F::from_canonical_u64(value));
+            builder.connect(Target::wire(gate_index, wire), value);
+        }
+
+        let high_limbs: Vec<_> = (0..U32ArithmeticGate::<F, D>::num_output_high_limbs())
+            .map(|k| {
+                U32Target(Target::wire(
+                    gate_index,
+                    gate.wire_ith_output_high_limb(copy, k),
+                ))
+            })
+            .collect();
+        let d = builder.constant_u32(d as u32);
+        let (sum_low, sum_high) = builder.add_u32(high_limbs[0], d);
+
+        let expected_low = builder.constant_u32(expected as u32);
+        let expected_high = builder.constant_u32((expected >> 32) as u32);
+        builder.connect_u32(sum_low, expected_low);
+        builder.connect_u32(sum_high, expected_high);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
 }
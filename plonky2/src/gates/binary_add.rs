@@ -0,0 +1,497 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to perform an addition on `BITS`-bit limbs: given `x`, `y`, and `carry_in`, it returns
+/// the `BITS`-bit result `x + y + carry_in` and, if this overflows, a `carry_out` bit. Inputs are
+/// not range-checked.
+#[derive(Copy, Clone, Debug)]
+pub struct BinaryAddGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> BinaryAddGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(
+            BITS < 63,
+            "Sums of more than 63 bits would overflow the field"
+        );
+        assert_eq!(BITS % Self::limb_bits(), 0);
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 5 + Self::num_limbs();
+        let routed_wires_per_op = 5;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input_x(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i
+    }
+    pub fn wire_ith_input_y(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 1
+    }
+    pub fn wire_ith_input_carry(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 2
+    }
+
+    pub fn wire_ith_output_result(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 3
+    }
+    pub fn wire_ith_output_carry(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 4
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // We have limbs for the `BITS` bits of `output_result`.
+    pub fn num_limbs() -> usize {
+        BITS / Self::limb_bits()
+    }
+
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        5 * self.num_ops + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for BinaryAddGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input_x = vars.local_wires[self.wire_ith_input_x(i)];
+            let input_y = vars.local_wires[self.wire_ith_input_y(i)];
+            let input_carry = vars.local_wires[self.wire_ith_input_carry(i)];
+
+            let computed_output = input_x + input_y + input_carry;
+            let base = F::Extension::from_canonical_u64(1 << BITS);
+
+            let output_result = vars.local_wires[self.wire_ith_output_result(i)];
+            let output_carry = vars.local_wires[self.wire_ith_output_carry(i)];
+
+            constraints.push(output_result + base * output_carry - computed_output);
+
+            // Range-check output_result to be at most BITS bits.
+            let mut combined_limbs = F::Extension::ZERO;
+            let limb_base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                    .product();
+                constraints.push(product);
+
+                combined_limbs = limb_base * combined_limbs + this_limb;
+            }
+            constraints.push(combined_limbs - output_result);
+
+            // Range-check output_carry to be one bit.
+            constraints.push(output_carry * (F::Extension::ONE - output_carry));
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input_x = vars.local_wires[self.wire_ith_input_x(i)];
+            let input_y = vars.local_wires[self.wire_ith_input_y(i)];
+            let input_carry = vars.local_wires[self.wire_ith_input_carry(i)];
+
+            let computed_output = builder.add_many_extension(&[input_x, input_y, input_carry]);
+            let base = builder.constant_extension(F::Extension::from_canonical_u64(1 << BITS));
+
+            let output_result = vars.local_wires[self.wire_ith_output_result(i)];
+            let output_carry = vars.local_wires[self.wire_ith_output_carry(i)];
+
+            let combined_output = builder.mul_add_extension(base, output_carry, output_result);
+            constraints.push(builder.sub_extension(combined_output, computed_output));
+
+            // Range-check output_result to be at most BITS bits.
+            let mut combined_limbs = builder.zero_extension();
+            let limb_base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let mut product = builder.one_extension();
+                for x in 0..max_limb {
+                    let x_target =
+                        builder.constant_extension(F::Extension::from_canonical_usize(x));
+                    let diff = builder.sub_extension(this_limb, x_target);
+                    product = builder.mul_extension(product, diff);
+                }
+                constraints.push(product);
+
+                combined_limbs = builder.mul_add_extension(limb_base, combined_limbs, this_limb);
+            }
+            constraints.push(builder.sub_extension(combined_limbs, output_result));
+
+            // Range-check output_carry to be one bit.
+            let one = builder.one_extension();
+            let not_carry = builder.sub_extension(one, output_carry);
+            constraints.push(builder.mul_extension(output_carry, not_carry));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BinaryAddGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                        _phantom: PhantomData,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (5 + Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (3 + Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for BinaryAddGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input_x = vars.local_wires[self.wire_ith_input_x(i)];
+            let input_y = vars.local_wires[self.wire_ith_input_y(i)];
+            let input_carry = vars.local_wires[self.wire_ith_input_carry(i)];
+
+            let computed_output = input_x + input_y + input_carry;
+            let base = F::from_canonical_u64(1 << BITS);
+
+            let output_result = vars.local_wires[self.wire_ith_output_result(i)];
+            let output_carry = vars.local_wires[self.wire_ith_output_carry(i)];
+
+            yield_constr.one(output_result + output_carry * base - computed_output);
+
+            // Range-check output_result to be at most BITS bits.
+            let mut combined_limbs = P::ZEROS;
+            let limb_base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::from_canonical_usize(x))
+                    .product();
+                yield_constr.one(product);
+
+                combined_limbs = combined_limbs * limb_base + this_limb;
+            }
+            yield_constr.one(combined_limbs - output_result);
+
+            // Range-check output_carry to be one bit.
+            yield_constr.one(output_carry * (P::ONES - output_carry));
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BinaryAddGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: BinaryAddGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for BinaryAddGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        vec![
+            local_target(self.gate.wire_ith_input_x(self.i)),
+            local_target(self.gate.wire_ith_input_y(self.i)),
+            local_target(self.gate.wire_ith_input_carry(self.i)),
+        ]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input));
+
+        let input_x = get_local_wire(self.gate.wire_ith_input_x(self.i));
+        let input_y = get_local_wire(self.gate.wire_ith_input_y(self.i));
+        let input_carry = get_local_wire(self.gate.wire_ith_input_carry(self.i));
+
+        let computed_output = (input_x + input_y + input_carry).to_canonical_u64();
+        let output_result_u64 = computed_output & ((1 << BITS) - 1);
+        let output_carry_u64 = computed_output >> BITS;
+
+        let output_result_wire = local_wire(self.gate.wire_ith_output_result(self.i));
+        let output_carry_wire = local_wire(self.gate.wire_ith_output_carry(self.i));
+
+        out_buffer.set_wire(output_result_wire, F::from_canonical_u64(output_result_u64));
+        out_buffer.set_wire(output_carry_wire, F::from_canonical_u64(output_carry_u64));
+
+        let num_limbs = BinaryAddGate::<F, D, BITS>::num_limbs();
+        let limb_base = 1 << BinaryAddGate::<F, D, BITS>::limb_bits();
+        let output_limbs = (0..num_limbs).scan(output_result_u64, |acc, _| {
+            let tmp = *acc % limb_base;
+            *acc /= limb_base;
+            Some(F::from_canonical_u64(tmp))
+        });
+
+        for (j, limb) in output_limbs.enumerate() {
+            let wire = local_wire(self.gate.wire_ith_output_jth_limb(self.i, j));
+            out_buffer.set_wire(wire, limb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::binary_add::BinaryAddGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(BinaryAddGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BinaryAddGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FF = <C as GenericConfig<D>>::FE;
+    const BITS: usize = 16;
+
+    /// Builds the wires of a gate performing the given additions, using the given results and
+    /// output carries.
+    fn get_wires(ops: &[(u64, u64, u64, u64, u64)]) -> Vec<FF> {
+        let mut v0 = Vec::new();
+        let mut v1 = Vec::new();
+
+        let num_limbs = BinaryAddGate::<F, D, BITS>::num_limbs();
+        let limb_base = 1 << BinaryAddGate::<F, D, BITS>::limb_bits();
+        for &(x, y, carry_in, result, carry_out) in ops {
+            v0.push(F::from_canonical_u64(x));
+            v0.push(F::from_canonical_u64(y));
+            v0.push(F::from_canonical_u64(carry_in));
+            v0.push(F::from_canonical_u64(result));
+            v0.push(F::from_canonical_u64(carry_out));
+
+            let mut value = result;
+            for _ in 0..num_limbs {
+                v1.push(F::from_canonical_u64(value % limb_base));
+                value /= limb_base;
+            }
+        }
+
+        v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        let mut rng = rand::thread_rng();
+        let max = (1 << BITS) - 1;
+        let inputs = [
+            (rng.gen::<u16>() as u64, rng.gen::<u16>() as u64, 0),
+            (rng.gen::<u16>() as u64, rng.gen::<u16>() as u64, 1),
+            (max, 0, 1),
+            (max, max, 1),
+        ];
+        let ops: Vec<_> = inputs
+            .iter()
+            .map(|&(x, y, c)| {
+                let sum = x + y + c;
+                (x, y, c, sum & max, sum >> BITS)
+            })
+            .collect();
+
+        let gate = BinaryAddGate::<F, D, BITS> {
+            num_ops: ops.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&ops),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Dropping the carry of an overflowing sum, or keeping the full sum as the result, should
+        // both be rejected.
+        for wrong_op in [(max, 1, 0, 0, 0), (max, 1, 0, max + 1, 0)] {
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&[wrong_op]),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            let gate = BinaryAddGate::<F, D, BITS> {
+                num_ops: 1,
+                _phantom: PhantomData,
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "A wrong sum should not satisfy the constraints."
+            );
+        }
+    }
+
+    /// Adds two multi-limb numbers by chaining the carry out of each operation into the carry in
+    /// of the next, and checks the resulting limbs and final carry.
+    #[test]
+    fn test_carry_chain() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let max = (1u64 << BITS) - 1;
+        // The low limbs all overflow, so a carry ripples through every operation.
+        let x_limbs = [max, max, 1, max];
+        let y_limbs = [1, 0, max, 0];
+        let expected_limbs = [0, 0, 1, 0];
+        let expected_carry = 1;
+
+        let gate = BinaryAddGate::<F, D, BITS>::new_from_config(&builder.config);
+        let mut carry = builder.zero();
+        for j in 0..x_limbs.len() {
+            let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+            let x = builder.add_virtual_target();
+            let y = builder.add_virtual_target();
+            builder.connect(x, Target::wire(gate_index, gate.wire_ith_input_x(i)));
+            builder.connect(y, Target::wire(gate_index, gate.wire_ith_input_y(i)));
+            builder.connect(
+                carry,
+                Target::wire(gate_index, gate.wire_ith_input_carry(i)),
+            );
+            pw.set_target(x, F::from_canonical_u64(x_limbs[j]));
+            pw.set_target(y, F::from_canonical_u64(y_limbs[j]));
+
+            let result = Target::wire(gate_index, gate.wire_ith_output_result(i));
+            let expected = builder.constant(F::from_canonical_u64(expected_limbs[j]));
+            builder.connect(result, expected);
+
+            carry = Target::wire(gate_index, gate.wire_ith_output_carry(i));
+        }
+        let expected = builder.constant(F::from_canonical_u64(expected_carry));
+        builder.connect(carry, expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
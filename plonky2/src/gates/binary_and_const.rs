@@ -0,0 +1,385 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute `x & MASK` for a `BITS`-bit value `x` and a constant `MASK < 2^BITS` baked
+/// into the gate, e.g. to extract a bit field. The input is split into bits, which also
+/// range-checks it. Since `MASK` is fixed, the output is the sum of the input bits selected by
+/// `MASK`, which is linear in the bits and cheaper than a general AND.
+#[derive(Copy, Clone, Debug)]
+pub struct BinaryAndConstGate<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const MASK: u64,
+> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const MASK: u64>
+    BinaryAndConstGate<F, D, BITS, MASK>
+{
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS < 64, "Values must fit in the field");
+        assert!(MASK < 1 << BITS, "The mask must fit in BITS bits");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 2 + BITS;
+        let routed_wires_per_op = 2;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i + 1
+    }
+
+    pub fn wire_ith_input_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        2 * self.num_ops + BITS * i + j
+    }
+
+    fn mask_bit(j: usize) -> bool {
+        (MASK >> j) & 1 == 1
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const MASK: u64> Gate<F, D>
+    for BinaryAndConstGate<F, D, BITS, MASK>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}, MASK={}>", self, BITS, MASK)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = F::Extension::ZERO;
+            let mut computed_output = F::Extension::ZERO;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(bit * (bit - F::Extension::ONE));
+
+                computed_input = computed_input.double() + bit;
+                computed_output = computed_output.double();
+                if Self::mask_bit(j) {
+                    computed_output += bit;
+                }
+            }
+
+            constraints.push(computed_input - input);
+            constraints.push(computed_output - output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let two = F::TWO;
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = builder.zero_extension();
+            let mut computed_output = builder.zero_extension();
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+
+                computed_input = builder.mul_const_add_extension(two, computed_input, bit);
+                computed_output = if Self::mask_bit(j) {
+                    builder.mul_const_add_extension(two, computed_output, bit)
+                } else {
+                    builder.mul_const_extension(two, computed_output)
+                };
+            }
+
+            constraints.push(builder.sub_extension(computed_input, input));
+            constraints.push(builder.sub_extension(computed_output, output));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BinaryAndConstGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (2 + BITS)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (BITS + 2)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const MASK: u64>
+    PackedEvaluableBase<F, D> for BinaryAndConstGate<F, D, BITS, MASK>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = P::ZEROS;
+            let mut computed_output = P::ZEROS;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                yield_constr.one(bit * (bit - F::ONE));
+
+                computed_input = computed_input * F::TWO + bit;
+                computed_output *= F::TWO;
+                if Self::mask_bit(j) {
+                    computed_output += bit;
+                }
+            }
+
+            yield_constr.one(computed_input - input);
+            yield_constr.one(computed_output - output);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BinaryAndConstGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const MASK: u64,
+> {
+    gate: BinaryAndConstGate<F, D, BITS, MASK>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const MASK: u64>
+    SimpleGenerator<F> for BinaryAndConstGenerator<F, D, BITS, MASK>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(input >> BITS == 0, "Input has more than {} bits", BITS);
+
+        for j in 0..BITS {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_input_bit(self.i, j)),
+                F::from_canonical_u64((input >> j) & 1),
+            );
+        }
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output(self.i)),
+            F::from_canonical_u64(input & MASK),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::binary_and_const::BinaryAndConstGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    const MASK: u64 = 0x0FF0_F00F;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(
+            BinaryAndConstGate::<GoldilocksField, 4, 32, MASK> {
+                num_ops: 3,
+                _phantom: PhantomData,
+            },
+        )
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BinaryAndConstGate::<GoldilocksField, D, 32, MASK> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FF = <C as GenericConfig<D>>::FE;
+    const BITS: usize = 32;
+
+    #[test]
+    fn test_gate_constraint() {
+        fn get_wires(inputs: &[u64], outputs: &[u64]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            for (&input, &output) in inputs.iter().zip(outputs) {
+                v0.push(F::from_canonical_u64(input));
+                v0.push(F::from_canonical_u64(output));
+                v1.extend((0..BITS).map(|j| F::from_canonical_u64((input >> j) & 1)));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let inputs: Vec<u64> = vec![0, u32::MAX as u64, rng.gen::<u32>() as u64];
+        let outputs: Vec<u64> = inputs.iter().map(|&x| x & MASK).collect();
+        let gate = BinaryAndConstGate::<F, D, BITS, MASK> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Passing through a bit which the mask clears must violate the constraints.
+        let wrong_outputs: Vec<u64> = inputs.iter().map(|&x| (x & MASK) | 1 << 7).collect();
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &wrong_outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Incorrect outputs should not satisfy the constraints."
+        );
+    }
+
+    #[test]
+    fn test_binary_and_const() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let mut rng = rand::thread_rng();
+        let gate = BinaryAndConstGate::<F, D, BITS, MASK>::new_from_config(&builder.config);
+        for input_value in [0, u32::MAX as u64, rng.gen::<u32>() as u64] {
+            let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+            let x = builder.add_virtual_target();
+            builder.connect(x, Target::wire(gate_index, gate.wire_ith_input(i)));
+            pw.set_target(x, F::from_canonical_u64(input_value));
+
+            let output = Target::wire(gate_index, gate.wire_ith_output(i));
+            let expected = builder.constant(F::from_canonical_u64(input_value & MASK));
+            builder.connect(output, expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
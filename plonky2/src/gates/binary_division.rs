@@ -0,0 +1,461 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute the quotient and remainder of the division of `dividend` by `divisor`, both
+/// `BITS`-bit values (we assume they are range-checked beforehand).
+///
+/// The quotient and remainder are range-checked to `BITS` bits, and `divisor - remainder - 1` is
+/// range-checked to `BITS` bits to enforce `remainder < divisor`. In particular, this makes the
+/// constraints unsatisfiable when `divisor = 0`, as `divisor - remainder - 1` is then negative.
+#[derive(Copy, Clone, Debug)]
+pub struct BinaryDivisionGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize>
+    BinaryDivisionGate<F, D, BITS>
+{
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(
+            BITS <= 32,
+            "Products of more than 32 bits would overflow the field"
+        );
+        assert_eq!(BITS % Self::limb_bits(), 0);
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 4 + Self::num_limbs();
+        let routed_wires_per_op = 4;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_dividend(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i
+    }
+    pub fn wire_ith_divisor(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 1
+    }
+
+    pub fn wire_ith_output_quotient(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 2
+    }
+    pub fn wire_ith_output_remainder(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 3
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // The number of limbs of each of the three range-checked values.
+    fn num_limbs_per_value() -> usize {
+        BITS / Self::limb_bits()
+    }
+    // We have limbs for the quotient, the remainder and `divisor - remainder - 1`, in that order.
+    pub fn num_limbs() -> usize {
+        3 * Self::num_limbs_per_value()
+    }
+
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        4 * self.num_ops + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for BinaryDivisionGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let dividend = vars.local_wires[self.wire_ith_dividend(i)];
+            let divisor = vars.local_wires[self.wire_ith_divisor(i)];
+            let quotient = vars.local_wires[self.wire_ith_output_quotient(i)];
+            let remainder = vars.local_wires[self.wire_ith_output_remainder(i)];
+
+            constraints.push(quotient * divisor + remainder - dividend);
+
+            let values = [quotient, remainder, divisor - remainder - F::Extension::ONE];
+            let limbs_per_value = Self::num_limbs_per_value();
+            let base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            for (k, value) in values.into_iter().enumerate() {
+                let mut combined_limbs = F::Extension::ZERO;
+                for j in (0..limbs_per_value).rev() {
+                    let this_limb =
+                        vars.local_wires[self.wire_ith_output_jth_limb(i, k * limbs_per_value + j)];
+                    let max_limb = 1 << Self::limb_bits();
+                    let product = (0..max_limb)
+                        .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                        .product();
+                    constraints.push(product);
+
+                    combined_limbs = base * combined_limbs + this_limb;
+                }
+                constraints.push(combined_limbs - value);
+            }
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        for i in 0..self.num_ops {
+            let dividend = vars.local_wires[self.wire_ith_dividend(i)];
+            let divisor = vars.local_wires[self.wire_ith_divisor(i)];
+            let quotient = vars.local_wires[self.wire_ith_output_quotient(i)];
+            let remainder = vars.local_wires[self.wire_ith_output_remainder(i)];
+
+            let computed_dividend = builder.mul_add_extension(quotient, divisor, remainder);
+            constraints.push(builder.sub_extension(computed_dividend, dividend));
+
+            let one = builder.one_extension();
+            let divisor_minus_remainder = builder.sub_extension(divisor, remainder);
+            let values = [
+                quotient,
+                remainder,
+                builder.sub_extension(divisor_minus_remainder, one),
+            ];
+            let limbs_per_value = Self::num_limbs_per_value();
+            let base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            for (k, value) in values.into_iter().enumerate() {
+                let mut combined_limbs = builder.zero_extension();
+                for j in (0..limbs_per_value).rev() {
+                    let this_limb =
+                        vars.local_wires[self.wire_ith_output_jth_limb(i, k * limbs_per_value + j)];
+                    let max_limb = 1 << Self::limb_bits();
+
+                    let mut product = builder.one_extension();
+                    for x in 0..max_limb {
+                        let x_target =
+                            builder.constant_extension(F::Extension::from_canonical_usize(x));
+                        let diff = builder.sub_extension(this_limb, x_target);
+                        product = builder.mul_extension(product, diff);
+                    }
+                    constraints.push(product);
+
+                    combined_limbs = builder.mul_add_extension(base, combined_limbs, this_limb);
+                }
+                constraints.push(builder.sub_extension(combined_limbs, value));
+            }
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BinaryDivisionGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                        _phantom: PhantomData,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    // Zero wires don't satisfy the constraints, as a zero divisor is rejected.
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(vec![
+            (self.wire_ith_dividend(op), F::ZERO),
+            (self.wire_ith_divisor(op), F::ONE),
+        ])
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (4 + Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (4 + Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for BinaryDivisionGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let dividend = vars.local_wires[self.wire_ith_dividend(i)];
+            let divisor = vars.local_wires[self.wire_ith_divisor(i)];
+            let quotient = vars.local_wires[self.wire_ith_output_quotient(i)];
+            let remainder = vars.local_wires[self.wire_ith_output_remainder(i)];
+
+            yield_constr.one(quotient * divisor + remainder - dividend);
+
+            let values = [quotient, remainder, divisor - remainder - P::ONES];
+            let limbs_per_value = Self::num_limbs_per_value();
+            let base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            for (k, value) in values.into_iter().enumerate() {
+                let mut combined_limbs = P::ZEROS;
+                for j in (0..limbs_per_value).rev() {
+                    let this_limb =
+                        vars.local_wires[self.wire_ith_output_jth_limb(i, k * limbs_per_value + j)];
+                    let max_limb = 1 << Self::limb_bits();
+                    let product = (0..max_limb)
+                        .map(|x| this_limb - F::from_canonical_usize(x))
+                        .product();
+                    yield_constr.one(product);
+
+                    combined_limbs = combined_limbs * base + this_limb;
+                }
+                yield_constr.one(combined_limbs - value);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BinaryDivisionGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: BinaryDivisionGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for BinaryDivisionGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        vec![
+            local_target(self.gate.wire_ith_dividend(self.i)),
+            local_target(self.gate.wire_ith_divisor(self.i)),
+        ]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input)).to_canonical_u64();
+
+        let dividend = get_local_wire(self.gate.wire_ith_dividend(self.i));
+        let divisor = get_local_wire(self.gate.wire_ith_divisor(self.i));
+        assert_ne!(divisor, 0, "Division by zero");
+
+        let quotient = dividend / divisor;
+        let remainder = dividend % divisor;
+
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_quotient(self.i)),
+            F::from_canonical_u64(quotient),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_remainder(self.i)),
+            F::from_canonical_u64(remainder),
+        );
+
+        let limbs_per_value = BinaryDivisionGate::<F, D, BITS>::num_limbs_per_value();
+        let limb_base = 1 << BinaryDivisionGate::<F, D, BITS>::limb_bits();
+        let values = [quotient, remainder, divisor - remainder - 1];
+        for (k, value) in values.into_iter().enumerate() {
+            let limbs = (0..limbs_per_value).scan(value, |acc, _| {
+                let tmp = *acc % limb_base;
+                *acc /= limb_base;
+                Some(F::from_canonical_u64(tmp))
+            });
+
+            for (j, limb) in limbs.enumerate() {
+                let wire = local_wire(
+                    self.gate
+                        .wire_ith_output_jth_limb(self.i, k * limbs_per_value + j),
+                );
+                out_buffer.set_wire(wire, limb);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::binary_division::BinaryDivisionGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(BinaryDivisionGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BinaryDivisionGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FF = <C as GenericConfig<D>>::FE;
+    const BITS: usize = 32;
+
+    /// Builds the wires of a gate performing the given divisions, using the given quotients and
+    /// remainders. The value `divisor - remainder - 1` is decomposed modulo `2^BITS`.
+    fn get_wires(ops: &[(u64, u64, u64, u64)]) -> Vec<FF> {
+        let mut v0 = Vec::new();
+        let mut v1 = Vec::new();
+
+        let limbs_per_value = BITS / BinaryDivisionGate::<F, D, BITS>::limb_bits();
+        let limb_base = 1 << BinaryDivisionGate::<F, D, BITS>::limb_bits();
+        for &(dividend, divisor, quotient, remainder) in ops {
+            v0.push(F::from_canonical_u64(dividend));
+            v0.push(F::from_canonical_u64(divisor));
+            v0.push(F::from_canonical_u64(quotient));
+            v0.push(F::from_canonical_u64(remainder));
+
+            let diff = divisor.wrapping_sub(remainder + 1) & ((1 << BITS) - 1);
+            for mut value in [quotient, remainder, diff] {
+                for _ in 0..limbs_per_value {
+                    v1.push(F::from_canonical_u64(value % limb_base));
+                    value /= limb_base;
+                }
+            }
+        }
+
+        v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        let mut rng = rand::thread_rng();
+        let max = u32::MAX as u64;
+        let dividends = [rng.gen::<u32>() as u64, max, 7, max];
+        let divisors = [rng.gen::<u16>() as u64 + 1, 1, 8, max];
+        let ops: Vec<_> = dividends
+            .iter()
+            .zip(divisors)
+            .map(|(&x, y)| (x, y, x / y, x % y))
+            .collect();
+
+        let gate = BinaryDivisionGate::<F, D, BITS> {
+            num_ops: ops.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&ops),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+    }
+
+    #[test]
+    fn test_gate_constraint_zero_divisor() {
+        let gate = BinaryDivisionGate::<F, D, BITS> {
+            num_ops: 1,
+            _phantom: PhantomData,
+        };
+
+        // With a zero divisor, `dividend = quotient * 0 + remainder` forces the remainder to be
+        // the dividend, which is then not less than the divisor.
+        for (dividend, quotient) in [(0, 0), (5, 0), (5, 3)] {
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&[(dividend, 0, quotient, dividend)]),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "A zero divisor should not satisfy the constraints."
+            );
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute `C * x + addend` for `BITS`-bit values `x` and `addend` (we assume they are
+/// range-checked beforehand) and a constant `C < 2^BITS` baked into the gate. The result is split
+/// into a low and a high half of `BITS` bits each. Since `C` is fixed, each operation only routes
+/// two input wires instead of three.
+#[derive(Copy, Clone, Debug)]
+pub struct BinaryMulConstGate<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const C: u64,
+> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const C: u64>
+    BinaryMulConstGate<F, D, BITS, C>
+{
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(
+            BITS <= 32,
+            "Products of more than 32 bits would overflow the field"
+        );
+        assert_eq!(BITS % Self::limb_bits(), 0);
+        assert!(C < 1 << BITS, "The constant must fit in BITS bits");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 4 + Self::num_limbs();
+        let routed_wires_per_op = 4;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i
+    }
+    pub fn wire_ith_addend(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 1
+    }
+
+    pub fn wire_ith_output_low_half(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 2
+    }
+    pub fn wire_ith_output_high_half(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 3
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // We have limbs for the `2 * BITS` bits of the output.
+    pub fn num_limbs() -> usize {
+        2 * BITS / Self::limb_bits()
+    }
+
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        4 * self.num_ops + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const C: u64> Gate<F, D>
+    for BinaryMulConstGate<F, D, BITS, C>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}, C={}>", self, BITS, C)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let addend = vars.local_wires[self.wire_ith_addend(i)];
+
+            let computed_output = input * F::Extension::from_canonical_u64(C) + addend;
+
+            let output_low = vars.local_wires[self.wire_ith_output_low_half(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high_half(i)];
+
+            let base = F::Extension::from_canonical_u64(1 << BITS);
+            let combined_output = output_high * base + output_low;
+
+            constraints.push(combined_output - computed_output);
+
+            let mut combined_low_limbs = F::Extension::ZERO;
+            let mut combined_high_limbs = F::Extension::ZERO;
+            let midpoint = Self::num_limbs() / 2;
+            let base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                    .product();
+                constraints.push(product);
+
+                if j < midpoint {
+                    combined_low_limbs = base * combined_low_limbs + this_limb;
+                } else {
+                    combined_high_limbs = base * combined_high_limbs + this_limb;
+                }
+            }
+            constraints.push(combined_low_limbs - output_low);
+            constraints.push(combined_high_limbs - output_high);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let addend = vars.local_wires[self.wire_ith_addend(i)];
+
+            let one = builder.one_extension();
+            let computed_output =
+                builder.arithmetic_extension(F::from_canonical_u64(C), F::ONE, input, one, addend);
+
+            let output_low = vars.local_wires[self.wire_ith_output_low_half(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high_half(i)];
+
+            let base: F::Extension = F::from_canonical_u64(1 << BITS).into();
+            let base_target = builder.constant_extension(base);
+            let combined_output = builder.mul_add_extension(output_high, base_target, output_low);
+
+            constraints.push(builder.sub_extension(combined_output, computed_output));
+
+            let mut combined_low_limbs = builder.zero_extension();
+            let mut combined_high_limbs = builder.zero_extension();
+            let midpoint = Self::num_limbs() / 2;
+            let base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+
+                let mut product = builder.one_extension();
+                for x in 0..max_limb {
+                    let x_target =
+                        builder.constant_extension(F::Extension::from_canonical_usize(x));
+                    let diff = builder.sub_extension(this_limb, x_target);
+                    product = builder.mul_extension(product, diff);
+                }
+                constraints.push(product);
+
+                if j < midpoint {
+                    combined_low_limbs =
+                        builder.mul_add_extension(base, combined_low_limbs, this_limb);
+                } else {
+                    combined_high_limbs =
+                        builder.mul_add_extension(base, combined_high_limbs, this_limb);
+                }
+            }
+
+            constraints.push(builder.sub_extension(combined_low_limbs, output_low));
+            constraints.push(builder.sub_extension(combined_high_limbs, output_high));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BinaryMulConstGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                        _phantom: PhantomData,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (4 + Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (3 + Self::num_limbs())
+    }
+
+    fn wire_labels(&self) -> Vec<(usize, String)> {
+        let mut labels = Vec::new();
+        for i in 0..self.num_ops {
+            labels.push((self.wire_ith_input(i), format!("input[{}]", i)));
+            labels.push((self.wire_ith_addend(i), format!("addend[{}]", i)));
+            labels.push((
+                self.wire_ith_output_low_half(i),
+                format!("output_low_half[{}]", i),
+            ));
+            labels.push((
+                self.wire_ith_output_high_half(i),
+                format!("output_high_half[{}]", i),
+            ));
+            for j in 0..Self::num_limbs() {
+                labels.push((
+                    self.wire_ith_output_jth_limb(i, j),
+                    format!("output_limb[{}][{}]", i, j),
+                ));
+            }
+        }
+        labels
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const C: u64>
+    PackedEvaluableBase<F, D> for BinaryMulConstGate<F, D, BITS, C>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let addend = vars.local_wires[self.wire_ith_addend(i)];
+
+            let computed_output = input * F::from_canonical_u64(C) + addend;
+
+            let output_low = vars.local_wires[self.wire_ith_output_low_half(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high_half(i)];
+
+            let base = F::from_canonical_u64(1 << BITS);
+            let combined_output = output_high * base + output_low;
+
+            yield_constr.one(combined_output - computed_output);
+
+            let mut combined_low_limbs = P::ZEROS;
+            let mut combined_high_limbs = P::ZEROS;
+            let midpoint = Self::num_limbs() / 2;
+            let base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::from_canonical_usize(x))
+                    .product();
+                yield_constr.one(product);
+
+                if j < midpoint {
+                    combined_low_limbs = combined_low_limbs * base + this_limb;
+                } else {
+                    combined_high_limbs = combined_high_limbs * base + this_limb;
+                }
+            }
+            yield_constr.one(combined_low_limbs - output_low);
+            yield_constr.one(combined_high_limbs - output_high);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BinaryMulConstGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const C: u64,
+> {
+    gate: BinaryMulConstGate<F, D, BITS, C>,
+    gate_index: usize,
+    i: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const C: u64>
+    SimpleGenerator<F> for BinaryMulConstGenerator<F, D, BITS, C>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        vec![
+            local_target(self.gate.wire_ith_input(self.i)),
+            local_target(self.gate.wire_ith_addend(self.i)),
+        ]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input));
+
+        let input = get_local_wire(self.gate.wire_ith_input(self.i));
+        let addend = get_local_wire(self.gate.wire_ith_addend(self.i));
+
+        let output = input * F::from_canonical_u64(C) + addend;
+        let output_u64 = output.to_canonical_u64();
+
+        let output_high_u64 = output_u64 >> BITS;
+        let output_low_u64 = output_u64 & ((1 << BITS) - 1);
+
+        let output_high = F::from_canonical_u64(output_high_u64);
+        let output_low = F::from_canonical_u64(output_low_u64);
+
+        let output_high_wire = local_wire(self.gate.wire_ith_output_high_half(self.i));
+        let output_low_wire = local_wire(self.gate.wire_ith_output_low_half(self.i));
+
+        out_buffer.set_wire(output_high_wire, output_high);
+        out_buffer.set_wire(output_low_wire, output_low);
+
+        let num_limbs = BinaryMulConstGate::<F, D, BITS, C>::num_limbs();
+        let limb_base = 1 << BinaryMulConstGate::<F, D, BITS, C>::limb_bits();
+        let output_limbs: Vec<_> = (0..num_limbs)
+            .scan(output_u64, |acc, _| {
+                let tmp = *acc % limb_base;
+                *acc /= limb_base;
+                Some(F::from_canonical_u64(tmp))
+            })
+            .collect();
+
+        for j in 0..num_limbs {
+            let wire = local_wire(self.gate.wire_ith_output_jth_limb(self.i, j));
+            out_buffer.set_wire(wire, output_limbs[j]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::binary_mul_const::BinaryMulConstGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(BinaryMulConstGate::<
+            GoldilocksField,
+            4,
+            16,
+            12345,
+        > {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BinaryMulConstGate::<GoldilocksField, D, 16, 12345> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    fn test_gate_constraint<const CONST: u64>() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 16;
+        const NUM_MUL_CONST_OPS: usize = 3;
+
+        fn get_wires<const CONST: u64>(inputs: Vec<u64>, addends: Vec<u64>) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+
+            let limb_bits = BinaryMulConstGate::<F, D, BITS, CONST>::limb_bits();
+            let num_limbs = BinaryMulConstGate::<F, D, BITS, CONST>::num_limbs();
+            let limb_base = 1 << limb_bits;
+            for c in 0..NUM_MUL_CONST_OPS {
+                let input = inputs[c];
+                let addend = addends[c];
+
+                let mut output = input * CONST + addend;
+                let output_low = output & ((1 << BITS) - 1);
+                let output_high = output >> BITS;
+
+                let mut output_limbs = Vec::with_capacity(num_limbs);
+                for _i in 0..num_limbs {
+                    output_limbs.push(output % limb_base);
+                    output /= limb_base;
+                }
+                let mut output_limbs_f: Vec<_> = output_limbs
+                    .into_iter()
+                    .map(F::from_canonical_u64)
+                    .collect();
+
+                v0.push(F::from_canonical_u64(input));
+                v0.push(F::from_canonical_u64(addend));
+                v0.push(F::from_canonical_u64(output_low));
+                v0.push(F::from_canonical_u64(output_high));
+                v1.append(&mut output_limbs_f);
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut inputs: Vec<_> = (0..NUM_MUL_CONST_OPS - 1)
+            .map(|_| rng.gen::<u16>() as u64)
+            .collect();
+        let mut addends: Vec<_> = (0..NUM_MUL_CONST_OPS - 1)
+            .map(|_| rng.gen::<u16>() as u64)
+            .collect();
+        // Include the largest possible output.
+        inputs.push(u16::MAX as u64);
+        addends.push(u16::MAX as u64);
+
+        let gate = BinaryMulConstGate::<F, D, BITS, CONST> {
+            num_ops: NUM_MUL_CONST_OPS,
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires::<CONST>(inputs, addends),
+            public_inputs_hash: &HashOut::rand(),
+        };
+
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+    }
+
+    #[test]
+    fn test_gate_constraint_zero() {
+        test_gate_constraint::<0>();
+    }
+
+    #[test]
+    fn test_gate_constraint_one() {
+        test_gate_constraint::<1>();
+    }
+
+    #[test]
+    fn test_gate_constraint_small() {
+        test_gate_constraint::<3>();
+    }
+
+    #[test]
+    fn test_gate_constraint_max() {
+        test_gate_constraint::<{ u16::MAX as u64 }>();
+    }
+}
@@ -0,0 +1,416 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute the two's-complement negation `~x + 1 = (2^BITS - x) mod 2^BITS` of a
+/// `BITS`-bit value `x` (we assume it is range-checked beforehand). We enforce
+/// `2^BITS - x = output + 2^BITS * overflow` for a boolean `overflow`, and range-check `output` to
+/// `BITS` bits. The `overflow` flag is the carry out of `~x + 1`, which is set exactly when `x = 0`.
+#[derive(Copy, Clone, Debug)]
+pub struct BinaryNegGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> BinaryNegGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(
+            BITS < 64,
+            "Values of 64 or more bits would overflow the field"
+        );
+        assert_eq!(BITS % Self::limb_bits(), 0);
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 3 + Self::num_limbs();
+        let routed_wires_per_op = 3;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 1
+    }
+    pub fn wire_ith_overflow(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 2
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // We have limbs for the `BITS` bits of the output.
+    pub fn num_limbs() -> usize {
+        BITS / Self::limb_bits()
+    }
+
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        3 * self.num_ops + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for BinaryNegGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let overflow = vars.local_wires[self.wire_ith_overflow(i)];
+            let base = F::Extension::from_canonical_u64(1 << BITS);
+
+            constraints.push(overflow * (overflow - F::Extension::ONE));
+            constraints.push(output + base * overflow - (base - input));
+
+            // Range-check the output to be at most BITS bits.
+            let mut combined_limbs = F::Extension::ZERO;
+            let limb_base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                    .product();
+                constraints.push(product);
+
+                combined_limbs = limb_base * combined_limbs + this_limb;
+            }
+            constraints.push(combined_limbs - output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let overflow = vars.local_wires[self.wire_ith_overflow(i)];
+            let base = builder.constant_extension(F::Extension::from_canonical_u64(1 << BITS));
+
+            constraints.push(builder.mul_sub_extension(overflow, overflow, overflow));
+
+            let combined_output = builder.mul_add_extension(base, overflow, output);
+            let negated_input = builder.sub_extension(base, input);
+            constraints.push(builder.sub_extension(combined_output, negated_input));
+
+            // Range-check the output to be at most BITS bits.
+            let mut combined_limbs = builder.zero_extension();
+            let limb_base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let mut product = builder.one_extension();
+                for x in 0..max_limb {
+                    let x_target =
+                        builder.constant_extension(F::Extension::from_canonical_usize(x));
+                    let diff = builder.sub_extension(this_limb, x_target);
+                    product = builder.mul_extension(product, diff);
+                }
+                constraints.push(product);
+
+                combined_limbs = builder.mul_add_extension(limb_base, combined_limbs, this_limb);
+            }
+            constraints.push(builder.sub_extension(combined_limbs, output));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BinaryNegGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                        _phantom: PhantomData,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    // Zero wires don't satisfy the constraints, as negating zero sets the overflow flag.
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(vec![(self.wire_ith_input(op), F::ZERO)])
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (3 + Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (3 + Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for BinaryNegGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let overflow = vars.local_wires[self.wire_ith_overflow(i)];
+            let base = F::from_canonical_u64(1 << BITS);
+
+            yield_constr.one(overflow * (overflow - F::ONE));
+            yield_constr.one(output + overflow * base + input - base);
+
+            // Range-check the output to be at most BITS bits.
+            let mut combined_limbs = P::ZEROS;
+            let limb_base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::from_canonical_usize(x))
+                    .product();
+                yield_constr.one(product);
+
+                combined_limbs = combined_limbs * limb_base + this_limb;
+            }
+            yield_constr.one(combined_limbs - output);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BinaryNegGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: BinaryNegGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for BinaryNegGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(input < 1 << BITS, "Input must fit in BITS bits");
+
+        let negated = (1 << BITS) - input;
+        let output = negated & ((1 << BITS) - 1);
+        let overflow = negated >> BITS;
+
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output(self.i)),
+            F::from_canonical_u64(output),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_overflow(self.i)),
+            F::from_canonical_u64(overflow),
+        );
+
+        let limb_bits = BinaryNegGate::<F, D, BITS>::limb_bits();
+        let limb_mask = (1 << limb_bits) - 1;
+        for j in 0..BinaryNegGate::<F, D, BITS>::num_limbs() {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_output_jth_limb(self.i, j)),
+                F::from_canonical_u64((output >> (limb_bits * j)) & limb_mask),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::binary_neg::BinaryNegGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(BinaryNegGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BinaryNegGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FF = <C as GenericConfig<D>>::FE;
+    const BITS: usize = 32;
+
+    /// Builds the wires of a gate negating the given inputs, using the given outputs and overflow
+    /// flags.
+    fn get_wires(ops: &[(u64, u64, u64)]) -> Vec<FF> {
+        let num_limbs = BinaryNegGate::<F, D, BITS>::num_limbs();
+        let limb_bits = BinaryNegGate::<F, D, BITS>::limb_bits();
+        let limb_mask = (1 << limb_bits) - 1;
+
+        let mut v0 = Vec::new();
+        let mut v1 = Vec::new();
+        for &(input, output, overflow) in ops {
+            v0.push(F::from_canonical_u64(input));
+            v0.push(F::from_canonical_u64(output));
+            v0.push(F::from_canonical_u64(overflow));
+            for j in 0..num_limbs {
+                v1.push(F::from_canonical_u64(
+                    (output >> (limb_bits * j)) & limb_mask,
+                ));
+            }
+        }
+
+        v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        let max = u32::MAX as u64;
+        let x = rand::thread_rng().gen::<u32>() as u64;
+        // `~x + 1` only carries out for `x == 0`, and `-1` is all ones.
+        let ops = [
+            (0, 0, 1),
+            (1, max, 0),
+            (max, 1, 0),
+            (x, x.wrapping_neg() & max, (x == 0) as u64),
+        ];
+
+        let gate = BinaryNegGate::<F, D, BITS> {
+            num_ops: ops.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&ops),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+    }
+
+    #[test]
+    fn test_gate_constraint_rejects_wrong_overflow() {
+        let gate = BinaryNegGate::<F, D, BITS> {
+            num_ops: 1,
+            _phantom: PhantomData,
+        };
+
+        // For `x == 0`, the output `0` requires the overflow flag, and the unreduced output `2^BITS`
+        // doesn't fit in `BITS` bits. For `x == 1`, claiming an overflow leaves a negative output.
+        let max = u32::MAX as u64;
+        for wrong_op in [(0, 0, 0), (0, 1 << BITS, 0), (1, max, 1), (1, 0, 1)] {
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&[wrong_op]),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "A wrong negation should not satisfy the constraints."
+            );
+        }
+    }
+}
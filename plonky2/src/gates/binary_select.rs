@@ -0,0 +1,314 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute `cond ? a : b` for `BITS`-bit words `a` and `b` and a boolean `cond`, as
+/// `out = cond * (a - b) + b`. The words are assumed to be range-checked already; the output, being
+/// one of them, then needs no check of its own.
+#[derive(Copy, Clone, Debug)]
+pub struct BinarySelectGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> BinarySelectGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS < 64, "Values must fit in the field");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 4;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / wires_per_op)
+    }
+
+    pub fn wire_ith_cond(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i
+    }
+    pub fn wire_ith_a(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 1
+    }
+    pub fn wire_ith_b(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 2
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 3
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for BinarySelectGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let cond = vars.local_wires[self.wire_ith_cond(i)];
+            let a = vars.local_wires[self.wire_ith_a(i)];
+            let b = vars.local_wires[self.wire_ith_b(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            constraints.push(cond * (cond - F::Extension::ONE));
+            constraints.push(cond * (a - b) + b - output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let cond = vars.local_wires[self.wire_ith_cond(i)];
+            let a = vars.local_wires[self.wire_ith_a(i)];
+            let b = vars.local_wires[self.wire_ith_b(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            constraints.push(builder.mul_sub_extension(cond, cond, cond));
+            let selected = builder.select_ext_generalized(cond, a, b);
+            constraints.push(builder.sub_extension(selected, output));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BinarySelectGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * 4
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * 2
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for BinarySelectGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let cond = vars.local_wires[self.wire_ith_cond(i)];
+            let a = vars.local_wires[self.wire_ith_a(i)];
+            let b = vars.local_wires[self.wire_ith_b(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            yield_constr.one(cond * (cond - F::ONE));
+            yield_constr.one(cond * (a - b) + b - output);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BinarySelectGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: BinarySelectGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for BinarySelectGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        [
+            self.gate.wire_ith_cond(self.i),
+            self.gate.wire_ith_a(self.i),
+            self.gate.wire_ith_b(self.i),
+        ]
+        .iter()
+        .map(|&input| Target::wire(self.gate_index, input))
+        .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let get_local_wire = |input| {
+            witness.get_wire(Wire {
+                gate: self.gate_index,
+                input,
+            })
+        };
+
+        let cond = get_local_wire(self.gate.wire_ith_cond(self.i));
+        let a = get_local_wire(self.gate.wire_ith_a(self.i));
+        let b = get_local_wire(self.gate.wire_ith_b(self.i));
+        debug_assert!(cond.is_zero() || cond.is_one(), "Condition is not boolean");
+
+        let output = if cond.is_one() { a } else { b };
+        debug_assert!(
+            output.to_canonical_u64() >> BITS == 0,
+            "Output has more than {} bits",
+            BITS
+        );
+
+        out_buffer.set_wire(
+            Wire {
+                gate: self.gate_index,
+                input: self.gate.wire_ith_output(self.i),
+            },
+            output,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::binary_select::BinarySelectGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(BinarySelectGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BinarySelectGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        fn get_wires(ops: &[(bool, u32, u32, u32)]) -> Vec<FF> {
+            ops.iter()
+                .flat_map(|&(cond, a, b, output)| {
+                    [
+                        F::from_bool(cond),
+                        F::from_canonical_u32(a),
+                        F::from_canonical_u32(b),
+                        F::from_canonical_u32(output),
+                    ]
+                })
+                .map(|x| x.into())
+                .collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let a = rng.gen::<u32>();
+        let b = a ^ rng.gen_range(1..=u32::MAX);
+        // Both branches, with the unselected word differing from the output.
+        let ops = [(true, a, b, a), (false, a, b, b)];
+        let gate = BinarySelectGate::<F, D, 32> {
+            num_ops: ops.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&ops),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        for wrong_op in [(true, a, b, b), (false, a, b, a)] {
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&[wrong_op, wrong_op]),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "Selecting the wrong branch should not satisfy the constraints."
+            );
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute `x ^ C` for a `BITS`-bit value `x` and a constant `C < 2^BITS` baked into the
+/// gate. The input is split into bits, which also range-checks it. Since `C` is fixed, each output
+/// bit is either an input bit or its complement, so the output is a linear combination of the
+/// input bits, and is range-checked by construction.
+#[derive(Copy, Clone, Debug)]
+pub struct BinaryXorConstGate<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const C: u64,
+> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const C: u64>
+    BinaryXorConstGate<F, D, BITS, C>
+{
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS < 64, "Values must fit in the field");
+        assert!(C < 1 << BITS, "The constant must fit in BITS bits");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 2 + BITS;
+        let routed_wires_per_op = 2;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i + 1
+    }
+
+    pub fn wire_ith_input_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        2 * self.num_ops + BITS * i + j
+    }
+
+    fn const_bit(j: usize) -> bool {
+        (C >> j) & 1 == 1
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const C: u64> Gate<F, D>
+    for BinaryXorConstGate<F, D, BITS, C>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}, C={}>", self, BITS, C)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = F::Extension::ZERO;
+            let mut computed_output = F::Extension::ZERO;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(bit * (bit - F::Extension::ONE));
+
+                let output_bit = if Self::const_bit(j) {
+                    F::Extension::ONE - bit
+                } else {
+                    bit
+                };
+                computed_input = computed_input.double() + bit;
+                computed_output = computed_output.double() + output_bit;
+            }
+
+            constraints.push(computed_input - input);
+            constraints.push(computed_output - output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let one = builder.one_extension();
+        let two = F::TWO;
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = builder.zero_extension();
+            let mut computed_output = builder.zero_extension();
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+
+                let output_bit = if Self::const_bit(j) {
+                    builder.sub_extension(one, bit)
+                } else {
+                    bit
+                };
+                computed_input = builder.mul_const_add_extension(two, computed_input, bit);
+                computed_output = builder.mul_const_add_extension(two, computed_output, output_bit);
+            }
+
+            constraints.push(builder.sub_extension(computed_input, input));
+            constraints.push(builder.sub_extension(computed_output, output));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BinaryXorConstGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    // Zero wires don't satisfy the constraints, as the output of a zero input is `C`.
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(vec![(self.wire_ith_input(op), F::ZERO)])
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (2 + BITS)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (BITS + 2)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const C: u64>
+    PackedEvaluableBase<F, D> for BinaryXorConstGate<F, D, BITS, C>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = P::ZEROS;
+            let mut computed_output = P::ZEROS;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                yield_constr.one(bit * (bit - F::ONE));
+
+                let output_bit = if Self::const_bit(j) {
+                    P::ONES - bit
+                } else {
+                    bit
+                };
+                computed_input = computed_input * F::TWO + bit;
+                computed_output = computed_output * F::TWO + output_bit;
+            }
+
+            yield_constr.one(computed_input - input);
+            yield_constr.one(computed_output - output);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BinaryXorConstGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const C: u64,
+> {
+    gate: BinaryXorConstGate<F, D, BITS, C>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const C: u64>
+    SimpleGenerator<F> for BinaryXorConstGenerator<F, D, BITS, C>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(input >> BITS == 0, "Input has more than {} bits", BITS);
+
+        for j in 0..BITS {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_input_bit(self.i, j)),
+                F::from_canonical_u64((input >> j) & 1),
+            );
+        }
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output(self.i)),
+            F::from_canonical_u64(input ^ C),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::binary_xor_const::BinaryXorConstGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    const C: u64 = 0x9E37_79B9;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(BinaryXorConstGate::<GoldilocksField, 4, 32, C> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BinaryXorConstGate::<GoldilocksField, D, 32, { self::C }> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 32;
+
+        fn get_wires(inputs: &[u64], outputs: &[u64]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            for (&input, &output) in inputs.iter().zip(outputs) {
+                v0.push(F::from_canonical_u64(input));
+                v0.push(F::from_canonical_u64(output));
+                v1.extend((0..BITS).map(|j| F::from_canonical_u64((input >> j) & 1)));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let inputs: Vec<u64> = vec![0, u32::MAX as u64, rng.gen::<u32>() as u64];
+        let outputs: Vec<u64> = inputs.iter().map(|&x| x ^ self::C).collect();
+        let gate = BinaryXorConstGate::<F, D, BITS, { self::C }> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Outputs of `x ^ C` with a single bit flipped must violate the constraints.
+        let wrong_outputs: Vec<u64> = outputs.iter().map(|&x| x ^ 1 << 7).collect();
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &wrong_outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Incorrect outputs should not satisfy the constraints."
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate which computes `a AND b` for single bits `a` and `b`, as `output = a * b`. All three
+/// wires are constrained to be boolean, and all of them are routed.
+#[derive(Copy, Clone, Debug)]
+pub struct BitAndGate<F: RichField + Extendable<D>, const D: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> BitAndGate<F, D> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 3;
+        config.num_routed_wires / wires_per_op
+    }
+
+    pub fn wire_ith_input_a(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i
+    }
+    pub fn wire_ith_input_b(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 1
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 2
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for BitAndGate<F, D> {
+    fn id(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_input_a(i)];
+            let b = vars.local_wires[self.wire_ith_input_b(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            constraints.push(a * (a - F::Extension::ONE));
+            constraints.push(b * (b - F::Extension::ONE));
+            constraints.push(output * (output - F::Extension::ONE));
+            constraints.push(output - a * b);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_input_a(i)];
+            let b = vars.local_wires[self.wire_ith_input_b(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            constraints.push(builder.mul_sub_extension(a, a, a));
+            constraints.push(builder.mul_sub_extension(b, b, b));
+            constraints.push(builder.mul_sub_extension(output, output, output));
+            let product = builder.mul_extension(a, b);
+            constraints.push(builder.sub_extension(output, product));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BitAndGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * 3
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * 4
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D> for BitAndGate<F, D> {
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_input_a(i)];
+            let b = vars.local_wires[self.wire_ith_input_b(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            yield_constr.one(a * (a - F::ONE));
+            yield_constr.one(b * (b - F::ONE));
+            yield_constr.one(output * (output - F::ONE));
+            yield_constr.one(output - a * b);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BitAndGenerator<F: RichField + Extendable<D>, const D: usize> {
+    gate: BitAndGate<F, D>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F> for BitAndGenerator<F, D> {
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        vec![
+            local_target(self.gate.wire_ith_input_a(self.i)),
+            local_target(self.gate.wire_ith_input_b(self.i)),
+        ]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let a = witness.get_wire(local_wire(self.gate.wire_ith_input_a(self.i)));
+        let b = witness.get_wire(local_wire(self.gate.wire_ith_input_b(self.i)));
+        debug_assert!(a.is_zero() || a.is_one(), "Input a is not a bit");
+        debug_assert!(b.is_zero() || b.is_one(), "Input b is not a bit");
+
+        out_buffer.set_wire(local_wire(self.gate.wire_ith_output(self.i)), a * b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::bit_and::BitAndGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(BitAndGate::<GoldilocksField, 4> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BitAndGate::<GoldilocksField, D> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        fn get_wires(ops: &[(u64, u64, u64)]) -> Vec<FF> {
+            ops.iter()
+                .flat_map(|&(a, b, output)| [a, b, output])
+                .map(|x| F::from_canonical_u64(x).into())
+                .collect()
+        }
+
+        let ops = [(0, 0, 0), (0, 1, 0), (1, 0, 0), (1, 1, 1)];
+        let gate = BitAndGate::<F, D> {
+            num_ops: ops.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&ops),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Flipping any single output must violate the constraints.
+        for i in 0..ops.len() {
+            let mut wrong_ops = ops;
+            wrong_ops[i].2 = 1 - wrong_ops[i].2;
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&wrong_ops),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "Incorrect outputs should not satisfy the constraints."
+            );
+        }
+
+        // Non-boolean inputs must violate the constraints, even if the product is correct.
+        let gate = BitAndGate::<F, D> {
+            num_ops: 1,
+            _phantom: PhantomData,
+        };
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&[(2, 1, 2)]),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Non-boolean inputs should not satisfy the constraints."
+        );
+    }
+}
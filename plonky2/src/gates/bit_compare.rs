@@ -0,0 +1,448 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::plonk_common::{reduce_with_powers, reduce_with_powers_ext_recursive};
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate which outputs whether `a < b` for `BITS`-bit values `a` and `b`. Rather than using a
+/// borrow chain, both inputs are split into bits, and with `e_j = 1 - (a_j - b_j)^2` the output is
+/// `sum_j p_j (1 - a_j) b_j`, where `p_j = prod_{k > j} e_k` says whether all bits above `j` agree.
+/// At most one term of the sum is nonzero, so the output is boolean. Each `p_j` is witnessed as
+/// `1 - p_j`, so that all-zero wires satisfy the constraints.
+#[derive(Copy, Clone, Debug)]
+pub struct BitCompareGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> BitCompareGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS > 0);
+        assert!(
+            BITS < F::BITS,
+            "Inputs must be smaller than the field order"
+        );
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 3 + Self::num_wires_per_op_unrouted();
+        let routed_wires_per_op = 3;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input_a(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i
+    }
+    pub fn wire_ith_input_b(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 1
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 2
+    }
+
+    // The bits of `a` and `b`, followed by the `BITS - 1` prefix wires `1 - p_j` for `j < BITS - 1`.
+    // The topmost prefix product is empty, so it is not stored.
+    fn num_wires_per_op_unrouted() -> usize {
+        3 * BITS - 1
+    }
+    fn ith_unrouted_start(&self, i: usize) -> usize {
+        3 * self.num_ops + Self::num_wires_per_op_unrouted() * i
+    }
+
+    pub fn wire_ith_a_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        self.ith_unrouted_start(i) + j
+    }
+    pub fn wire_ith_b_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        self.ith_unrouted_start(i) + BITS + j
+    }
+    pub fn wire_ith_prefix_differs(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS - 1);
+        self.ith_unrouted_start(i) + 2 * BITS + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for BitCompareGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_input_a(i)];
+            let b = vars.local_wires[self.wire_ith_input_b(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let a_bits: Vec<_> = (0..BITS)
+                .map(|j| vars.local_wires[self.wire_ith_a_bit(i, j)])
+                .collect();
+            let b_bits: Vec<_> = (0..BITS)
+                .map(|j| vars.local_wires[self.wire_ith_b_bit(i, j)])
+                .collect();
+
+            for &bit in a_bits.iter().chain(&b_bits) {
+                constraints.push(bit * (bit - F::Extension::ONE));
+            }
+            constraints.push(reduce_with_powers(&a_bits, F::Extension::TWO) - a);
+            constraints.push(reduce_with_powers(&b_bits, F::Extension::TWO) - b);
+
+            let prefix_eq = |j: usize| {
+                if j == BITS - 1 {
+                    F::Extension::ONE
+                } else {
+                    F::Extension::ONE - vars.local_wires[self.wire_ith_prefix_differs(i, j)]
+                }
+            };
+            let mut computed_output = F::Extension::ZERO;
+            for j in 0..BITS {
+                if j < BITS - 1 {
+                    let diff = a_bits[j + 1] - b_bits[j + 1];
+                    let bit_eq = F::Extension::ONE - diff * diff;
+                    constraints.push(prefix_eq(j) - prefix_eq(j + 1) * bit_eq);
+                }
+                computed_output += prefix_eq(j) * (b_bits[j] - a_bits[j] * b_bits[j]);
+            }
+            constraints.push(output - computed_output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let one = builder.one_extension();
+        let two = builder.two();
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_input_a(i)];
+            let b = vars.local_wires[self.wire_ith_input_b(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let a_bits: Vec<_> = (0..BITS)
+                .map(|j| vars.local_wires[self.wire_ith_a_bit(i, j)])
+                .collect();
+            let b_bits: Vec<_> = (0..BITS)
+                .map(|j| vars.local_wires[self.wire_ith_b_bit(i, j)])
+                .collect();
+
+            for &bit in a_bits.iter().chain(&b_bits) {
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+            }
+            let computed_a = reduce_with_powers_ext_recursive(builder, &a_bits, two);
+            constraints.push(builder.sub_extension(computed_a, a));
+            let computed_b = reduce_with_powers_ext_recursive(builder, &b_bits, two);
+            constraints.push(builder.sub_extension(computed_b, b));
+
+            let prefix_eqs: Vec<_> = (0..BITS - 1)
+                .map(|j| {
+                    let prefix_differs = vars.local_wires[self.wire_ith_prefix_differs(i, j)];
+                    builder.sub_extension(one, prefix_differs)
+                })
+                .chain([one])
+                .collect();
+            let mut computed_output = builder.zero_extension();
+            for j in 0..BITS {
+                if j < BITS - 1 {
+                    let diff = builder.sub_extension(a_bits[j + 1], b_bits[j + 1]);
+                    let neg_bit_eq = builder.mul_sub_extension(diff, diff, one);
+                    constraints.push(builder.mul_add_extension(
+                        prefix_eqs[j + 1],
+                        neg_bit_eq,
+                        prefix_eqs[j],
+                    ));
+                }
+                let b_not_a = builder.arithmetic_extension(
+                    F::NEG_ONE,
+                    F::ONE,
+                    a_bits[j],
+                    b_bits[j],
+                    b_bits[j],
+                );
+                computed_output =
+                    builder.mul_add_extension(prefix_eqs[j], b_not_a, computed_output);
+            }
+            constraints.push(builder.sub_extension(output, computed_output));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BitCompareGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (3 + Self::num_wires_per_op_unrouted())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        3
+    }
+
+    fn num_constraints(&self) -> usize {
+        // Booleanity of the bits, two recompositions, `BITS - 1` prefix products and the output.
+        self.num_ops * (3 * BITS + 2)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for BitCompareGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_input_a(i)];
+            let b = vars.local_wires[self.wire_ith_input_b(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let a_bits: Vec<_> = (0..BITS)
+                .map(|j| vars.local_wires[self.wire_ith_a_bit(i, j)])
+                .collect();
+            let b_bits: Vec<_> = (0..BITS)
+                .map(|j| vars.local_wires[self.wire_ith_b_bit(i, j)])
+                .collect();
+
+            for &bit in a_bits.iter().chain(&b_bits) {
+                yield_constr.one(bit * (bit - F::ONE));
+            }
+            yield_constr.one(reduce_with_powers(&a_bits, F::TWO) - a);
+            yield_constr.one(reduce_with_powers(&b_bits, F::TWO) - b);
+
+            let prefix_eq = |j: usize| {
+                if j == BITS - 1 {
+                    P::ONES
+                } else {
+                    P::ONES - vars.local_wires[self.wire_ith_prefix_differs(i, j)]
+                }
+            };
+            let mut computed_output = P::ZEROS;
+            for j in 0..BITS {
+                if j < BITS - 1 {
+                    let diff = a_bits[j + 1] - b_bits[j + 1];
+                    let bit_eq = P::ONES - diff * diff;
+                    yield_constr.one(prefix_eq(j) - prefix_eq(j + 1) * bit_eq);
+                }
+                computed_output += prefix_eq(j) * (b_bits[j] - a_bits[j] * b_bits[j]);
+            }
+            yield_constr.one(output - computed_output);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BitCompareGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: BitCompareGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for BitCompareGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        vec![
+            local_target(self.gate.wire_ith_input_a(self.i)),
+            local_target(self.gate.wire_ith_input_b(self.i)),
+        ]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input));
+
+        let a = get_local_wire(self.gate.wire_ith_input_a(self.i)).to_canonical_u64();
+        let b = get_local_wire(self.gate.wire_ith_input_b(self.i)).to_canonical_u64();
+        debug_assert!(a >> BITS == 0, "Input a has more than {} bits", BITS);
+        debug_assert!(b >> BITS == 0, "Input b has more than {} bits", BITS);
+
+        for j in 0..BITS {
+            let a_bit = F::from_canonical_u64((a >> j) & 1);
+            let b_bit = F::from_canonical_u64((b >> j) & 1);
+            out_buffer.set_wire(local_wire(self.gate.wire_ith_a_bit(self.i, j)), a_bit);
+            out_buffer.set_wire(local_wire(self.gate.wire_ith_b_bit(self.i, j)), b_bit);
+        }
+        for j in 0..BITS - 1 {
+            let prefix_differs = (a >> (j + 1)) != (b >> (j + 1));
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_prefix_differs(self.i, j)),
+                F::from_bool(prefix_differs),
+            );
+        }
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output(self.i)),
+            F::from_bool(a < b),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::bit_compare::BitCompareGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(BitCompareGate::<GoldilocksField, 4, 8> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BitCompareGate::<GoldilocksField, D, 8> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 8;
+
+        fn get_wires(inputs: &[(u64, u64, bool)]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+
+            for &(a, b, output) in inputs {
+                v0.push(F::from_canonical_u64(a));
+                v0.push(F::from_canonical_u64(b));
+                v0.push(F::from_bool(output));
+
+                v1.extend((0..BITS).map(|j| F::from_canonical_u64((a >> j) & 1)));
+                v1.extend((0..BITS).map(|j| F::from_canonical_u64((b >> j) & 1)));
+                v1.extend((0..BITS - 1).map(|j| F::from_bool((a >> (j + 1)) != (b >> (j + 1)))));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        // Covers `a < b`, `a = b` and `a > b`, differing in the top, middle and bottom bits.
+        let inputs = [
+            (0x12, 0x92, true),
+            (0x5a, 0x5a, false),
+            (0x5b, 0x5a, false),
+            (0x00, 0x00, false),
+            (0x49, 0x51, true),
+            (0xff, 0x7f, false),
+        ];
+        let gate = BitCompareGate::<F, D, BITS> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Flipping any single output must violate the constraints.
+        for i in 0..inputs.len() {
+            let mut wrong_inputs = inputs;
+            wrong_inputs[i].2 = !wrong_inputs[i].2;
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&wrong_inputs),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "Incorrect outputs should not satisfy the constraints."
+            );
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute `bit * x` for a `BITS`-bit value `x` and a boolean `bit`, i.e. `x` if `bit`
+/// is set and zero otherwise. The bit is constrained to be boolean and the output is
+/// range-checked to `BITS` bits, which is cheaper than a full product of two `BITS`-bit values.
+#[derive(Copy, Clone, Debug)]
+pub struct BitMulGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> BitMulGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(
+            BITS < 64,
+            "Values of 64 or more bits would overflow the field"
+        );
+        assert_eq!(BITS % Self::limb_bits(), 0);
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 3 + Self::num_limbs();
+        let routed_wires_per_op = 3;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_bit(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i
+    }
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 1
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 2
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // We have limbs for the `BITS` bits of the output.
+    pub fn num_limbs() -> usize {
+        BITS / Self::limb_bits()
+    }
+
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        3 * self.num_ops + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for BitMulGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let bit = vars.local_wires[self.wire_ith_bit(i)];
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            constraints.push(output - bit * input);
+
+            // Range-check the bit to be boolean.
+            constraints.push(bit * (F::Extension::ONE - bit));
+
+            // Range-check the output to be at most BITS bits.
+            let mut combined_limbs = F::Extension::ZERO;
+            let base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                    .product();
+                constraints.push(product);
+
+                combined_limbs = base * combined_limbs + this_limb;
+            }
+            constraints.push(combined_limbs - output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let bit = vars.local_wires[self.wire_ith_bit(i)];
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let computed_output = builder.mul_extension(bit, input);
+            constraints.push(builder.sub_extension(output, computed_output));
+
+            // Range-check the bit to be boolean.
+            let one = builder.one_extension();
+            let not_bit = builder.sub_extension(one, bit);
+            constraints.push(builder.mul_extension(bit, not_bit));
+
+            // Range-check the output to be at most BITS bits.
+            let mut combined_limbs = builder.zero_extension();
+            let base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+
+                let mut product = builder.one_extension();
+                for x in 0..max_limb {
+                    let x_target =
+                        builder.constant_extension(F::Extension::from_canonical_usize(x));
+                    let diff = builder.sub_extension(this_limb, x_target);
+                    product = builder.mul_extension(product, diff);
+                }
+                constraints.push(product);
+
+                combined_limbs = builder.mul_add_extension(base, combined_limbs, this_limb);
+            }
+            constraints.push(builder.sub_extension(combined_limbs, output));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BitMulGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                        _phantom: PhantomData,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (3 + Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (3 + Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for BitMulGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let bit = vars.local_wires[self.wire_ith_bit(i)];
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            yield_constr.one(output - bit * input);
+
+            // Range-check the bit to be boolean.
+            yield_constr.one(bit * (P::ONES - bit));
+
+            // Range-check the output to be at most BITS bits.
+            let mut combined_limbs = P::ZEROS;
+            let base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::from_canonical_usize(x))
+                    .product();
+                yield_constr.one(product);
+
+                combined_limbs = combined_limbs * base + this_limb;
+            }
+            yield_constr.one(combined_limbs - output);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BitMulGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: BitMulGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for BitMulGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        vec![
+            local_target(self.gate.wire_ith_bit(self.i)),
+            local_target(self.gate.wire_ith_input(self.i)),
+        ]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input));
+
+        let bit = get_local_wire(self.gate.wire_ith_bit(self.i));
+        let input = get_local_wire(self.gate.wire_ith_input(self.i));
+        let output = bit * input;
+
+        out_buffer.set_wire(local_wire(self.gate.wire_ith_output(self.i)), output);
+
+        let num_limbs = BitMulGate::<F, D, BITS>::num_limbs();
+        let limb_base = 1 << BitMulGate::<F, D, BITS>::limb_bits();
+        let output_limbs = (0..num_limbs).scan(output.to_canonical_u64(), |acc, _| {
+            let tmp = *acc % limb_base;
+            *acc /= limb_base;
+            Some(F::from_canonical_u64(tmp))
+        });
+
+        for (j, limb) in output_limbs.enumerate() {
+            let wire = local_wire(self.gate.wire_ith_output_jth_limb(self.i, j));
+            out_buffer.set_wire(wire, limb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::bit_mul::BitMulGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(BitMulGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BitMulGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FF = <C as GenericConfig<D>>::FE;
+    const BITS: usize = 32;
+
+    /// Builds the wires of a gate performing the given operations, using the given outputs.
+    fn get_wires(ops: &[(u64, u64, u64)]) -> Vec<FF> {
+        let mut v0 = Vec::new();
+        let mut v1 = Vec::new();
+
+        let num_limbs = BitMulGate::<F, D, BITS>::num_limbs();
+        let limb_base = 1 << BitMulGate::<F, D, BITS>::limb_bits();
+        for &(bit, input, output) in ops {
+            v0.push(F::from_canonical_u64(bit));
+            v0.push(F::from_canonical_u64(input));
+            v0.push(F::from_canonical_u64(output));
+
+            let mut value = output;
+            for _ in 0..num_limbs {
+                v1.push(F::from_canonical_u64(value % limb_base));
+                value /= limb_base;
+            }
+        }
+
+        v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        let mut rng = rand::thread_rng();
+        let x = rng.gen::<u32>() as u64;
+        let max = u32::MAX as u64;
+        let ops = [(0, x, 0), (1, x, x), (0, max, 0), (1, max, max)];
+
+        let gate = BitMulGate::<F, D, BITS> {
+            num_ops: ops.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&ops),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // A wrong output, or a non-boolean bit with a consistent output, should be rejected.
+        let gate = BitMulGate::<F, D, BITS> {
+            num_ops: 1,
+            _phantom: PhantomData,
+        };
+        for wrong_op in [(0, x, x), (1, x, 0), (2, 3, 6)] {
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&[wrong_op]),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "A wrong operation should not satisfy the constraints."
+            );
+        }
+    }
+
+    #[test]
+    fn test_bit_mul() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x_value = F::from_canonical_u32(rand::thread_rng().gen());
+        let gate = BitMulGate::<F, D, BITS>::new_from_config(&builder.config);
+        for (bit_value, expected_value) in [(F::ZERO, F::ZERO), (F::ONE, x_value)] {
+            let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+            let bit = builder.add_virtual_target();
+            let x = builder.add_virtual_target();
+            builder.connect(bit, Target::wire(gate_index, gate.wire_ith_bit(i)));
+            builder.connect(x, Target::wire(gate_index, gate.wire_ith_input(i)));
+            pw.set_target(bit, bit_value);
+            pw.set_target(x, x_value);
+
+            let output = Target::wire(gate_index, gate.wire_ith_output(i));
+            let expected = builder.constant(expected_value);
+            builder.connect(output, expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
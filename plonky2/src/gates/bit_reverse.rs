@@ -0,0 +1,385 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+use crate::util::reverse_bits;
+
+/// A gate to reverse the order of the bits of a `BITS`-bit input, e.g. for the index permutation
+/// of an FFT. The input is split into bits, which also range-checks it, and the output is
+/// recomposed from the same bits with the most significant bit first.
+#[derive(Copy, Clone, Debug)]
+pub struct BitReverseGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> BitReverseGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS >= 1, "Inputs must have at least one bit");
+        assert!(BITS < 64, "Values must fit in the field");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 2 + BITS;
+        let routed_wires_per_op = 2;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i + 1
+    }
+
+    pub fn wire_ith_input_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        2 * self.num_ops + BITS * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for BitReverseGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = F::Extension::ZERO;
+            let mut computed_output = F::Extension::ZERO;
+            for j in 0..BITS {
+                let high_bit = vars.local_wires[self.wire_ith_input_bit(i, BITS - 1 - j)];
+                let low_bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(high_bit * (high_bit - F::Extension::ONE));
+                computed_input = computed_input.double() + high_bit;
+                computed_output = computed_output.double() + low_bit;
+            }
+            constraints.push(computed_input - input);
+            constraints.push(computed_output - output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let two = F::TWO;
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = builder.zero_extension();
+            let mut computed_output = builder.zero_extension();
+            for j in 0..BITS {
+                let high_bit = vars.local_wires[self.wire_ith_input_bit(i, BITS - 1 - j)];
+                let low_bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(builder.mul_sub_extension(high_bit, high_bit, high_bit));
+                computed_input = builder.mul_const_add_extension(two, computed_input, high_bit);
+                computed_output = builder.mul_const_add_extension(two, computed_output, low_bit);
+            }
+            constraints.push(builder.sub_extension(computed_input, input));
+            constraints.push(builder.sub_extension(computed_output, output));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    BitReverseGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (2 + BITS)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (BITS + 2)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for BitReverseGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = P::ZEROS;
+            let mut computed_output = P::ZEROS;
+            for j in 0..BITS {
+                let high_bit = vars.local_wires[self.wire_ith_input_bit(i, BITS - 1 - j)];
+                let low_bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                yield_constr.one(high_bit * (high_bit - F::ONE));
+                computed_input = computed_input * F::TWO + high_bit;
+                computed_output = computed_output * F::TWO + low_bit;
+            }
+            yield_constr.one(computed_input - input);
+            yield_constr.one(computed_output - output);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BitReverseGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: BitReverseGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for BitReverseGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(input >> BITS == 0, "Input has more than {} bits", BITS);
+
+        for j in 0..BITS {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_input_bit(self.i, j)),
+                F::from_canonical_u64((input >> j) & 1),
+            );
+        }
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output(self.i)),
+            F::from_canonical_usize(reverse_bits(input as usize, BITS)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::extension_field::Extendable;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::bit_reverse::BitReverseGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::{HashOut, RichField};
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+    use crate::util::reverse_bits;
+
+    /// Returns `BITS`-bit inputs including the edge cases zero, one, the top bit alone and all
+    /// ones, plus some random values.
+    fn test_inputs<const BITS: usize>() -> Vec<u64> {
+        let max = (1 << BITS) - 1;
+        let mut rng = rand::thread_rng();
+        let mut inputs = vec![0, 1, 1 << (BITS - 1), max];
+        inputs.extend((0..3).map(|_| rng.gen_range(0..=max)));
+        inputs
+    }
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(BitReverseGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(BitReverseGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    fn test_gate_constraint_with<const BITS: usize>() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        fn get_wires<const BITS: usize>(inputs: &[u64], outputs: &[u64]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            for (&input, &output) in inputs.iter().zip(outputs) {
+                v0.extend([input, output].map(F::from_canonical_u64));
+                v1.extend((0..BITS).map(|j| F::from_canonical_u64((input >> j) & 1)));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let inputs = test_inputs::<BITS>();
+        let outputs: Vec<u64> = inputs
+            .iter()
+            .map(|&x| reverse_bits(x as usize, BITS) as u64)
+            .collect();
+        let gate = BitReverseGate::<F, D, BITS> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires::<BITS>(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Returning the input unchanged is only correct for palindromic inputs, so flip the lowest
+        // bit of the expected output instead.
+        let wrong_outputs: Vec<u64> = outputs.iter().map(|&x| x ^ 1).collect();
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires::<BITS>(&inputs, &wrong_outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars)
+                .chunks(BITS + 2)
+                .all(|op_constraints| op_constraints.iter().any(|x| !x.is_zero())),
+            "Incorrect outputs should not satisfy the constraints."
+        );
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        test_gate_constraint_with::<8>();
+        test_gate_constraint_with::<32>();
+    }
+
+    /// Reverses some `BITS`-bit inputs in `builder`, checking the outputs against the host.
+    fn add_bit_reverse_checks<F: RichField + Extendable<D>, const D: usize, const BITS: usize>(
+        builder: &mut CircuitBuilder<F, D>,
+        pw: &mut PartialWitness<F>,
+    ) {
+        let gate = BitReverseGate::<F, D, BITS>::new_from_config(&builder.config);
+        for input in test_inputs::<BITS>() {
+            let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+            let x = builder.add_virtual_target();
+            builder.connect(x, Target::wire(gate_index, gate.wire_ith_input(i)));
+            pw.set_target(x, F::from_canonical_u64(input));
+
+            let output = Target::wire(gate_index, gate.wire_ith_output(i));
+            let expected =
+                builder.constant(F::from_canonical_usize(reverse_bits(input as usize, BITS)));
+            builder.connect(output, expected);
+        }
+    }
+
+    #[test]
+    fn test_bit_reverse() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        add_bit_reverse_checks::<F, D, 8>(&mut builder, &mut pw);
+        add_bit_reverse_checks::<F, D, 32>(&mut builder, &mut pw);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
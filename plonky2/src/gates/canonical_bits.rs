@@ -0,0 +1,313 @@
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::{Field, Field64};
+use plonky2_field::goldilocks_field::GoldilocksField;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::plonk_common::{reduce_with_powers, reduce_with_powers_ext_recursive};
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate which decomposes a Goldilocks field element into 64 little-endian bits, and checks that
+/// the bits represent an integer strictly below the modulus `p = 2^64 - 2^32 + 1`.
+///
+/// A 64-bit integer is at least `p` iff its high 32 bits are all ones and its low 32 bits are not
+/// all zeros. With `h` the number of zeros among the high bits and `l` the integer represented by
+/// the low bits, we enforce `l * (1 - h * h_inv) = 0` for a witnessed `h_inv`. If `h = 0` this
+/// forces `l = 0`; otherwise the prover may pick `h_inv = 1 / h`.
+#[derive(Copy, Clone, Debug)]
+pub struct CanonicalBitsGate<F: RichField + Extendable<D>, const D: usize> {
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CanonicalBitsGate<F, D> {
+    pub fn new() -> Self {
+        assert_eq!(
+            F::ORDER,
+            GoldilocksField::ORDER,
+            "CanonicalBitsGate only supports the Goldilocks field"
+        );
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+
+    pub const NUM_BITS: usize = 64;
+    pub const NUM_HIGH_BITS: usize = 32;
+
+    pub const WIRE_SUM: usize = 0;
+    pub const START_BITS: usize = 1;
+    pub const WIRE_HIGH_ZEROS_INV: usize = Self::START_BITS + Self::NUM_BITS;
+
+    /// Returns the indices of the bit wires, least significant first.
+    pub fn bits(&self) -> Range<usize> {
+        Self::START_BITS..Self::START_BITS + Self::NUM_BITS
+    }
+
+    fn low_bits(&self) -> Range<usize> {
+        Self::START_BITS..Self::START_BITS + Self::NUM_BITS - Self::NUM_HIGH_BITS
+    }
+
+    fn high_bits(&self) -> Range<usize> {
+        Self::START_BITS + Self::NUM_BITS - Self::NUM_HIGH_BITS..Self::START_BITS + Self::NUM_BITS
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for CanonicalBitsGate<F, D> {
+    fn id(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let sum = vars.local_wires[Self::WIRE_SUM];
+        let bits = &vars.local_wires[self.bits()];
+        let computed_sum = reduce_with_powers(bits, F::Extension::TWO);
+        let mut constraints = vec![computed_sum - sum];
+        for &bit in bits {
+            constraints.push(bit * (bit - F::Extension::ONE));
+        }
+
+        let low = reduce_with_powers(&vars.local_wires[self.low_bits()], F::Extension::TWO);
+        let high_ones: F::Extension = vars.local_wires[self.high_bits()].iter().copied().sum();
+        let high_zeros = F::Extension::from_canonical_usize(Self::NUM_HIGH_BITS) - high_ones;
+        let high_zeros_inv = vars.local_wires[Self::WIRE_HIGH_ZEROS_INV];
+        constraints.push(low * (F::Extension::ONE - high_zeros * high_zeros_inv));
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let two = builder.two();
+        let sum = vars.local_wires[Self::WIRE_SUM];
+        let bits = &vars.local_wires[self.bits()];
+        let computed_sum = reduce_with_powers_ext_recursive(builder, bits, two);
+        let mut constraints = vec![builder.sub_extension(computed_sum, sum)];
+        for &bit in bits {
+            constraints.push(builder.mul_sub_extension(bit, bit, bit));
+        }
+
+        let low =
+            reduce_with_powers_ext_recursive(builder, &vars.local_wires[self.low_bits()], two);
+        let high_ones = builder.add_many_extension(&vars.local_wires[self.high_bits()]);
+        let num_high_bits =
+            builder.constant_extension(F::Extension::from_canonical_usize(Self::NUM_HIGH_BITS));
+        let high_zeros = builder.sub_extension(num_high_bits, high_ones);
+        let high_zeros_inv = vars.local_wires[Self::WIRE_HIGH_ZEROS_INV];
+        let one = builder.one_extension();
+        // 1 - high_zeros * high_zeros_inv
+        let is_all_ones =
+            builder.arithmetic_extension(F::NEG_ONE, F::ONE, high_zeros, high_zeros_inv, one);
+        constraints.push(builder.mul_extension(low, is_all_ones));
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        let gen = CanonicalBitsGenerator::<F, D> {
+            gate_index,
+            _phantom: PhantomData,
+        };
+        vec![Box::new(gen.adapter())]
+    }
+
+    // 1 for the sum, 64 for the bits, then 1 for the inverse of the number of high zeros.
+    fn num_wires(&self) -> usize {
+        Self::NUM_BITS + 2
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    // Bounded by the canonicity check `l * (1 - h * h_inv)`.
+    fn degree(&self) -> usize {
+        3
+    }
+
+    // 1 for checking the sum, 64 for the bit checks, then 1 for the canonicity check.
+    fn num_constraints(&self) -> usize {
+        Self::NUM_BITS + 2
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D>
+    for CanonicalBitsGate<F, D>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        let sum = vars.local_wires[Self::WIRE_SUM];
+        let bits = vars.local_wires.view(self.bits());
+        let computed_sum = reduce_with_powers(bits, F::TWO);
+        yield_constr.one(computed_sum - sum);
+        yield_constr.many(bits.iter().map(|&bit| bit * (bit - F::ONE)));
+
+        let low = reduce_with_powers(vars.local_wires.view(self.low_bits()), F::TWO);
+        let high_ones: P = vars
+            .local_wires
+            .view(self.high_bits())
+            .iter()
+            .copied()
+            .sum();
+        let high_zeros = -high_ones + F::from_canonical_usize(Self::NUM_HIGH_BITS);
+        let high_zeros_inv = vars.local_wires[Self::WIRE_HIGH_ZEROS_INV];
+        yield_constr.one(low * (P::ONES - high_zeros * high_zeros_inv));
+    }
+}
+
+#[derive(Debug)]
+struct CanonicalBitsGenerator<F: RichField + Extendable<D>, const D: usize> {
+    gate_index: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
+    for CanonicalBitsGenerator<F, D>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            CanonicalBitsGate::<F, D>::WIRE_SUM,
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let sum_value = witness
+            .get_target(Target::wire(
+                self.gate_index,
+                CanonicalBitsGate::<F, D>::WIRE_SUM,
+            ))
+            .to_canonical_u64();
+
+        let num_bits = CanonicalBitsGate::<F, D>::NUM_BITS;
+        let start_bits = CanonicalBitsGate::<F, D>::START_BITS;
+        for i in 0..num_bits {
+            out_buffer.set_target(
+                Target::wire(self.gate_index, start_bits + i),
+                F::from_canonical_u64((sum_value >> i) & 1),
+            );
+        }
+
+        let num_high_bits = CanonicalBitsGate::<F, D>::NUM_HIGH_BITS;
+        let high_zeros =
+            (sum_value >> (num_bits - num_high_bits)).count_zeros() as usize - (64 - num_high_bits);
+        let high_zeros_inv = F::from_canonical_usize(high_zeros)
+            .try_inverse()
+            .unwrap_or(F::ZERO);
+        out_buffer.set_target(
+            Target::wire(
+                self.gate_index,
+                CanonicalBitsGate::<F, D>::WIRE_HIGH_ZEROS_INV,
+            ),
+            high_zeros_inv,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::field_types::{Field, Field64};
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::canonical_bits::CanonicalBitsGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(CanonicalBitsGate::new())
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(CanonicalBitsGate::new())
+    }
+
+    /// Checks the gate constraints for the bit pattern of `value`, which may be at least the field
+    /// order. The sum wire is set to `value` reduced modulo the order, so that only the canonicity
+    /// check can fail.
+    fn constraints_satisfied(value: u64) -> bool {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        let high_zeros = (value >> 32).count_zeros() - 32;
+        let high_zeros_inv = F::from_canonical_u32(high_zeros)
+            .try_inverse()
+            .unwrap_or(F::ZERO);
+
+        let mut wires = vec![F::from_noncanonical_u64(value)];
+        wires.extend((0..64).map(|i| F::from_canonical_u64((value >> i) & 1)));
+        wires.push(high_zeros_inv);
+        let wires: Vec<FF> = wires.into_iter().map(|x| x.into()).collect();
+
+        let gate = CanonicalBitsGate::<F, D>::new();
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &wires,
+            public_inputs_hash: &HashOut::rand(),
+        };
+        gate.eval_unfiltered(vars).iter().all(|x| x.is_zero())
+    }
+
+    #[test]
+    fn test_canonical_bits() {
+        let order = GoldilocksField::ORDER;
+        assert!(constraints_satisfied(0));
+        assert!(constraints_satisfied(u32::MAX as u64));
+        assert!(constraints_satisfied(order - 2));
+        // The high bits are all ones but the low bits are zero.
+        assert!(constraints_satisfied(order - 1));
+    }
+
+    #[test]
+    fn test_non_canonical_bits() {
+        let order = GoldilocksField::ORDER;
+        assert!(!constraints_satisfied(order));
+        assert!(!constraints_satisfied(order + 1));
+        assert!(!constraints_satisfied(u64::MAX));
+    }
+}
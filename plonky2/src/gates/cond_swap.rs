@@ -0,0 +1,315 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to conditionally swap two `BITS`-bit words `a` and `b`, outputting `(a, b)` if `swap` is
+/// zero and `(b, a)` if it is one, as `out0 = a + swap * (b - a)` and `out1 = b + swap * (a - b)`.
+/// Packing several swaps per row suits the compare-exchange layers of sorting networks. The words
+/// are assumed to be range-checked already; the outputs, being a permutation of them, then need no
+/// checks of their own.
+#[derive(Copy, Clone, Debug)]
+pub struct CondSwapGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> CondSwapGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS < 64, "Values must fit in the field");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 5;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / wires_per_op)
+    }
+
+    pub fn wire_ith_swap(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i
+    }
+    pub fn wire_ith_a(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 1
+    }
+    pub fn wire_ith_b(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 2
+    }
+    pub fn wire_ith_output_0(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 3
+    }
+    pub fn wire_ith_output_1(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 4
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for CondSwapGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let swap = vars.local_wires[self.wire_ith_swap(i)];
+            let a = vars.local_wires[self.wire_ith_a(i)];
+            let b = vars.local_wires[self.wire_ith_b(i)];
+            let output_0 = vars.local_wires[self.wire_ith_output_0(i)];
+            let output_1 = vars.local_wires[self.wire_ith_output_1(i)];
+
+            constraints.push(swap * (swap - F::Extension::ONE));
+            constraints.push(a + swap * (b - a) - output_0);
+            constraints.push(b + swap * (a - b) - output_1);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let swap = vars.local_wires[self.wire_ith_swap(i)];
+            let a = vars.local_wires[self.wire_ith_a(i)];
+            let b = vars.local_wires[self.wire_ith_b(i)];
+            let output_0 = vars.local_wires[self.wire_ith_output_0(i)];
+            let output_1 = vars.local_wires[self.wire_ith_output_1(i)];
+
+            constraints.push(builder.mul_sub_extension(swap, swap, swap));
+            let computed_output_0 = builder.select_ext_generalized(swap, b, a);
+            constraints.push(builder.sub_extension(computed_output_0, output_0));
+            let computed_output_1 = builder.select_ext_generalized(swap, a, b);
+            constraints.push(builder.sub_extension(computed_output_1, output_1));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    CondSwapGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * 5
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * 3
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for CondSwapGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let swap = vars.local_wires[self.wire_ith_swap(i)];
+            let a = vars.local_wires[self.wire_ith_a(i)];
+            let b = vars.local_wires[self.wire_ith_b(i)];
+            let output_0 = vars.local_wires[self.wire_ith_output_0(i)];
+            let output_1 = vars.local_wires[self.wire_ith_output_1(i)];
+
+            yield_constr.one(swap * (swap - F::ONE));
+            yield_constr.one(a + swap * (b - a) - output_0);
+            yield_constr.one(b + swap * (a - b) - output_1);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CondSwapGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: CondSwapGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for CondSwapGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        [
+            self.gate.wire_ith_swap(self.i),
+            self.gate.wire_ith_a(self.i),
+            self.gate.wire_ith_b(self.i),
+        ]
+        .iter()
+        .map(|&input| Target::wire(self.gate_index, input))
+        .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let swap = witness.get_wire(local_wire(self.gate.wire_ith_swap(self.i)));
+        let a = witness.get_wire(local_wire(self.gate.wire_ith_a(self.i)));
+        let b = witness.get_wire(local_wire(self.gate.wire_ith_b(self.i)));
+        debug_assert!(swap.is_zero() || swap.is_one(), "Swap bit is not boolean");
+
+        let (output_0, output_1) = if swap.is_one() { (b, a) } else { (a, b) };
+        out_buffer.set_wire(local_wire(self.gate.wire_ith_output_0(self.i)), output_0);
+        out_buffer.set_wire(local_wire(self.gate.wire_ith_output_1(self.i)), output_1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::cond_swap::CondSwapGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(CondSwapGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(CondSwapGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        fn get_wires(ops: &[(bool, u32, u32, u32, u32)]) -> Vec<FF> {
+            ops.iter()
+                .flat_map(|&(swap, a, b, output_0, output_1)| {
+                    [
+                        F::from_bool(swap),
+                        F::from_canonical_u32(a),
+                        F::from_canonical_u32(b),
+                        F::from_canonical_u32(output_0),
+                        F::from_canonical_u32(output_1),
+                    ]
+                })
+                .map(|x| x.into())
+                .collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let a = rng.gen::<u32>();
+        let b = a ^ rng.gen_range(1..=u32::MAX);
+        // Both bit values, so that the outputs pass through once and are swapped once.
+        let ops = [(false, a, b, a, b), (true, a, b, b, a)];
+        let gate = CondSwapGate::<F, D, 32> {
+            num_ops: ops.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&ops),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        for wrong_op in [(false, a, b, b, a), (true, a, b, a, b), (true, a, b, b, b)] {
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&[wrong_op, wrong_op]),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "Wrong outputs should not satisfy the constraints."
+            );
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate which looks up the entry at a given index in `0..N` of a table of `N` constants, e.g. for
+/// S-box evaluations. The table is given by the gate's local constants, and the entry is selected
+/// with a one-hot selector vector. This is the constant-table counterpart of `RandomAccessGate`,
+/// whose list is given by wires instead.
+#[derive(Copy, Clone, Debug)]
+pub struct ConstTableGate<F: RichField + Extendable<D>, const D: usize, const N: usize> {
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const N: usize> ConstTableGate<F, D, N> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(N > 0, "The table must have at least one entry");
+        assert!(
+            N + 2 <= config.num_wires,
+            "A table of {} entries needs more than {} wires",
+            N,
+            config.num_wires
+        );
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn wire_index(&self) -> usize {
+        0
+    }
+    pub fn wire_output(&self) -> usize {
+        1
+    }
+
+    /// The `i`th entry of the one-hot selector vector, which is one exactly at the looked-up index.
+    pub fn wire_selector(&self, i: usize) -> usize {
+        debug_assert!(i < N);
+        2 + i
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const N: usize> Gate<F, D>
+    for ConstTableGate<F, D, N>
+{
+    fn id(&self) -> String {
+        format!("{:?}<N={}>", self, N)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        let index = vars.local_wires[self.wire_index()];
+        let output = vars.local_wires[self.wire_output()];
+
+        let mut selector_sum = F::Extension::ZERO;
+        let mut selected_index = F::Extension::ZERO;
+        let mut selected_entry = F::Extension::ZERO;
+        for i in 0..N {
+            let selector = vars.local_wires[self.wire_selector(i)];
+            constraints.push(selector * (selector - F::Extension::ONE));
+
+            selector_sum += selector;
+            selected_index += selector * F::Extension::from_canonical_usize(i);
+            selected_entry += selector * vars.local_constants[i];
+        }
+        constraints.push(selector_sum - F::Extension::ONE);
+        constraints.push(selected_index - index);
+        constraints.push(selected_entry - output);
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        let index = vars.local_wires[self.wire_index()];
+        let output = vars.local_wires[self.wire_output()];
+
+        let zero = builder.zero_extension();
+        let mut selector_sum = zero;
+        let mut selected_index = zero;
+        let mut selected_entry = zero;
+        for i in 0..N {
+            let selector = vars.local_wires[self.wire_selector(i)];
+            constraints.push(builder.mul_sub_extension(selector, selector, selector));
+
+            selector_sum = builder.add_extension(selector_sum, selector);
+            selected_index = builder.mul_const_add_extension(
+                F::from_canonical_usize(i),
+                selector,
+                selected_index,
+            );
+            selected_entry =
+                builder.mul_add_extension(selector, vars.local_constants[i], selected_entry);
+        }
+        let one = builder.one_extension();
+        constraints.push(builder.sub_extension(selector_sum, one));
+        constraints.push(builder.sub_extension(selected_index, index));
+        constraints.push(builder.sub_extension(selected_entry, output));
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        let gen = ConstTableGenerator::<F, D, N> {
+            gate_index,
+            gate: *self,
+            table: local_constants[..N].to_vec(),
+        };
+        vec![Box::new(gen.adapter())]
+    }
+
+    fn num_wires(&self) -> usize {
+        N + 2
+    }
+
+    fn num_constants(&self) -> usize {
+        N
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        N + 3
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const N: usize> PackedEvaluableBase<F, D>
+    for ConstTableGate<F, D, N>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        let index = vars.local_wires[self.wire_index()];
+        let output = vars.local_wires[self.wire_output()];
+
+        let mut selector_sum = P::ZEROS;
+        let mut selected_index = P::ZEROS;
+        let mut selected_entry = P::ZEROS;
+        for i in 0..N {
+            let selector = vars.local_wires[self.wire_selector(i)];
+            yield_constr.one(selector * (selector - F::ONE));
+
+            selector_sum += selector;
+            selected_index += selector * F::from_canonical_usize(i);
+            selected_entry += selector * vars.local_constants[i];
+        }
+        yield_constr.one(selector_sum - F::ONE);
+        yield_constr.one(selected_index - index);
+        yield_constr.one(selected_entry - output);
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ConstTableGenerator<F: RichField + Extendable<D>, const D: usize, const N: usize> {
+    gate_index: usize,
+    gate: ConstTableGate<F, D, N>,
+    table: Vec<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const N: usize> SimpleGenerator<F>
+    for ConstTableGenerator<F, D, N>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(self.gate_index, self.gate.wire_index())]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let index = witness
+            .get_wire(local_wire(self.gate.wire_index()))
+            .to_canonical_u64() as usize;
+        assert!(
+            index < N,
+            "Table index {} is out of range for a table of {} entries",
+            index,
+            N
+        );
+
+        out_buffer.set_wire(local_wire(self.gate.wire_output()), self.table[index]);
+        for i in 0..N {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_selector(i)),
+                F::from_bool(i == index),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::const_table::ConstTableGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    const N: usize = 8;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(
+            ConstTableGate::<GoldilocksField, 4, N>::new_from_config(
+                &CircuitConfig::standard_recursion_config(),
+            ),
+        )
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(ConstTableGate::<F, D, N>::new_from_config(
+            &CircuitConfig::standard_recursion_config(),
+        ))
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        /// Returns the local wires for a lookup of `index`, with one-hot selectors for
+        /// `selected_index` and the given output.
+        fn get_wires(index: usize, selected_index: usize, output: F) -> Vec<FF> {
+            let mut v = vec![F::from_canonical_usize(index), output];
+            v.extend((0..N).map(|i| F::from_bool(i == selected_index)));
+            v.iter().map(|&x| x.into()).collect()
+        }
+
+        let gate =
+            ConstTableGate::<F, D, N>::new_from_config(&CircuitConfig::standard_recursion_config());
+        let table = F::rand_vec(N);
+        let constants = table.iter().map(|&c| c.into()).collect::<Vec<FF>>();
+
+        for index in 0..N {
+            let good_vars = EvaluationVars {
+                local_constants: &constants,
+                local_wires: &get_wires(index, index, table[index]),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                gate.eval_unfiltered(good_vars).iter().all(|x| x.is_zero()),
+                "Gate constraints are not satisfied."
+            );
+
+            let wrong_output = table[index] + F::ONE;
+            let wrong_selector = (index + 1) % N;
+            for bad_wires in [
+                get_wires(index, index, wrong_output),
+                get_wires(index, wrong_selector, table[wrong_selector]),
+            ] {
+                let bad_vars = EvaluationVars {
+                    local_constants: &constants,
+                    local_wires: &bad_wires,
+                    public_inputs_hash: &HashOut::rand(),
+                };
+                assert!(
+                    !gate.eval_unfiltered(bad_vars).iter().all(|x| x.is_zero()),
+                    "Gate constraints are satisfied but should not be."
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_const_table() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let gate = ConstTableGate::<F, D, N>::new_from_config(&builder.config);
+        let table = F::rand_vec(N);
+        for (i, &entry) in table.iter().enumerate() {
+            let gate_index = builder.add_gate(gate, table.clone());
+            let index = builder.add_virtual_target();
+            builder.connect(index, Target::wire(gate_index, gate.wire_index()));
+            pw.set_target(index, F::from_canonical_usize(i));
+
+            let output = Target::wire(gate_index, gate.wire_output());
+            let expected = builder.constant(entry);
+            builder.connect(output, expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
@@ -0,0 +1,452 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute `a * b - c * d` for `BITS`-bit values: it returns the `2 * BITS`-bit result
+/// `a * b - c * d + 2^(2 * BITS) * borrow`, where `borrow` is set if the difference underflows.
+/// The result is range-checked, but inputs are not.
+#[derive(Copy, Clone, Debug)]
+pub struct CrossMulGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> CrossMulGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        // The constraint `output = a * b - c * d + 2^(2 * BITS) * borrow` must hold over the
+        // integers, and both sides lie in `(-2^(2 * BITS + 1), 2^(2 * BITS + 1))`.
+        assert!(2 * BITS + 1 < 64, "Results must fit in the field");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 6 + Self::num_limbs();
+        let routed_wires_per_op = 6;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_multiplicand_a(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i
+    }
+    pub fn wire_ith_multiplicand_b(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i + 1
+    }
+    pub fn wire_ith_multiplicand_c(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i + 2
+    }
+    pub fn wire_ith_multiplicand_d(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i + 3
+    }
+
+    pub fn wire_ith_output_result(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i + 4
+    }
+    pub fn wire_ith_output_borrow(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i + 5
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // We have limbs for the `2 * BITS` bits of `output_result`.
+    pub fn num_limbs() -> usize {
+        2 * BITS / Self::limb_bits()
+    }
+
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        6 * self.num_ops + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for CrossMulGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_multiplicand_a(i)];
+            let b = vars.local_wires[self.wire_ith_multiplicand_b(i)];
+            let c = vars.local_wires[self.wire_ith_multiplicand_c(i)];
+            let d = vars.local_wires[self.wire_ith_multiplicand_d(i)];
+
+            let result_initial = a * b - c * d;
+            let base = F::Extension::from_canonical_u64(1 << (2 * BITS));
+
+            let output_result = vars.local_wires[self.wire_ith_output_result(i)];
+            let output_borrow = vars.local_wires[self.wire_ith_output_borrow(i)];
+
+            constraints.push(output_result - (result_initial + base * output_borrow));
+
+            // Range-check output_result to be at most 2 * BITS bits.
+            let mut combined_limbs = F::Extension::ZERO;
+            let limb_base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                    .product();
+                constraints.push(product);
+
+                combined_limbs = limb_base * combined_limbs + this_limb;
+            }
+            constraints.push(combined_limbs - output_result);
+
+            // Range-check output_borrow to be one bit.
+            constraints.push(output_borrow * (F::Extension::ONE - output_borrow));
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_multiplicand_a(i)];
+            let b = vars.local_wires[self.wire_ith_multiplicand_b(i)];
+            let c = vars.local_wires[self.wire_ith_multiplicand_c(i)];
+            let d = vars.local_wires[self.wire_ith_multiplicand_d(i)];
+
+            let cd = builder.mul_extension(c, d);
+            let result_initial = builder.mul_sub_extension(a, b, cd);
+            let base =
+                builder.constant_extension(F::Extension::from_canonical_u64(1 << (2 * BITS)));
+
+            let output_result = vars.local_wires[self.wire_ith_output_result(i)];
+            let output_borrow = vars.local_wires[self.wire_ith_output_borrow(i)];
+
+            let computed_output = builder.mul_add_extension(base, output_borrow, result_initial);
+            constraints.push(builder.sub_extension(output_result, computed_output));
+
+            // Range-check output_result to be at most 2 * BITS bits.
+            let mut combined_limbs = builder.zero_extension();
+            let limb_base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let mut product = builder.one_extension();
+                for x in 0..max_limb {
+                    let x_target =
+                        builder.constant_extension(F::Extension::from_canonical_usize(x));
+                    let diff = builder.sub_extension(this_limb, x_target);
+                    product = builder.mul_extension(product, diff);
+                }
+                constraints.push(product);
+
+                combined_limbs = builder.mul_add_extension(limb_base, combined_limbs, this_limb);
+            }
+            constraints.push(builder.sub_extension(combined_limbs, output_result));
+
+            // Range-check output_borrow to be one bit.
+            let one = builder.one_extension();
+            let not_borrow = builder.sub_extension(one, output_borrow);
+            constraints.push(builder.mul_extension(output_borrow, not_borrow));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    CrossMulGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (6 + Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (3 + Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for CrossMulGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_multiplicand_a(i)];
+            let b = vars.local_wires[self.wire_ith_multiplicand_b(i)];
+            let c = vars.local_wires[self.wire_ith_multiplicand_c(i)];
+            let d = vars.local_wires[self.wire_ith_multiplicand_d(i)];
+
+            let result_initial = a * b - c * d;
+            let base = F::from_canonical_u64(1 << (2 * BITS));
+
+            let output_result = vars.local_wires[self.wire_ith_output_result(i)];
+            let output_borrow = vars.local_wires[self.wire_ith_output_borrow(i)];
+
+            yield_constr.one(output_result - (result_initial + output_borrow * base));
+
+            // Range-check output_result to be at most 2 * BITS bits.
+            let mut combined_limbs = P::ZEROS;
+            let limb_base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::from_canonical_usize(x))
+                    .product();
+                yield_constr.one(product);
+
+                combined_limbs = combined_limbs * limb_base + this_limb;
+            }
+            yield_constr.one(combined_limbs - output_result);
+
+            // Range-check output_borrow to be one bit.
+            yield_constr.one(output_borrow * (P::ONES - output_borrow));
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CrossMulGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: CrossMulGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for CrossMulGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        vec![
+            local_target(self.gate.wire_ith_multiplicand_a(self.i)),
+            local_target(self.gate.wire_ith_multiplicand_b(self.i)),
+            local_target(self.gate.wire_ith_multiplicand_c(self.i)),
+            local_target(self.gate.wire_ith_multiplicand_d(self.i)),
+        ]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input)).to_canonical_u64();
+
+        let a = get_local_wire(self.gate.wire_ith_multiplicand_a(self.i));
+        let b = get_local_wire(self.gate.wire_ith_multiplicand_b(self.i));
+        let c = get_local_wire(self.gate.wire_ith_multiplicand_c(self.i));
+        let d = get_local_wire(self.gate.wire_ith_multiplicand_d(self.i));
+
+        let ab = a * b;
+        let cd = c * d;
+        let (output_result, output_borrow) = if ab >= cd {
+            (ab - cd, 0)
+        } else {
+            (ab + (1 << (2 * BITS)) - cd, 1)
+        };
+
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_result(self.i)),
+            F::from_canonical_u64(output_result),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_borrow(self.i)),
+            F::from_canonical_u64(output_borrow),
+        );
+
+        let num_limbs = CrossMulGate::<F, D, BITS>::num_limbs();
+        let limb_base = 1 << CrossMulGate::<F, D, BITS>::limb_bits();
+        let output_limbs = (0..num_limbs).scan(output_result, |acc, _| {
+            let tmp = *acc % limb_base;
+            *acc /= limb_base;
+            Some(F::from_canonical_u64(tmp))
+        });
+
+        for (j, limb) in output_limbs.enumerate() {
+            let wire = local_wire(self.gate.wire_ith_output_jth_limb(self.i, j));
+            out_buffer.set_wire(wire, limb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::extension_field::quartic::QuarticExtension;
+    use plonky2_field::field_types::{Field, PrimeField64};
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::cross_mul::CrossMulGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(CrossMulGate::<GoldilocksField, 4, 30> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(CrossMulGate::<GoldilocksField, D, 30> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        type F = GoldilocksField;
+        type FF = QuarticExtension<GoldilocksField>;
+        const D: usize = 4;
+        const BITS: usize = 30;
+
+        /// Returns the wires for the given inputs, with the host-computed `a * b - c * d` as
+        /// output. If `flip_borrow` is set, the borrow is inverted and the output adjusted to
+        /// match, which only satisfies the constraints modulo `2^(2 * BITS)`.
+        fn get_wires(inputs: &[[u64; 4]], flip_borrow: bool) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+
+            let limb_bits = CrossMulGate::<F, D, BITS>::limb_bits();
+            let num_limbs = CrossMulGate::<F, D, BITS>::num_limbs();
+            let limb_base = 1 << limb_bits;
+            for &[a, b, c, d] in inputs {
+                let borrow = (a * b < c * d) ^ flip_borrow;
+                let output = F::from_canonical_u64(a * b) - F::from_canonical_u64(c * d)
+                    + F::from_canonical_u64((borrow as u64) << (2 * BITS));
+
+                v0.extend([a, b, c, d].map(F::from_canonical_u64));
+                v0.push(output);
+                v0.push(F::from_bool(borrow));
+                let output_u64 = output.to_canonical_u64();
+                v1.extend(
+                    (0..num_limbs).map(|j| {
+                        F::from_canonical_u64((output_u64 >> (limb_bits * j)) % limb_base)
+                    }),
+                );
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut rand_input = || rng.gen::<u64>() >> (64 - BITS);
+        let max = (1 << BITS) - 1;
+        let inputs = vec![
+            [rand_input(), rand_input(), rand_input(), rand_input()],
+            // Underflows.
+            [1, 2, 3, 4],
+            [0, 0, max, max],
+        ];
+        let gate = CrossMulGate::<F, D, BITS> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, false),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // With the wrong borrow, the output either underflows or exceeds `2 * BITS` bits.
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, true),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Incorrect borrows should not satisfy the constraints."
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::ops::Range;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::extension_field::FieldExtension;
+use plonky2_field::field_types::Field;
+
+use crate::gates::gate::Gate;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBase};
+
+/// A gate which checks whether two extension field elements `x` and `y` are equal, outputting a
+/// boolean. With `d = x - y` and a witnessed `inv`, we enforce `output = 1 - d * inv` and
+/// `d * output = 0`. If `d != 0` the latter forces `output = 0`, and if `d = 0` the former forces
+/// `output = 1`; `inv` must then be the inverse of `d` when it is nonzero.
+#[derive(Debug, Clone)]
+pub struct ExtEqualGate<const D: usize> {
+    /// Number of comparisons performed by the gate.
+    pub num_ops: usize,
+}
+
+impl<const D: usize> ExtEqualGate<D> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        Self {
+            num_ops: Self::num_ops(config),
+        }
+    }
+
+    /// Determine the maximum number of operations that can fit in one gate for the given config.
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 3 * D + 1;
+        let routed_wires_per_op = 2 * D + 1;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wires_ith_input_x(&self, i: usize) -> Range<usize> {
+        debug_assert!(i < self.num_ops);
+        (2 * D + 1) * i..(2 * D + 1) * i + D
+    }
+    pub fn wires_ith_input_y(&self, i: usize) -> Range<usize> {
+        debug_assert!(i < self.num_ops);
+        (2 * D + 1) * i + D..(2 * D + 1) * i + 2 * D
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        (2 * D + 1) * i + 2 * D
+    }
+
+    // The inverses are not routed, so they are placed after all routed wires.
+    pub fn wires_ith_diff_inverse(&self, i: usize) -> Range<usize> {
+        debug_assert!(i < self.num_ops);
+        let start = (2 * D + 1) * self.num_ops + D * i;
+        start..start + D
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for ExtEqualGate<D> {
+    fn id(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::new();
+        for i in 0..self.num_ops {
+            let x = vars.get_local_ext_algebra(self.wires_ith_input_x(i));
+            let y = vars.get_local_ext_algebra(self.wires_ith_input_y(i));
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let inverse = vars.get_local_ext_algebra(self.wires_ith_diff_inverse(i));
+
+            let diff = x - y;
+            let mut is_nonzero = (diff * inverse).to_basefield_array();
+            is_nonzero[0] += output - F::Extension::ONE;
+            constraints.extend(is_nonzero);
+            constraints.extend(diff.scalar_mul(output).to_basefield_array());
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        vars: EvaluationVarsBase<F>,
+        mut yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        for i in 0..self.num_ops {
+            let x = vars.get_local_ext(self.wires_ith_input_x(i));
+            let y = vars.get_local_ext(self.wires_ith_input_y(i));
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let inverse = vars.get_local_ext(self.wires_ith_diff_inverse(i));
+
+            let diff = x - y;
+            let mut is_nonzero = (diff * inverse).to_basefield_array();
+            is_nonzero[0] += output - F::ONE;
+            yield_constr.many(is_nonzero);
+            yield_constr.many(diff.scalar_mul(output).to_basefield_array());
+        }
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::new();
+        for i in 0..self.num_ops {
+            let x = vars.get_local_ext_algebra(self.wires_ith_input_x(i));
+            let y = vars.get_local_ext_algebra(self.wires_ith_input_y(i));
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let inverse = vars.get_local_ext_algebra(self.wires_ith_diff_inverse(i));
+
+            let diff = builder.sub_ext_algebra(x, y);
+            let mut is_nonzero = builder.mul_ext_algebra(diff, inverse).to_ext_target_array();
+            let one = builder.one_extension();
+            let output_minus_one = builder.sub_extension(output, one);
+            is_nonzero[0] = builder.add_extension(is_nonzero[0], output_minus_one);
+            constraints.extend(is_nonzero);
+
+            let masked_diff = builder.scalar_mul_ext_algebra(output, diff);
+            constraints.extend(masked_diff.to_ext_target_array());
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    ExtEqualGenerator {
+                        gate: self.clone(),
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    // Zero wires don't satisfy the constraints, as equal inputs must give an output of one.
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(
+            self.wires_ith_input_x(op)
+                .chain(self.wires_ith_input_y(op))
+                .map(|wire| (wire, F::ZERO))
+                .collect(),
+        )
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (3 * D + 1)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * 2 * D
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ExtEqualGenerator<const D: usize> {
+    gate: ExtEqualGate<D>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F> for ExtEqualGenerator<D> {
+    fn dependencies(&self) -> Vec<Target> {
+        self.gate
+            .wires_ith_input_x(self.i)
+            .chain(self.gate.wires_ith_input_y(self.i))
+            .map(|i| Target::wire(self.gate_index, i))
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let extract_extension = |range: Range<usize>| -> F::Extension {
+            let t = ExtensionTarget::from_range(self.gate_index, range);
+            witness.get_extension_target(t)
+        };
+
+        let x = extract_extension(self.gate.wires_ith_input_x(self.i));
+        let y = extract_extension(self.gate.wires_ith_input_y(self.i));
+        let diff = x - y;
+
+        let output_target = Target::wire(self.gate_index, self.gate.wire_ith_output(self.i));
+        out_buffer.set_target(output_target, F::from_bool(diff.is_zero()));
+
+        let inverse_target =
+            ExtensionTarget::from_range(self.gate_index, self.gate.wires_ith_diff_inverse(self.i));
+        out_buffer.set_extension_target(
+            inverse_target,
+            diff.try_inverse().unwrap_or(F::Extension::ZERO),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::extension_field::FieldExtension;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::ext_equal::ExtEqualGate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn low_degree() {
+        let gate = ExtEqualGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_low_degree::<GoldilocksField, _, 4>(gate);
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = ExtEqualGate::new_from_config(&CircuitConfig::standard_recursion_config());
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+
+    #[test]
+    fn test_is_equal_extension() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x_value = FF::rand();
+        // Differing in a single coordinate must be detected.
+        let mut y_arr: [F; D] = x_value.to_basefield_array();
+        y_arr[D - 1] += F::ONE;
+        let y_value = FF::from_basefield_array(y_arr);
+
+        let x = builder.add_virtual_extension_target();
+        let x_copy = builder.add_virtual_extension_target();
+        let y = builder.add_virtual_extension_target();
+        pw.set_extension_target(x, x_value);
+        pw.set_extension_target(x_copy, x_value);
+        pw.set_extension_target(y, y_value);
+
+        let equal = builder.is_equal_extension(x, x_copy);
+        let not_equal = builder.is_equal_extension(x, y);
+        builder.assert_one(equal.target);
+        builder.assert_zero(not_equal.target);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
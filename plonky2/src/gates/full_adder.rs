@@ -0,0 +1,367 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate of 1-bit full-adder cells, for bit-serial designs such as ripple adders. Each operation
+/// takes boolean inputs `a`, `b` and `cin`, and outputs `sum = a ^ b ^ cin` and
+/// `cout = Maj(a, b, cin)`. Given boolean inputs and outputs, these are fully determined by
+/// `a + b + cin = sum + 2 * cout`, so no auxiliary wires are needed.
+#[derive(Copy, Clone, Debug)]
+pub struct FullAdderGate<F: RichField + Extendable<D>, const D: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> FullAdderGate<F, D> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 5;
+        config.num_routed_wires / wires_per_op
+    }
+
+    pub fn wire_ith_a(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i
+    }
+    pub fn wire_ith_b(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 1
+    }
+    pub fn wire_ith_carry_in(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 2
+    }
+    pub fn wire_ith_sum(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 3
+    }
+    pub fn wire_ith_carry_out(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 4
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for FullAdderGate<F, D> {
+    fn id(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_a(i)];
+            let b = vars.local_wires[self.wire_ith_b(i)];
+            let carry_in = vars.local_wires[self.wire_ith_carry_in(i)];
+            let sum = vars.local_wires[self.wire_ith_sum(i)];
+            let carry_out = vars.local_wires[self.wire_ith_carry_out(i)];
+
+            for bit in [a, b, carry_in, sum, carry_out] {
+                constraints.push(bit * (bit - F::Extension::ONE));
+            }
+            constraints.push(a + b + carry_in - sum - carry_out.double());
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_a(i)];
+            let b = vars.local_wires[self.wire_ith_b(i)];
+            let carry_in = vars.local_wires[self.wire_ith_carry_in(i)];
+            let sum = vars.local_wires[self.wire_ith_sum(i)];
+            let carry_out = vars.local_wires[self.wire_ith_carry_out(i)];
+
+            for bit in [a, b, carry_in, sum, carry_out] {
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+            }
+            let inputs = builder.add_many_extension(&[a, b, carry_in]);
+            let outputs = builder.mul_const_add_extension(F::TWO, carry_out, sum);
+            constraints.push(builder.sub_extension(inputs, outputs));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    FullAdderGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * 5
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * 6
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D>
+    for FullAdderGate<F, D>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let a = vars.local_wires[self.wire_ith_a(i)];
+            let b = vars.local_wires[self.wire_ith_b(i)];
+            let carry_in = vars.local_wires[self.wire_ith_carry_in(i)];
+            let sum = vars.local_wires[self.wire_ith_sum(i)];
+            let carry_out = vars.local_wires[self.wire_ith_carry_out(i)];
+
+            for bit in [a, b, carry_in, sum, carry_out] {
+                yield_constr.one(bit * (bit - F::ONE));
+            }
+            yield_constr.one(a + b + carry_in - sum - carry_out * F::TWO);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct FullAdderGenerator<F: RichField + Extendable<D>, const D: usize> {
+    gate: FullAdderGate<F, D>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F> for FullAdderGenerator<F, D> {
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        vec![
+            local_target(self.gate.wire_ith_a(self.i)),
+            local_target(self.gate.wire_ith_b(self.i)),
+            local_target(self.gate.wire_ith_carry_in(self.i)),
+        ]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input)).to_canonical_u64();
+
+        let a = get_local_wire(self.gate.wire_ith_a(self.i));
+        let b = get_local_wire(self.gate.wire_ith_b(self.i));
+        let carry_in = get_local_wire(self.gate.wire_ith_carry_in(self.i));
+        debug_assert!((a | b | carry_in) >> 1 == 0, "Inputs are not boolean");
+
+        let total = a + b + carry_in;
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_sum(self.i)),
+            F::from_canonical_u64(total & 1),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_carry_out(self.i)),
+            F::from_canonical_u64(total >> 1),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::full_adder::FullAdderGate;
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    /// All eight combinations of `(a, b, carry_in)`.
+    fn all_inputs() -> Vec<[u64; 3]> {
+        (0..8u64).map(|x| [x & 1, (x >> 1) & 1, x >> 2]).collect()
+    }
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(FullAdderGate::<GoldilocksField, 4> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(FullAdderGate::<F, D> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        /// Returns the local wires for one operation per row of `values`, each of the form
+        /// `[a, b, carry_in, sum, carry_out]`.
+        fn get_wires(values: &[[u64; 5]]) -> Vec<FF> {
+            values
+                .iter()
+                .flat_map(|v| v.map(|x| F::from_canonical_u64(x).into()))
+                .collect()
+        }
+
+        let inputs = all_inputs();
+        let gate = FullAdderGate::<F, D> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+        let eval = |values: &[[u64; 5]]| {
+            gate.eval_unfiltered(EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(values),
+                public_inputs_hash: &HashOut::rand(),
+            })
+        };
+
+        let good_values: Vec<[u64; 5]> = inputs
+            .iter()
+            .map(|&[a, b, c]| [a, b, c, a ^ b ^ c, (a & b) | (a & c) | (b & c)])
+            .collect();
+        assert!(
+            eval(&good_values).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        for op in 0..inputs.len() {
+            let mut wrong_sum = good_values.clone();
+            wrong_sum[op][3] ^= 1;
+            assert!(
+                !eval(&wrong_sum).iter().all(|x| x.is_zero()),
+                "Incorrect sums should not satisfy the constraints."
+            );
+        }
+
+        // `2 + 0 + 0 = 0 + 2 * 1`, but the input isn't boolean.
+        let mut non_boolean = good_values;
+        non_boolean[0] = [2, 0, 0, 0, 1];
+        assert!(
+            !eval(&non_boolean).iter().all(|x| x.is_zero()),
+            "Non-boolean inputs should not satisfy the constraints."
+        );
+    }
+
+    #[test]
+    fn test_full_adder() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let gate = FullAdderGate::<F, D>::new_from_config(&builder.config);
+        for [a, b, c] in all_inputs() {
+            let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+            for (wire, value) in [
+                (gate.wire_ith_a(i), a),
+                (gate.wire_ith_b(i), b),
+                (gate.wire_ith_carry_in(i), c),
+            ] {
+                let target = builder.add_virtual_target();
+                builder.connect(target, Target::wire(gate_index, wire));
+                pw.set_target(target, F::from_canonical_u64(value));
+            }
+
+            let total = a + b + c;
+            for (wire, expected) in [
+                (gate.wire_ith_sum(i), total & 1),
+                (gate.wire_ith_carry_out(i), total >> 1),
+            ] {
+                let expected = builder.constant(F::from_canonical_u64(expected));
+                builder.connect(Target::wire(gate_index, wire), expected);
+            }
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
@@ -148,6 +148,21 @@ pub trait Gate<F: RichField + Extendable<D>, const D: usize>: 'static + Send + S
         self.generators(0, &vec![F::ZERO; self.num_constants()])
             .len()
     }
+
+    /// Values for the input wires of operation `op`, as `(wire, value)` pairs, from which the
+    /// gate's generators produce a witness satisfying its constraints. `build` assigns them to the
+    /// unused operations of gates packed via `find_slot`. Gates returning `None`, the default, must
+    /// instead be satisfied by all-zero wires, since the generators of unused operations are then
+    /// dropped and their wires left unset.
+    fn unused_op_inputs(&self, _op: usize) -> Option<Vec<(usize, F)>> {
+        None
+    }
+
+    /// Describes the role of each wire used by this gate, as pairs of wire indices and labels.
+    /// This is only used for debugging circuit layouts; gates may leave it empty.
+    fn wire_labels(&self) -> Vec<(usize, String)> {
+        Vec::new()
+    }
 }
 
 /// A wrapper around an `Rc<Gate>` which implements `PartialEq`, `Eq` and `Hash` based on gate IDs.
@@ -211,6 +226,15 @@ impl<F: RichField + Extendable<D>, const D: usize> PrefixedGate<F, D> {
     }
 }
 
+/// Estimates the total number of constraints of the given gates by summing their
+/// `num_constraints`. Since most gates pack a config-dependent number of operations, they should
+/// be constructed from the final `CircuitConfig` before calling this.
+pub fn estimate_constraints<F: RichField + Extendable<D>, const D: usize>(
+    gates: &[&dyn Gate<F, D>],
+) -> usize {
+    gates.iter().map(|gate| gate.num_constraints()).sum()
+}
+
 /// A gate's filter is computed as `prod b_i*c_i + (1-b_i)*(1-c_i)`, with `(b_i)` the prefix and
 /// `(c_i)` the local constants, which is one if the prefix of `constants` matches `prefix`.
 fn compute_filter<'a, K: Field, T: IntoIterator<Item = &'a K>>(prefix: &[bool], constants: T) -> K {
@@ -241,3 +265,38 @@ fn compute_filter_recursively<F: RichField + Extendable<D>, const D: usize>(
 
     builder.mul_many_extension(&v)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::gates::arithmetic_u32::U32ArithmeticGate;
+    use crate::gates::binary_mul_const::BinaryMulConstGate;
+    use crate::gates::gate::{estimate_constraints, Gate};
+    use crate::gates::subtraction_u32::U32SubtractionGate;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_estimate_constraints() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // 135 wires, of which 80 are routed.
+        let config = CircuitConfig::standard_recursion_config();
+        let arithmetic = U32ArithmeticGate::<F, D>::new_from_config(&config);
+        let subtraction = U32SubtractionGate::<F, D>::new_from_config(&config);
+        let mul_const = BinaryMulConstGate::<F, D, 16, 3>::new_from_config(&config);
+
+        assert_eq!(estimate_constraints::<F, D>(&[]), 0);
+        // 3 ops of 37 wires, each with 3 constraints plus one per 2-bit limb of the 64-bit result.
+        assert_eq!(estimate_constraints(&[&arithmetic]), 3 * (3 + 32));
+
+        // Subtraction: 6 ops of 21 wires, with 16 limbs each. Multiplication by a constant: 6 ops
+        // of 20 wires, with 16 limbs each.
+        let gates: [&dyn Gate<F, D>; 3] = [&arithmetic, &subtraction, &mul_const];
+        assert_eq!(
+            estimate_constraints(&gates),
+            105 + 6 * (3 + 16) + 6 * (3 + 16)
+        );
+    }
+}
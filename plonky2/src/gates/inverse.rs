@@ -0,0 +1,301 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate which checks a witnessed inverse, enforcing `x * x_inv = 1`. This also proves that `x`
+/// is nonzero, as zero has no inverse.
+#[derive(Copy, Clone, Debug)]
+pub struct InverseGate<F: RichField + Extendable<D>, const D: usize> {
+    /// Number of inverse checks performed by the gate.
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> InverseGate<F, D> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Determine the maximum number of operations that can fit in one gate for the given config.
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 2;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / wires_per_op)
+    }
+
+    pub fn wire_ith_x(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i
+    }
+    pub fn wire_ith_x_inv(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i + 1
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for InverseGate<F, D> {
+    fn id(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let x = vars.local_wires[self.wire_ith_x(i)];
+            let x_inv = vars.local_wires[self.wire_ith_x_inv(i)];
+
+            constraints.push(x * x_inv - F::Extension::ONE);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let one = builder.one_extension();
+        for i in 0..self.num_ops {
+            let x = vars.local_wires[self.wire_ith_x(i)];
+            let x_inv = vars.local_wires[self.wire_ith_x_inv(i)];
+
+            constraints.push(builder.mul_sub_extension(x, x_inv, one));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    InverseGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    // Zero has no inverse, so unused operations are given inputs of one.
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(vec![(self.wire_ith_x(op), F::ONE)])
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * 2
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D> for InverseGate<F, D> {
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let x = vars.local_wires[self.wire_ith_x(i)];
+            let x_inv = vars.local_wires[self.wire_ith_x_inv(i)];
+
+            yield_constr.one(x * x_inv - F::ONE);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct InverseGenerator<F: RichField + Extendable<D>, const D: usize> {
+    gate: InverseGate<F, D>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F> for InverseGenerator<F, D> {
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(self.gate_index, self.gate.wire_ith_x(self.i))]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let x = witness.get_wire(Wire {
+            gate: self.gate_index,
+            input: self.gate.wire_ith_x(self.i),
+        });
+
+        out_buffer.set_wire(
+            Wire {
+                gate: self.gate_index,
+                input: self.gate.wire_ith_x_inv(self.i),
+            },
+            x.inverse(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::inverse::InverseGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(InverseGate::<GoldilocksField, 4> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(InverseGate::<GoldilocksField, D> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        fn get_wires(ops: &[(F, F)]) -> Vec<FF> {
+            ops.iter()
+                .flat_map(|&(x, x_inv)| [x, x_inv])
+                .map(|x| x.into())
+                .collect()
+        }
+
+        let x = F::rand();
+        let gate = InverseGate::<F, D> {
+            num_ops: 2,
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&[(x, x.inverse()), (F::ONE, F::ONE)]),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Zero has no inverse, so no choice of `x_inv` satisfies the constraint.
+        for wrong_op in [(x, x), (F::ZERO, F::ZERO), (F::ZERO, F::rand())] {
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&[wrong_op, wrong_op]),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "A wrong inverse should not satisfy the constraints."
+            );
+        }
+    }
+
+    /// Builds a circuit checking the inverse of `x_value` in a partially used `InverseGate`.
+    fn prove_inverse(x_value: GoldilocksField) -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let gate = InverseGate::<F, D>::new_from_config(&builder.config);
+        let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+        let x = builder.add_virtual_target();
+        builder.connect(x, Target::wire(gate_index, gate.wire_ith_x(i)));
+        pw.set_target(x, x_value);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_inverse() -> Result<()> {
+        prove_inverse(GoldilocksField::rand())
+    }
+
+    #[test]
+    #[should_panic(expected = "Tried to invert zero")]
+    fn test_inverse_of_zero_fails() {
+        prove_inverse(GoldilocksField::ZERO).unwrap();
+    }
+}
@@ -0,0 +1,401 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute the SHA-256 majority function `Maj(x, y, z) = (x & y) ^ (x & z) ^ (y & z)` on
+/// `BITS`-bit values. All three inputs are split into bits, which also range-checks them, and each
+/// output bit is `x_j y_j + x_j z_j + y_j z_j - 2 x_j y_j z_j`, i.e. set if at least two of the
+/// input bits are.
+#[derive(Copy, Clone, Debug)]
+pub struct MajGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> MajGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS < 64, "Values must fit in the field");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 4 + 3 * BITS;
+        let routed_wires_per_op = 4;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input_x(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i
+    }
+    pub fn wire_ith_input_y(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 1
+    }
+    pub fn wire_ith_input_z(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 2
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 3
+    }
+
+    pub fn wire_ith_x_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        4 * self.num_ops + 3 * BITS * i + j
+    }
+    pub fn wire_ith_y_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        4 * self.num_ops + 3 * BITS * i + BITS + j
+    }
+    pub fn wire_ith_z_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        4 * self.num_ops + 3 * BITS * i + 2 * BITS + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for MajGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let x = vars.local_wires[self.wire_ith_input_x(i)];
+            let y = vars.local_wires[self.wire_ith_input_y(i)];
+            let z = vars.local_wires[self.wire_ith_input_z(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_x = F::Extension::ZERO;
+            let mut computed_y = F::Extension::ZERO;
+            let mut computed_z = F::Extension::ZERO;
+            let mut computed_output = F::Extension::ZERO;
+            for j in (0..BITS).rev() {
+                let x_bit = vars.local_wires[self.wire_ith_x_bit(i, j)];
+                let y_bit = vars.local_wires[self.wire_ith_y_bit(i, j)];
+                let z_bit = vars.local_wires[self.wire_ith_z_bit(i, j)];
+                for bit in [x_bit, y_bit, z_bit] {
+                    constraints.push(bit * (bit - F::Extension::ONE));
+                }
+
+                let y_z = y_bit * z_bit;
+                let output_bit = x_bit * (y_bit + z_bit) + y_z - x_bit * y_z * F::Extension::TWO;
+                computed_x = computed_x.double() + x_bit;
+                computed_y = computed_y.double() + y_bit;
+                computed_z = computed_z.double() + z_bit;
+                computed_output = computed_output.double() + output_bit;
+            }
+
+            constraints.push(computed_x - x);
+            constraints.push(computed_y - y);
+            constraints.push(computed_z - z);
+            constraints.push(computed_output - output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let two = F::TWO;
+        for i in 0..self.num_ops {
+            let x = vars.local_wires[self.wire_ith_input_x(i)];
+            let y = vars.local_wires[self.wire_ith_input_y(i)];
+            let z = vars.local_wires[self.wire_ith_input_z(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_x = builder.zero_extension();
+            let mut computed_y = builder.zero_extension();
+            let mut computed_z = builder.zero_extension();
+            let mut computed_output = builder.zero_extension();
+            for j in (0..BITS).rev() {
+                let x_bit = vars.local_wires[self.wire_ith_x_bit(i, j)];
+                let y_bit = vars.local_wires[self.wire_ith_y_bit(i, j)];
+                let z_bit = vars.local_wires[self.wire_ith_z_bit(i, j)];
+                for bit in [x_bit, y_bit, z_bit] {
+                    constraints.push(builder.mul_sub_extension(bit, bit, bit));
+                }
+
+                let y_plus_z = builder.add_extension(y_bit, z_bit);
+                let y_z = builder.mul_extension(y_bit, z_bit);
+                let output_bit = builder.mul_add_extension(x_bit, y_plus_z, y_z);
+                let output_bit = builder.arithmetic_extension(-two, F::ONE, x_bit, y_z, output_bit);
+                computed_x = builder.mul_const_add_extension(two, computed_x, x_bit);
+                computed_y = builder.mul_const_add_extension(two, computed_y, y_bit);
+                computed_z = builder.mul_const_add_extension(two, computed_z, z_bit);
+                computed_output = builder.mul_const_add_extension(two, computed_output, output_bit);
+            }
+
+            constraints.push(builder.sub_extension(computed_x, x));
+            constraints.push(builder.sub_extension(computed_y, y));
+            constraints.push(builder.sub_extension(computed_z, z));
+            constraints.push(builder.sub_extension(computed_output, output));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    MajGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (4 + 3 * BITS)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        3
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (3 * BITS + 4)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for MajGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let x = vars.local_wires[self.wire_ith_input_x(i)];
+            let y = vars.local_wires[self.wire_ith_input_y(i)];
+            let z = vars.local_wires[self.wire_ith_input_z(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_x = P::ZEROS;
+            let mut computed_y = P::ZEROS;
+            let mut computed_z = P::ZEROS;
+            let mut computed_output = P::ZEROS;
+            for j in (0..BITS).rev() {
+                let x_bit = vars.local_wires[self.wire_ith_x_bit(i, j)];
+                let y_bit = vars.local_wires[self.wire_ith_y_bit(i, j)];
+                let z_bit = vars.local_wires[self.wire_ith_z_bit(i, j)];
+                for bit in [x_bit, y_bit, z_bit] {
+                    yield_constr.one(bit * (bit - F::ONE));
+                }
+
+                let y_z = y_bit * z_bit;
+                let output_bit = x_bit * (y_bit + z_bit) + y_z - x_bit * y_z * F::TWO;
+                computed_x = computed_x * F::TWO + x_bit;
+                computed_y = computed_y * F::TWO + y_bit;
+                computed_z = computed_z * F::TWO + z_bit;
+                computed_output = computed_output * F::TWO + output_bit;
+            }
+
+            yield_constr.one(computed_x - x);
+            yield_constr.one(computed_y - y);
+            yield_constr.one(computed_z - z);
+            yield_constr.one(computed_output - output);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MajGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: MajGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for MajGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        vec![
+            local_target(self.gate.wire_ith_input_x(self.i)),
+            local_target(self.gate.wire_ith_input_y(self.i)),
+            local_target(self.gate.wire_ith_input_z(self.i)),
+        ]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input)).to_canonical_u64();
+
+        let x = get_local_wire(self.gate.wire_ith_input_x(self.i));
+        let y = get_local_wire(self.gate.wire_ith_input_y(self.i));
+        let z = get_local_wire(self.gate.wire_ith_input_z(self.i));
+        debug_assert!(
+            (x | y | z) >> BITS == 0,
+            "Inputs have more than {} bits",
+            BITS
+        );
+
+        for j in 0..BITS {
+            let bit = |value: u64| F::from_canonical_u64((value >> j) & 1);
+            out_buffer.set_wire(local_wire(self.gate.wire_ith_x_bit(self.i, j)), bit(x));
+            out_buffer.set_wire(local_wire(self.gate.wire_ith_y_bit(self.i, j)), bit(y));
+            out_buffer.set_wire(local_wire(self.gate.wire_ith_z_bit(self.i, j)), bit(z));
+        }
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output(self.i)),
+            F::from_canonical_u64((x & y) ^ (x & z) ^ (y & z)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::maj::MajGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(MajGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(MajGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 32;
+        const NUM_OPS: usize = 3;
+
+        fn get_wires(inputs: &[[u32; 3]], outputs: &[u32]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            for (&[x, y, z], &output) in inputs.iter().zip(outputs) {
+                v0.extend([x, y, z, output].map(F::from_canonical_u32));
+                for value in [x, y, z] {
+                    v1.extend((0..BITS).map(|j| F::from_canonical_u32((value >> j) & 1)));
+                }
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let inputs: Vec<[u32; 3]> = (0..NUM_OPS).map(|_| rng.gen()).collect();
+        let outputs: Vec<u32> = inputs
+            .iter()
+            .map(|&[x, y, z]| (x & y) ^ (x & z) ^ (y & z))
+            .collect();
+        let gate = MajGate::<F, D, BITS> {
+            num_ops: NUM_OPS,
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // `Ch` agrees with `Maj` on some bits, but not on all of them.
+        let wrong_outputs: Vec<u32> = inputs.iter().map(|&[x, y, z]| (x & y) ^ (!x & z)).collect();
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &wrong_outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Incorrect outputs should not satisfy the constraints."
+        );
+    }
+}
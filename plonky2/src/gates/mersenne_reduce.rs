@@ -0,0 +1,560 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+use plonky2_util::ceil_div_usize;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to reduce an input `x < 2^(2 BITS)` modulo the Mersenne number `m = 2^BITS - 1`, e.g. for
+/// arithmetic in the field of order `2^31 - 1`. Since `2^BITS = 1 mod m`, splitting
+/// `x = hi * 2^BITS + lo` gives `x = hi + lo mod m`, so no quotient by `m` has to be witnessed.
+///
+/// `hi`, `lo` and the output are range-checked to `BITS` bits. As `hi + lo <= 2 m`, the fold is
+/// reduced by subtracting `m` at most twice, which we check with
+/// `d (d - m) (d - 2 m) = 0` for `d = hi + lo - output`. A `BITS`-bit output could still equal `m`,
+/// so we rule that out by witnessing the inverse of `output - m`.
+#[derive(Copy, Clone, Debug)]
+pub struct MersenneReduceGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize>
+    MersenneReduceGate<F, D, BITS>
+{
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS >= 2, "The modulus must be at least 3");
+        assert!(
+            2 * BITS < 64,
+            "Inputs of {} bits would overflow the field",
+            2 * BITS
+        );
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 3 + 3 * Self::num_limbs();
+        let routed_wires_per_op = 2;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    /// The modulus `2^BITS - 1`.
+    pub fn modulus() -> u64 {
+        (1 << BITS) - 1
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i + 1
+    }
+
+    pub fn wire_ith_output_diff_inverse(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * self.num_ops + i
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // `BITS` may be odd, in which case the most significant limb only has one bit.
+    pub fn num_limbs() -> usize {
+        ceil_div_usize(BITS, Self::limb_bits())
+    }
+    /// The number of values the `j`th limb of a `BITS`-bit value can take.
+    fn limb_range(j: usize) -> usize {
+        let bits = (BITS - j * Self::limb_bits()).min(Self::limb_bits());
+        1 << bits
+    }
+
+    pub fn wire_ith_high_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        3 * self.num_ops + 3 * Self::num_limbs() * i + j
+    }
+    pub fn wire_ith_low_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        3 * self.num_ops + 3 * Self::num_limbs() * i + Self::num_limbs() + j
+    }
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        3 * self.num_ops + 3 * Self::num_limbs() * i + 2 * Self::num_limbs() + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for MersenneReduceGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let modulus = F::Extension::from_canonical_u64(Self::modulus());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut high = F::Extension::ZERO;
+            let mut low = F::Extension::ZERO;
+            let mut combined_output = F::Extension::ZERO;
+            let base = F::Extension::from_canonical_u64(1 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let high_limb = vars.local_wires[self.wire_ith_high_jth_limb(i, j)];
+                let low_limb = vars.local_wires[self.wire_ith_low_jth_limb(i, j)];
+                let output_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                for limb in [high_limb, low_limb, output_limb] {
+                    let product = (0..Self::limb_range(j))
+                        .map(|x| limb - F::Extension::from_canonical_usize(x))
+                        .product();
+                    constraints.push(product);
+                }
+
+                high = base * high + high_limb;
+                low = base * low + low_limb;
+                combined_output = base * combined_output + output_limb;
+            }
+
+            let split = high * F::Extension::from_canonical_u64(1 << BITS) + low;
+            constraints.push(split - input);
+            constraints.push(combined_output - output);
+
+            let diff = high + low - output;
+            constraints.push(diff * (diff - modulus) * (diff - modulus.double()));
+
+            let inverse = vars.local_wires[self.wire_ith_output_diff_inverse(i)];
+            constraints.push((output - modulus) * inverse - F::Extension::ONE);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let modulus = F::from_canonical_u64(Self::modulus());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut high = builder.zero_extension();
+            let mut low = builder.zero_extension();
+            let mut combined_output = builder.zero_extension();
+            let base = F::from_canonical_u64(1 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let high_limb = vars.local_wires[self.wire_ith_high_jth_limb(i, j)];
+                let low_limb = vars.local_wires[self.wire_ith_low_jth_limb(i, j)];
+                let output_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                for limb in [high_limb, low_limb, output_limb] {
+                    let mut product = builder.one_extension();
+                    for x in 0..Self::limb_range(j) {
+                        let diff = builder.add_const_extension(limb, -F::from_canonical_usize(x));
+                        product = builder.mul_extension(product, diff);
+                    }
+                    constraints.push(product);
+                }
+
+                high = builder.mul_const_add_extension(base, high, high_limb);
+                low = builder.mul_const_add_extension(base, low, low_limb);
+                combined_output =
+                    builder.mul_const_add_extension(base, combined_output, output_limb);
+            }
+
+            let split =
+                builder.mul_const_add_extension(F::from_canonical_u64(1 << BITS), high, low);
+            constraints.push(builder.sub_extension(split, input));
+            constraints.push(builder.sub_extension(combined_output, output));
+
+            let high_plus_low = builder.add_extension(high, low);
+            let diff = builder.sub_extension(high_plus_low, output);
+            let diff_minus_modulus = builder.add_const_extension(diff, -modulus);
+            let diff_minus_double_modulus = builder.add_const_extension(diff, -modulus.double());
+            constraints.push(builder.mul_many_extension(&[
+                diff,
+                diff_minus_modulus,
+                diff_minus_double_modulus,
+            ]));
+
+            let inverse = vars.local_wires[self.wire_ith_output_diff_inverse(i)];
+            let output_diff = builder.add_const_extension(output, -modulus);
+            let one = builder.one_extension();
+            constraints.push(builder.mul_sub_extension(output_diff, inverse, one));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    MersenneReduceGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    // Zero wires don't satisfy the constraints, as `output - m` must have an inverse.
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(vec![(self.wire_ith_input(op), F::ZERO)])
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (3 + 3 * Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (4 + 3 * Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for MersenneReduceGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        let modulus = F::from_canonical_u64(Self::modulus());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut high = P::ZEROS;
+            let mut low = P::ZEROS;
+            let mut combined_output = P::ZEROS;
+            let base = F::from_canonical_u64(1 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let high_limb = vars.local_wires[self.wire_ith_high_jth_limb(i, j)];
+                let low_limb = vars.local_wires[self.wire_ith_low_jth_limb(i, j)];
+                let output_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                for limb in [high_limb, low_limb, output_limb] {
+                    let product = (0..Self::limb_range(j))
+                        .map(|x| limb - F::from_canonical_usize(x))
+                        .product();
+                    yield_constr.one(product);
+                }
+
+                high = high * base + high_limb;
+                low = low * base + low_limb;
+                combined_output = combined_output * base + output_limb;
+            }
+
+            let split = high * F::from_canonical_u64(1 << BITS) + low;
+            yield_constr.one(split - input);
+            yield_constr.one(combined_output - output);
+
+            let diff = high + low - output;
+            yield_constr.one(diff * (diff - modulus) * (diff - modulus.double()));
+
+            let inverse = vars.local_wires[self.wire_ith_output_diff_inverse(i)];
+            yield_constr.one((output - modulus) * inverse - P::ONES);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MersenneReduceGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: MersenneReduceGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for MersenneReduceGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(
+            input >> (2 * BITS) == 0,
+            "Input does not fit in {} bits",
+            2 * BITS
+        );
+
+        let modulus = MersenneReduceGate::<F, D, BITS>::modulus();
+        let high = input >> BITS;
+        let low = input & modulus;
+        let output = input % modulus;
+
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output(self.i)),
+            F::from_canonical_u64(output),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_diff_inverse(self.i)),
+            (F::from_canonical_u64(output) - F::from_canonical_u64(modulus)).inverse(),
+        );
+
+        let num_limbs = MersenneReduceGate::<F, D, BITS>::num_limbs();
+        let limb_bits = MersenneReduceGate::<F, D, BITS>::limb_bits();
+        let limb_mask = (1 << limb_bits) - 1;
+        for j in 0..num_limbs {
+            let limb = |value: u64| F::from_canonical_u64((value >> (limb_bits * j)) & limb_mask);
+            for (wire, value) in [
+                (self.gate.wire_ith_high_jth_limb(self.i, j), high),
+                (self.gate.wire_ith_low_jth_limb(self.i, j), low),
+                (self.gate.wire_ith_output_jth_limb(self.i, j), output),
+            ] {
+                out_buffer.set_wire(local_wire(wire), limb(value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::mersenne_reduce::MersenneReduceGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    /// Returns inputs below `2^(2 bits)` covering the edge cases of the fold: zero, the modulus and
+    /// its multiples, the largest input, and random values.
+    fn test_inputs(bits: usize) -> Vec<u64> {
+        let modulus = (1 << bits) - 1;
+        let max = (1 << (2 * bits)) - 1;
+        let mut rng = rand::thread_rng();
+        vec![
+            0,
+            1,
+            modulus - 1,
+            modulus,
+            modulus + 1,
+            1 << bits,
+            2 * modulus,
+            modulus * modulus,
+            max,
+            rng.gen_range(0..=max),
+            rng.gen_range(0..=max),
+        ]
+    }
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(MersenneReduceGate::<GoldilocksField, 4, 31> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(MersenneReduceGate::<F, D, 31> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    fn test_gate_constraint_with<const BITS: usize>() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        fn get_wires<const BITS: usize>(inputs: &[u64], outputs: &[u64]) -> Vec<FF> {
+            let modulus = MersenneReduceGate::<F, D, BITS>::modulus();
+            let num_limbs = MersenneReduceGate::<F, D, BITS>::num_limbs();
+            let limb_bits = MersenneReduceGate::<F, D, BITS>::limb_bits();
+            let limbs = |value: u64| {
+                (0..num_limbs).map(move |j| {
+                    F::from_canonical_u64((value >> (limb_bits * j)) & ((1 << limb_bits) - 1))
+                })
+            };
+
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            let mut v2 = Vec::new();
+            for (&input, &output) in inputs.iter().zip(outputs) {
+                v0.push(F::from_canonical_u64(input));
+                v0.push(F::from_canonical_u64(output));
+                // An output of `m` has no inverse to witness, so we use zero instead.
+                let output_diff = F::from_canonical_u64(output) - F::from_canonical_u64(modulus);
+                v1.push(output_diff.try_inverse().unwrap_or(F::ZERO));
+                v2.extend(limbs(input >> BITS));
+                v2.extend(limbs(input & modulus));
+                v2.extend(limbs(output));
+            }
+
+            v0.iter()
+                .chain(v1.iter())
+                .chain(v2.iter())
+                .map(|&x| x.into())
+                .collect()
+        }
+
+        let inputs = test_inputs(BITS);
+        let modulus = MersenneReduceGate::<F, D, BITS>::modulus();
+        let outputs: Vec<_> = inputs.iter().map(|x| x % modulus).collect();
+        let gate = MersenneReduceGate::<F, D, BITS> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires::<BITS>(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        let wrong_outputs: Vec<_> = outputs.iter().map(|x| (x + 1) % modulus).collect();
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires::<BITS>(&inputs, &wrong_outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Incorrect outputs should not satisfy the constraints."
+        );
+
+        // A multiple of the modulus must reduce to zero rather than to the modulus itself, even
+        // though the fold constraint alone would accept either.
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires::<BITS>(&[modulus], &[modulus]),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        let gate = MersenneReduceGate::<F, D, BITS> {
+            num_ops: 1,
+            _phantom: PhantomData,
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Unreduced outputs should not satisfy the constraints."
+        );
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        // 2^31 - 1 and 2^13 - 1 are Mersenne primes, and 2^16 - 1 checks an even number of bits.
+        test_gate_constraint_with::<31>();
+        test_gate_constraint_with::<13>();
+        test_gate_constraint_with::<16>();
+    }
+
+    #[test]
+    fn test_mersenne_reduce() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        const BITS: usize = 31;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let gate = MersenneReduceGate::<F, D, BITS>::new_from_config(&builder.config);
+        let modulus = MersenneReduceGate::<F, D, BITS>::modulus();
+        // The last row is only partially used, so its unused operations must be filled in.
+        let inputs = test_inputs(BITS);
+        assert_ne!(inputs.len() % gate.num_ops, 0);
+        for &input in &inputs {
+            let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+            let x = builder.add_virtual_target();
+            builder.connect(x, Target::wire(gate_index, gate.wire_ith_input(i)));
+            pw.set_target(x, F::from_canonical_u64(input));
+
+            let output = Target::wire(gate_index, gate.wire_ith_output(i));
+            let expected = builder.constant(F::from_canonical_u64(input % modulus));
+            builder.connect(output, expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
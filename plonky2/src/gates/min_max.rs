@@ -0,0 +1,380 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute both `min(x, y)` and `max(x, y)` of `BITS`-bit words `x` and `y`, along with
+/// the comparison bit `lt = (x < y)`. We enforce `min = lt * (x - y) + y` and `min + max = x + y`,
+/// and split `max - min - lt` into `BITS` bits. The split shows that `y - x - 1` is non-negative
+/// when `lt` is set, and that `x - y` is otherwise, so the outputs, including `lt` when `x == y`,
+/// are fully determined. The inputs are assumed to be range-checked already.
+#[derive(Copy, Clone, Debug)]
+pub struct MinMaxGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> MinMaxGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS < 63, "Differences must fit in the field");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 5 + BITS;
+        let routed_wires_per_op = 5;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_x(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i
+    }
+    pub fn wire_ith_y(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 1
+    }
+    pub fn wire_ith_min(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 2
+    }
+    pub fn wire_ith_max(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 3
+    }
+    pub fn wire_ith_lt(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        5 * i + 4
+    }
+
+    /// Bit `j` of `max - min - lt`.
+    pub fn wire_ith_diff_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        5 * self.num_ops + BITS * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for MinMaxGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let x = vars.local_wires[self.wire_ith_x(i)];
+            let y = vars.local_wires[self.wire_ith_y(i)];
+            let min = vars.local_wires[self.wire_ith_min(i)];
+            let max = vars.local_wires[self.wire_ith_max(i)];
+            let lt = vars.local_wires[self.wire_ith_lt(i)];
+
+            constraints.push(lt * (lt - F::Extension::ONE));
+            constraints.push(lt * (x - y) + y - min);
+            constraints.push(min + max - x - y);
+
+            let mut computed_diff = F::Extension::ZERO;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_diff_bit(i, j)];
+                constraints.push(bit * (bit - F::Extension::ONE));
+                computed_diff = computed_diff.double() + bit;
+            }
+            constraints.push(computed_diff - (max - min - lt));
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let x = vars.local_wires[self.wire_ith_x(i)];
+            let y = vars.local_wires[self.wire_ith_y(i)];
+            let min = vars.local_wires[self.wire_ith_min(i)];
+            let max = vars.local_wires[self.wire_ith_max(i)];
+            let lt = vars.local_wires[self.wire_ith_lt(i)];
+
+            constraints.push(builder.mul_sub_extension(lt, lt, lt));
+            let selected = builder.select_ext_generalized(lt, x, y);
+            constraints.push(builder.sub_extension(selected, min));
+            let outputs_sum = builder.add_extension(min, max);
+            let inputs_sum = builder.add_extension(x, y);
+            constraints.push(builder.sub_extension(outputs_sum, inputs_sum));
+
+            let mut computed_diff = builder.zero_extension();
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_diff_bit(i, j)];
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+                computed_diff = builder.mul_const_add_extension(F::TWO, computed_diff, bit);
+            }
+            let max_minus_min = builder.sub_extension(max, min);
+            let diff = builder.sub_extension(max_minus_min, lt);
+            constraints.push(builder.sub_extension(computed_diff, diff));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    MinMaxGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (5 + BITS)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (BITS + 4)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for MinMaxGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let x = vars.local_wires[self.wire_ith_x(i)];
+            let y = vars.local_wires[self.wire_ith_y(i)];
+            let min = vars.local_wires[self.wire_ith_min(i)];
+            let max = vars.local_wires[self.wire_ith_max(i)];
+            let lt = vars.local_wires[self.wire_ith_lt(i)];
+
+            yield_constr.one(lt * (lt - F::ONE));
+            yield_constr.one(lt * (x - y) + y - min);
+            yield_constr.one(min + max - x - y);
+
+            let mut computed_diff = P::ZEROS;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_diff_bit(i, j)];
+                yield_constr.one(bit * (bit - F::ONE));
+                computed_diff = computed_diff * F::TWO + bit;
+            }
+            yield_constr.one(computed_diff - (max - min - lt));
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MinMaxGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: MinMaxGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for MinMaxGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        [self.gate.wire_ith_x(self.i), self.gate.wire_ith_y(self.i)]
+            .iter()
+            .map(|&input| Target::wire(self.gate_index, input))
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let x = witness
+            .get_wire(local_wire(self.gate.wire_ith_x(self.i)))
+            .to_canonical_u64();
+        let y = witness
+            .get_wire(local_wire(self.gate.wire_ith_y(self.i)))
+            .to_canonical_u64();
+        debug_assert!(x >> BITS == 0, "x has more than {} bits", BITS);
+        debug_assert!(y >> BITS == 0, "y has more than {} bits", BITS);
+
+        let lt = x < y;
+        let min = x.min(y);
+        let max = x.max(y);
+        let diff = max - min - lt as u64;
+
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_min(self.i)),
+            F::from_canonical_u64(min),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_max(self.i)),
+            F::from_canonical_u64(max),
+        );
+        out_buffer.set_wire(local_wire(self.gate.wire_ith_lt(self.i)), F::from_bool(lt));
+        for j in 0..BITS {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_diff_bit(self.i, j)),
+                F::from_canonical_u64((diff >> j) & 1),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::{Field, PrimeField64};
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::min_max::MinMaxGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(MinMaxGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(MinMaxGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 32;
+
+        /// Returns the wires for the given inputs and claimed outputs, with the bits of
+        /// `max - min - lt` computed from the claimed outputs.
+        fn get_wires(ops: &[(u32, u32, u32, u32, bool)]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            for &(x, y, min, max, lt) in ops {
+                v0.extend([x, y, min, max].map(F::from_canonical_u32));
+                v0.push(F::from_bool(lt));
+                let diff =
+                    F::from_canonical_u32(max) - F::from_canonical_u32(min) - F::from_bool(lt);
+                let diff = diff.to_canonical_u64();
+                v1.extend((0..BITS).map(|j| F::from_canonical_u64((diff >> j) & 1)));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let x = rng.gen::<u32>();
+        let y = x.wrapping_add(rng.gen_range(1..=u32::MAX));
+        let ops = [
+            (x, y, x.min(y), x.max(y), x < y),
+            (y, x, x.min(y), x.max(y), y < x),
+            (x, x, x, x, false),
+            (0, u32::MAX, 0, u32::MAX, true),
+        ];
+        let gate = MinMaxGate::<F, D, BITS> {
+            num_ops: ops.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&ops),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Swapped outputs, and equal inputs claimed to be strictly ordered.
+        let wrong_ops = [
+            (x, y, x.max(y), x.min(y), x < y),
+            (x, y, x.min(y), x.max(y), x >= y),
+            (x, x, x, x, true),
+        ];
+        for wrong_op in wrong_ops {
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&[wrong_op; 4]),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "Wrong outputs should not satisfy the constraints."
+            );
+        }
+    }
+}
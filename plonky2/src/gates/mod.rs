@@ -7,26 +7,63 @@ pub mod arithmetic_extension;
 pub mod arithmetic_u32;
 pub mod assert_le;
 pub mod base_sum;
+pub mod binary_add;
+pub mod binary_and_const;
+pub mod binary_division;
+pub mod binary_mul_const;
+pub mod binary_neg;
+pub mod binary_select;
+pub mod binary_xor_const;
+pub mod bit_and;
+pub mod bit_compare;
+pub mod bit_mul;
+pub mod bit_reverse;
+pub mod canonical_bits;
+pub mod ch;
 pub mod comparison;
+pub mod cond_swap;
+pub mod const_table;
 pub mod constant;
+pub mod cross_mul;
 pub mod exponentiation;
+pub mod ext_equal;
+pub mod full_adder;
 pub mod gate;
 pub mod gate_tree;
 pub mod interpolation;
+pub mod inverse;
 pub mod low_degree_interpolation;
+pub mod maj;
+pub mod mersenne_reduce;
+pub mod min_max;
+pub mod mod_neg;
+pub mod msb;
+pub mod mul_parity;
+pub mod multi_word_sub;
 pub mod multiplication_extension;
 pub mod noop;
 mod packed_util;
+pub mod parity;
 pub mod poseidon;
 pub(crate) mod poseidon_mds;
+pub mod product_accumulate;
 pub(crate) mod public_input;
 pub mod random_access;
 pub mod range_check_u32;
 pub mod reducing;
 pub mod reducing_extension;
+pub mod shl_mod;
+pub mod signed_abs;
+pub mod small_mod;
+pub mod sorted;
+pub mod square_mod;
+pub mod subgroup_index;
 pub mod subtraction_u32;
 pub mod switch;
+pub mod symmetric_mul;
+pub mod triple_add;
 pub mod util;
+pub mod xor_accumulate;
 
 // Can't use #[cfg(test)] here because it needs to be visible to other crates.
 // See https://github.com/rust-lang/cargo/issues/8379
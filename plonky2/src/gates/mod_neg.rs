@@ -0,0 +1,485 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+use plonky2_util::ceil_div_usize;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute `(M - x) mod M` for a constant modulus `M` and an input `x < M` (we assume it
+/// is range-checked beforehand). We enforce `x + output = M * wrapped` for a boolean `wrapped`, and
+/// range-check both `output` and `M - 1 - output`, so that `output < M`.
+#[derive(Copy, Clone, Debug)]
+pub struct ModNegGate<F: RichField + Extendable<D>, const D: usize, const M: u64> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const M: u64> ModNegGate<F, D, M> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(
+            M > 0 && M < 1 << 62,
+            "Modulus must be positive and below 2^62"
+        );
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 3 + 2 * Self::num_limbs();
+        let routed_wires_per_op = 2;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i + 1
+    }
+
+    pub fn wire_ith_wrapped(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * self.num_ops + (1 + 2 * Self::num_limbs()) * i
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // We have limbs for the bits of `M - 1`, which bound both `output` and `M - 1 - output`.
+    pub fn num_limbs() -> usize {
+        let bits = 64 - (M - 1).leading_zeros() as usize;
+        ceil_div_usize(bits, Self::limb_bits())
+    }
+
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(j < Self::num_limbs());
+        self.wire_ith_wrapped(i) + 1 + j
+    }
+    pub fn wire_ith_output_slack_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(j < Self::num_limbs());
+        self.wire_ith_wrapped(i) + 1 + Self::num_limbs() + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const M: u64> Gate<F, D>
+    for ModNegGate<F, D, M>
+{
+    fn id(&self) -> String {
+        format!("{:?}<M={}>", self, M)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let wrapped = vars.local_wires[self.wire_ith_wrapped(i)];
+            let modulus = F::Extension::from_canonical_u64(M);
+
+            constraints.push(wrapped * (wrapped - F::Extension::ONE));
+            constraints.push(input + output - modulus * wrapped);
+
+            let output_slack = modulus - F::Extension::ONE - output;
+            let mut combined_limbs = F::Extension::ZERO;
+            let mut combined_slack_limbs = F::Extension::ZERO;
+            let limb_base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let this_slack_limb = vars.local_wires[self.wire_ith_output_slack_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                for limb in [this_limb, this_slack_limb] {
+                    let product = (0..max_limb)
+                        .map(|x| limb - F::Extension::from_canonical_usize(x))
+                        .product();
+                    constraints.push(product);
+                }
+
+                combined_limbs = limb_base * combined_limbs + this_limb;
+                combined_slack_limbs = limb_base * combined_slack_limbs + this_slack_limb;
+            }
+            constraints.push(combined_limbs - output);
+            constraints.push(combined_slack_limbs - output_slack);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let wrapped = vars.local_wires[self.wire_ith_wrapped(i)];
+            let modulus = F::from_canonical_u64(M);
+
+            constraints.push(builder.mul_sub_extension(wrapped, wrapped, wrapped));
+            let sum = builder.add_extension(input, output);
+            let wrapped_modulus = builder.mul_const_extension(modulus, wrapped);
+            constraints.push(builder.sub_extension(sum, wrapped_modulus));
+
+            let modulus_minus_one =
+                builder.constant_extension(F::Extension::from_canonical_u64(M - 1));
+            let output_slack = builder.sub_extension(modulus_minus_one, output);
+            let mut combined_limbs = builder.zero_extension();
+            let mut combined_slack_limbs = builder.zero_extension();
+            let limb_base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let this_slack_limb = vars.local_wires[self.wire_ith_output_slack_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                for limb in [this_limb, this_slack_limb] {
+                    let mut product = builder.one_extension();
+                    for x in 0..max_limb {
+                        let x_target =
+                            builder.constant_extension(F::Extension::from_canonical_usize(x));
+                        let diff = builder.sub_extension(limb, x_target);
+                        product = builder.mul_extension(product, diff);
+                    }
+                    constraints.push(product);
+                }
+
+                combined_limbs = builder.mul_add_extension(limb_base, combined_limbs, this_limb);
+                combined_slack_limbs =
+                    builder.mul_add_extension(limb_base, combined_slack_limbs, this_slack_limb);
+            }
+            constraints.push(builder.sub_extension(combined_limbs, output));
+            constraints.push(builder.sub_extension(combined_slack_limbs, output_slack));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    ModNegGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                        _phantom: PhantomData,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    // Zero wires don't satisfy the constraints, as the slack `M - 1 - output` must be range-checked.
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(vec![(self.wire_ith_input(op), F::ZERO)])
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (3 + 2 * Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (4 + 2 * Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const M: u64> PackedEvaluableBase<F, D>
+    for ModNegGate<F, D, M>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let wrapped = vars.local_wires[self.wire_ith_wrapped(i)];
+            let modulus = F::from_canonical_u64(M);
+
+            yield_constr.one(wrapped * (wrapped - F::ONE));
+            yield_constr.one(input + output - wrapped * modulus);
+
+            let output_slack = -output + F::from_canonical_u64(M - 1);
+            let mut combined_limbs = P::ZEROS;
+            let mut combined_slack_limbs = P::ZEROS;
+            let limb_base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let this_slack_limb = vars.local_wires[self.wire_ith_output_slack_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                for limb in [this_limb, this_slack_limb] {
+                    let product = (0..max_limb)
+                        .map(|x| limb - F::from_canonical_usize(x))
+                        .product();
+                    yield_constr.one(product);
+                }
+
+                combined_limbs = combined_limbs * limb_base + this_limb;
+                combined_slack_limbs = combined_slack_limbs * limb_base + this_slack_limb;
+            }
+            yield_constr.one(combined_limbs - output);
+            yield_constr.one(combined_slack_limbs - output_slack);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ModNegGenerator<F: RichField + Extendable<D>, const D: usize, const M: u64> {
+    gate: ModNegGate<F, D, M>,
+    gate_index: usize,
+    i: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const M: u64> SimpleGenerator<F>
+    for ModNegGenerator<F, D, M>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(input < M, "Input must be reduced modulo M");
+
+        let (output, wrapped) = if input == 0 { (0, 0) } else { (M - input, 1) };
+
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output(self.i)),
+            F::from_canonical_u64(output),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_wrapped(self.i)),
+            F::from_canonical_u64(wrapped),
+        );
+
+        let num_limbs = ModNegGate::<F, D, M>::num_limbs();
+        let limb_bits = ModNegGate::<F, D, M>::limb_bits();
+        let limb_mask = (1 << limb_bits) - 1;
+        let output_slack = M - 1 - output;
+        for j in 0..num_limbs {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_output_jth_limb(self.i, j)),
+                F::from_canonical_u64((output >> (limb_bits * j)) & limb_mask),
+            );
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_output_slack_jth_limb(self.i, j)),
+                F::from_canonical_u64((output_slack >> (limb_bits * j)) & limb_mask),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::mod_neg::ModNegGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    const M: u64 = 1_000_003;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(ModNegGate::<GoldilocksField, 4, M> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(ModNegGate::<GoldilocksField, D, M> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        fn get_wires(inputs: &[u64], outputs: &[u64]) -> Vec<FF> {
+            let num_limbs = ModNegGate::<F, D, M>::num_limbs();
+            let limb_bits = ModNegGate::<F, D, M>::limb_bits();
+            let limb_mask = (1 << limb_bits) - 1;
+
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            for (&input, &output) in inputs.iter().zip(outputs) {
+                v0.push(F::from_canonical_u64(input));
+                v0.push(F::from_canonical_u64(output));
+
+                let wrapped = (input + output) / M;
+                v1.push(F::from_canonical_u64(wrapped));
+                for value in [output, M - 1 - output] {
+                    for j in 0..num_limbs {
+                        v1.push(F::from_canonical_u64(
+                            (value >> (limb_bits * j)) & limb_mask,
+                        ));
+                    }
+                }
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let inputs = [0, 1, M - 1, rng.gen_range(0..M)];
+        let outputs: Vec<_> = inputs.iter().map(|&x| (M - x) % M).collect();
+        assert_eq!(outputs[0], 0);
+
+        let gate = ModNegGate::<F, D, M> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+    }
+
+    #[test]
+    fn test_gate_constraint_rejects_unreduced_output() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let gate = ModNegGate::<F, D, M> {
+            num_ops: 1,
+            _phantom: PhantomData,
+        };
+
+        // For `x == 0`, the output `M` satisfies `x + out ≡ 0 (mod M)` but is not reduced. Its
+        // slack `M - 1 - M` is not a small value, so no limb decomposition can satisfy the gate.
+        let num_limbs = ModNegGate::<F, D, M>::num_limbs();
+        let limb_bits = ModNegGate::<F, D, M>::limb_bits();
+        let limb_mask = (1 << limb_bits) - 1;
+        let mut wires = vec![F::ZERO, F::from_canonical_u64(M), F::ONE];
+        for j in 0..num_limbs {
+            wires.push(F::from_canonical_u64((M >> (limb_bits * j)) & limb_mask));
+        }
+        wires.extend(vec![F::ZERO; num_limbs]);
+        let wires: Vec<_> = wires.into_iter().map(|x| x.into()).collect();
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &wires,
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Unreduced output should not satisfy the constraints."
+        );
+    }
+
+    #[test]
+    fn test_mod_neg_partial_row() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // A single operation leaves the rest of the row unused.
+        let gate = ModNegGate::<F, D, M>::new_from_config(&builder.config);
+        assert!(gate.num_ops > 1);
+        let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+        let x = builder.add_virtual_target();
+        builder.connect(x, Target::wire(gate_index, gate.wire_ith_input(i)));
+        pw.set_target(x, F::from_canonical_u64(5));
+
+        let output = Target::wire(gate_index, gate.wire_ith_output(i));
+        let expected = builder.constant(F::from_canonical_u64(M - 5));
+        builder.connect(output, expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
@@ -0,0 +1,329 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate which range-checks its input to `BITS` bits and outputs the input's most significant
+/// bit. The lower `BITS - 1` bits are kept in unrouted wires; only the most significant bit is
+/// routed.
+#[derive(Copy, Clone, Debug)]
+pub struct MsbGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> MsbGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(
+            (1..64).contains(&BITS),
+            "Values of 64 or more bits would overflow the field"
+        );
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 2 + Self::num_low_bits();
+        let routed_wires_per_op = 2;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i
+    }
+    pub fn wire_ith_output_msb(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i + 1
+    }
+
+    // All bits except the most significant one, which is the output.
+    pub fn num_low_bits() -> usize {
+        BITS - 1
+    }
+
+    pub fn wire_ith_jth_low_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_low_bits());
+        2 * self.num_ops + Self::num_low_bits() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for MsbGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let msb = vars.local_wires[self.wire_ith_output_msb(i)];
+
+            let mut combined_bits = msb;
+            constraints.push(msb * (msb - F::Extension::ONE));
+            for j in (0..Self::num_low_bits()).rev() {
+                let bit = vars.local_wires[self.wire_ith_jth_low_bit(i, j)];
+                constraints.push(bit * (bit - F::Extension::ONE));
+                combined_bits = combined_bits.double() + bit;
+            }
+            constraints.push(combined_bits - input);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let two = builder.two_extension();
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let msb = vars.local_wires[self.wire_ith_output_msb(i)];
+
+            let mut combined_bits = msb;
+            constraints.push(builder.mul_sub_extension(msb, msb, msb));
+            for j in (0..Self::num_low_bits()).rev() {
+                let bit = vars.local_wires[self.wire_ith_jth_low_bit(i, j)];
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+                combined_bits = builder.mul_add_extension(two, combined_bits, bit);
+            }
+            constraints.push(builder.sub_extension(combined_bits, input));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    MsbGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                        _phantom: PhantomData,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (2 + Self::num_low_bits())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (BITS + 1)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for MsbGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let msb = vars.local_wires[self.wire_ith_output_msb(i)];
+
+            let mut combined_bits = msb;
+            yield_constr.one(msb * (msb - F::ONE));
+            for j in (0..Self::num_low_bits()).rev() {
+                let bit = vars.local_wires[self.wire_ith_jth_low_bit(i, j)];
+                yield_constr.one(bit * (bit - F::ONE));
+                combined_bits = combined_bits * F::TWO + bit;
+            }
+            yield_constr.one(combined_bits - input);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MsbGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: MsbGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for MsbGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(input >> BITS == 0, "Input does not fit in {} bits", BITS);
+
+        let msb = (input >> (BITS - 1)) & 1;
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_msb(self.i)),
+            F::from_canonical_u64(msb),
+        );
+
+        for j in 0..MsbGate::<F, D, BITS>::num_low_bits() {
+            let bit = (input >> j) & 1;
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_jth_low_bit(self.i, j)),
+                F::from_canonical_u64(bit),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::msb::MsbGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(MsbGate::<GoldilocksField, 4, 16> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(MsbGate::<GoldilocksField, D, 16> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 16;
+
+        fn get_wires(inputs: &[u64], msbs: &[u64]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+
+            for (&input, &msb) in inputs.iter().zip(msbs) {
+                v0.push(F::from_canonical_u64(input));
+                v0.push(F::from_canonical_u64(msb));
+                for j in 0..BITS - 1 {
+                    v1.push(F::from_canonical_u64((input >> j) & 1));
+                }
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let boundary = 1 << (BITS - 1);
+        let inputs = [0, boundary - 1, boundary, boundary + 1, (1 << BITS) - 1];
+        let msbs: Vec<_> = inputs.iter().map(|x| (x >> (BITS - 1)) & 1).collect();
+        assert_eq!(msbs, vec![0, 0, 1, 1, 1]);
+
+        let gate = MsbGate::<F, D, BITS> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &msbs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Flipping the most significant bit must violate the constraints.
+        let wrong_msbs: Vec<_> = msbs.iter().map(|b| 1 - b).collect();
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &wrong_msbs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Incorrect msb should not satisfy the constraints."
+        );
+    }
+}
@@ -0,0 +1,619 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to perform a mul-add `x * y + z` on `BITS`-bit values (we assume they are range-checked
+/// beforehand), like `U32ArithmeticGate`, which also outputs the low bit of the result as a
+/// boolean. With a zero addend, this is the parity of the product `x * y`, so circuits can branch
+/// on it without decomposing the output again.
+///
+/// As with `U32ArithmeticGate`, the output limbs alone allow a second decomposition of the result
+/// that differs from it by the field order when `BITS = 32`, which would let the parity be forged.
+/// Since `x * y + z <= (2^BITS - 1) * 2^BITS`, the high half can only be `2^BITS - 1` if the low
+/// half is zero. We enforce this by witnessing the inverse of `high_half - (2^BITS - 1)`, which
+/// must exist unless the low half is zero.
+#[derive(Copy, Clone, Debug)]
+pub struct MulParityGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> MulParityGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(
+            BITS <= 32,
+            "Products of values of more than 32 bits would overflow the field"
+        );
+        assert_eq!(BITS % Self::limb_bits(), 0);
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 7 + Self::num_limbs();
+        let routed_wires_per_op = 6;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_multiplicand_0(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i
+    }
+    pub fn wire_ith_multiplicand_1(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i + 1
+    }
+    pub fn wire_ith_addend(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i + 2
+    }
+
+    pub fn wire_ith_output_low_half(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i + 3
+    }
+    pub fn wire_ith_output_high_half(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i + 4
+    }
+    pub fn wire_ith_parity(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * i + 5
+    }
+
+    pub fn wire_ith_high_half_diff_inverse(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        6 * self.num_ops + i
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // We have limbs for the `2 * BITS` bits of the output.
+    pub fn num_limbs() -> usize {
+        2 * BITS / Self::limb_bits()
+    }
+
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        7 * self.num_ops + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for MulParityGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let multiplicand_0 = vars.local_wires[self.wire_ith_multiplicand_0(i)];
+            let multiplicand_1 = vars.local_wires[self.wire_ith_multiplicand_1(i)];
+            let addend = vars.local_wires[self.wire_ith_addend(i)];
+
+            let computed_output = multiplicand_0 * multiplicand_1 + addend;
+
+            let output_low = vars.local_wires[self.wire_ith_output_low_half(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high_half(i)];
+
+            let base = F::Extension::from_canonical_u64(1 << BITS);
+            let combined_output = output_high * base + output_low;
+
+            constraints.push(combined_output - computed_output);
+
+            // Either the high half is not `2^BITS - 1`, so that the difference has an inverse, or
+            // the low half is zero.
+            let high_half_diff = output_high - F::Extension::from_canonical_u64((1 << BITS) - 1);
+            let inverse = vars.local_wires[self.wire_ith_high_half_diff_inverse(i)];
+            constraints.push((high_half_diff * inverse - F::Extension::ONE) * output_low);
+
+            let mut combined_low_limbs = F::Extension::ZERO;
+            let mut combined_high_limbs = F::Extension::ZERO;
+            let midpoint = Self::num_limbs() / 2;
+            let base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                    .product();
+                constraints.push(product);
+
+                if j < midpoint {
+                    combined_low_limbs = base * combined_low_limbs + this_limb;
+                } else {
+                    combined_high_limbs = base * combined_high_limbs + this_limb;
+                }
+            }
+            constraints.push(combined_low_limbs - output_low);
+            constraints.push(combined_high_limbs - output_high);
+
+            // The parity is boolean, and the lowest limb minus the parity is either 0 or 2, so the
+            // parity is the low bit of that limb.
+            let parity = vars.local_wires[self.wire_ith_parity(i)];
+            constraints.push(parity * (parity - F::Extension::ONE));
+            let high_bit_of_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, 0)] - parity;
+            constraints.push(high_bit_of_limb * (high_bit_of_limb - F::Extension::TWO));
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        for i in 0..self.num_ops {
+            let multiplicand_0 = vars.local_wires[self.wire_ith_multiplicand_0(i)];
+            let multiplicand_1 = vars.local_wires[self.wire_ith_multiplicand_1(i)];
+            let addend = vars.local_wires[self.wire_ith_addend(i)];
+
+            let computed_output = builder.mul_add_extension(multiplicand_0, multiplicand_1, addend);
+
+            let output_low = vars.local_wires[self.wire_ith_output_low_half(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high_half(i)];
+
+            let base: F::Extension = F::from_canonical_u64(1 << BITS).into();
+            let base_target = builder.constant_extension(base);
+            let combined_output = builder.mul_add_extension(output_high, base_target, output_low);
+
+            constraints.push(builder.sub_extension(combined_output, computed_output));
+
+            let max_half =
+                builder.constant_extension(F::Extension::from_canonical_u64((1 << BITS) - 1));
+            let high_half_diff = builder.sub_extension(output_high, max_half);
+            let inverse = vars.local_wires[self.wire_ith_high_half_diff_inverse(i)];
+            let one = builder.one_extension();
+            let not_max = builder.mul_sub_extension(high_half_diff, inverse, one);
+            constraints.push(builder.mul_extension(not_max, output_low));
+
+            let mut combined_low_limbs = builder.zero_extension();
+            let mut combined_high_limbs = builder.zero_extension();
+            let midpoint = Self::num_limbs() / 2;
+            let base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+
+                let mut product = builder.one_extension();
+                for x in 0..max_limb {
+                    let x_target =
+                        builder.constant_extension(F::Extension::from_canonical_usize(x));
+                    let diff = builder.sub_extension(this_limb, x_target);
+                    product = builder.mul_extension(product, diff);
+                }
+                constraints.push(product);
+
+                if j < midpoint {
+                    combined_low_limbs =
+                        builder.mul_add_extension(base, combined_low_limbs, this_limb);
+                } else {
+                    combined_high_limbs =
+                        builder.mul_add_extension(base, combined_high_limbs, this_limb);
+                }
+            }
+
+            constraints.push(builder.sub_extension(combined_low_limbs, output_low));
+            constraints.push(builder.sub_extension(combined_high_limbs, output_high));
+
+            let parity = vars.local_wires[self.wire_ith_parity(i)];
+            constraints.push(builder.mul_sub_extension(parity, parity, parity));
+            let lowest_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, 0)];
+            let high_bit_of_limb = builder.sub_extension(lowest_limb, parity);
+            constraints.push(builder.arithmetic_extension(
+                F::ONE,
+                -F::TWO,
+                high_bit_of_limb,
+                high_bit_of_limb,
+                high_bit_of_limb,
+            ));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    MulParityGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                        _phantom: PhantomData,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (7 + Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (6 + Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for MulParityGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let multiplicand_0 = vars.local_wires[self.wire_ith_multiplicand_0(i)];
+            let multiplicand_1 = vars.local_wires[self.wire_ith_multiplicand_1(i)];
+            let addend = vars.local_wires[self.wire_ith_addend(i)];
+
+            let computed_output = multiplicand_0 * multiplicand_1 + addend;
+
+            let output_low = vars.local_wires[self.wire_ith_output_low_half(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high_half(i)];
+
+            let base = F::from_canonical_u64(1 << BITS);
+            let combined_output = output_high * base + output_low;
+
+            yield_constr.one(combined_output - computed_output);
+
+            let high_half_diff = output_high - F::from_canonical_u64((1 << BITS) - 1);
+            let inverse = vars.local_wires[self.wire_ith_high_half_diff_inverse(i)];
+            yield_constr.one((high_half_diff * inverse - P::ONES) * output_low);
+
+            let mut combined_low_limbs = P::ZEROS;
+            let mut combined_high_limbs = P::ZEROS;
+            let midpoint = Self::num_limbs() / 2;
+            let base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::from_canonical_usize(x))
+                    .product();
+                yield_constr.one(product);
+
+                if j < midpoint {
+                    combined_low_limbs = combined_low_limbs * base + this_limb;
+                } else {
+                    combined_high_limbs = combined_high_limbs * base + this_limb;
+                }
+            }
+            yield_constr.one(combined_low_limbs - output_low);
+            yield_constr.one(combined_high_limbs - output_high);
+
+            let parity = vars.local_wires[self.wire_ith_parity(i)];
+            yield_constr.one(parity * (parity - F::ONE));
+            let high_bit_of_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, 0)] - parity;
+            yield_constr.one(high_bit_of_limb * (high_bit_of_limb - F::TWO));
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MulParityGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: MulParityGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for MulParityGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        vec![
+            local_target(self.gate.wire_ith_multiplicand_0(self.i)),
+            local_target(self.gate.wire_ith_multiplicand_1(self.i)),
+            local_target(self.gate.wire_ith_addend(self.i)),
+        ]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input));
+
+        let multiplicand_0 = get_local_wire(self.gate.wire_ith_multiplicand_0(self.i));
+        let multiplicand_1 = get_local_wire(self.gate.wire_ith_multiplicand_1(self.i));
+        let addend = get_local_wire(self.gate.wire_ith_addend(self.i));
+
+        let output = (multiplicand_0 * multiplicand_1 + addend).to_canonical_u64();
+
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_high_half(self.i)),
+            F::from_canonical_u64(output >> BITS),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_low_half(self.i)),
+            F::from_canonical_u64(output & ((1 << BITS) - 1)),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_parity(self.i)),
+            F::from_canonical_u64(output & 1),
+        );
+
+        let high_half_diff =
+            F::from_canonical_u64(output >> BITS) - F::from_canonical_u64((1 << BITS) - 1);
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_high_half_diff_inverse(self.i)),
+            high_half_diff.try_inverse().unwrap_or(F::ZERO),
+        );
+
+        let num_limbs = MulParityGate::<F, D, BITS>::num_limbs();
+        let limb_base = 1 << MulParityGate::<F, D, BITS>::limb_bits();
+        let output_limbs = (0..num_limbs).scan(output, |acc, _| {
+            let tmp = *acc % limb_base;
+            *acc /= limb_base;
+            Some(F::from_canonical_u64(tmp))
+        });
+
+        for (j, limb) in output_limbs.enumerate() {
+            let wire = local_wire(self.gate.wire_ith_output_jth_limb(self.i, j));
+            out_buffer.set_wire(wire, limb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::{Field, Field64};
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::mul_parity::MulParityGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(MulParityGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(MulParityGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FF = <C as GenericConfig<D>>::FE;
+    const BITS: usize = 32;
+
+    /// Builds the wires of a gate computing `x * y + z` for each of `ops`, with the given parities.
+    fn get_wires(ops: &[(u64, u64, u64)], parities: &[u64]) -> Vec<FF> {
+        let outputs: Vec<u64> = ops.iter().map(|&(x, y, z)| x * y + z).collect();
+        get_wires_with_outputs(ops, &outputs, parities)
+    }
+
+    /// Builds the wires of a gate with the given outputs in place of `x * y + z`. An output may
+    /// exceed the field order, as long as it fits in `2 * BITS` bits.
+    fn get_wires_with_outputs(
+        ops: &[(u64, u64, u64)],
+        outputs: &[u64],
+        parities: &[u64],
+    ) -> Vec<FF> {
+        let mut v0 = Vec::new();
+        let mut v1 = Vec::new();
+        let mut v2 = Vec::new();
+
+        let num_limbs = MulParityGate::<F, D, BITS>::num_limbs();
+        let limb_base = 1 << MulParityGate::<F, D, BITS>::limb_bits();
+        for ((&(x, y, z), &output), &parity) in ops.iter().zip(outputs).zip(parities) {
+            let high_half = output >> BITS;
+            v0.extend(
+                [x, y, z, output & ((1 << BITS) - 1), high_half, parity].map(F::from_canonical_u64),
+            );
+            let high_half_diff =
+                F::from_canonical_u64(high_half) - F::from_canonical_u64((1 << BITS) - 1);
+            v1.push(high_half_diff.try_inverse().unwrap_or(F::ZERO));
+
+            let mut value = output;
+            for _ in 0..num_limbs {
+                v2.push(F::from_canonical_u64(value % limb_base));
+                value /= limb_base;
+            }
+        }
+
+        v0.iter()
+            .chain(v1.iter())
+            .chain(v2.iter())
+            .map(|&x| x.into())
+            .collect()
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        let mut rng = rand::thread_rng();
+        let max = u32::MAX as u64;
+        let mut ops = vec![
+            (0, 0, 0),
+            (1, 1, 0),
+            (max, max, 0),
+            (max, max, max),
+            (2, 3, 1),
+        ];
+        ops.extend((0..4).map(|_| (rng.gen::<u32>() as u64, rng.gen::<u32>() as u64, 0)));
+        let parities: Vec<u64> = ops.iter().map(|&(x, y, z)| (x * y + z) & 1).collect();
+        for (&(x, y, z), &parity) in ops.iter().zip(&parities) {
+            if z == 0 {
+                assert_eq!(parity, (x * y) & 1);
+            }
+        }
+
+        let gate = MulParityGate::<F, D, BITS> {
+            num_ops: ops.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&ops, &parities),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // A flipped parity, or a non-boolean one, should be rejected.
+        let gate = MulParityGate::<F, D, BITS> {
+            num_ops: 1,
+            _phantom: PhantomData,
+        };
+        for (&op, &parity) in ops.iter().zip(&parities) {
+            for wrong_parity in [parity ^ 1, parity + 2] {
+                let vars = EvaluationVars {
+                    local_constants: &[],
+                    local_wires: &get_wires(&[op], &[wrong_parity]),
+                    public_inputs_hash: &HashOut::rand(),
+                };
+                assert!(
+                    !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                    "A wrong parity should not satisfy the constraints."
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_gate_constraint_non_canonical_output() {
+        let gate = MulParityGate::<F, D, BITS> {
+            num_ops: 1,
+            _phantom: PhantomData,
+        };
+
+        // With `x = y = z = 0`, the output `(2^32 - 1) * 2^32 + 1` is the field order, so it only
+        // differs from the true result by the field order, and has odd parity.
+        let forged_output = ((1 << BITS) - 1) * (1 << BITS) + 1;
+        assert_eq!(forged_output, F::ORDER);
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires_with_outputs(&[(0, 0, 0)], &[forged_output], &[1]),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        let num_unsatisfied = gate
+            .eval_unfiltered(vars)
+            .iter()
+            .filter(|x| !x.is_zero())
+            .count();
+        assert_eq!(
+            num_unsatisfied, 1,
+            "Only the canonicity constraint should reject the forged output."
+        );
+    }
+
+    #[test]
+    fn test_mul_parity() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let mut rng = rand::thread_rng();
+        let gate = MulParityGate::<F, D, BITS>::new_from_config(&builder.config);
+        for _ in 0..4 {
+            let x_value = rng.gen::<u32>() as u64;
+            let y_value = rng.gen::<u32>() as u64;
+
+            let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+            let x = builder.add_virtual_target();
+            let y = builder.add_virtual_target();
+            builder.connect(x, Target::wire(gate_index, gate.wire_ith_multiplicand_0(i)));
+            builder.connect(y, Target::wire(gate_index, gate.wire_ith_multiplicand_1(i)));
+            let zero = builder.zero();
+            builder.connect(zero, Target::wire(gate_index, gate.wire_ith_addend(i)));
+            pw.set_target(x, F::from_canonical_u64(x_value));
+            pw.set_target(y, F::from_canonical_u64(y_value));
+
+            let parity = Target::wire(gate_index, gate.wire_ith_parity(i));
+            let expected = builder.constant(F::from_canonical_u64((x_value * y_value) & 1));
+            builder.connect(parity, expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
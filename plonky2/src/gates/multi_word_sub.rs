@@ -0,0 +1,578 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to subtract two `WORDS`-word integers of `BITS`-bit words in a single row: given
+/// little-endian words `x` and `y` and an input `borrow`, it returns the words of `x - y - borrow`
+/// and, if this underflows, an output `borrow`. The borrows between words are internal to the gate,
+/// so a wide subtraction doesn't take a row per word as with `U32SubtractionGate`. Inputs are not
+/// range-checked.
+#[derive(Copy, Clone, Debug)]
+pub struct MultiWordSubGate<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const WORDS: usize,
+> {
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const WORDS: usize>
+    MultiWordSubGate<F, D, BITS, WORDS>
+{
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(
+            BITS < 63,
+            "Differences of more than 63 bits would overflow the field"
+        );
+        assert_eq!(BITS % Self::limb_bits(), 0);
+        assert!(WORDS > 0, "There must be at least one word");
+        let gate = Self {
+            _phantom: PhantomData,
+        };
+        assert!(
+            Self::num_routed_wires() <= config.num_routed_wires,
+            "{} words need more than {} routed wires",
+            WORDS,
+            config.num_routed_wires
+        );
+        assert!(
+            gate.num_wires() <= config.num_wires,
+            "{} words need more than {} wires",
+            WORDS,
+            config.num_wires
+        );
+        gate
+    }
+
+    fn num_routed_wires() -> usize {
+        3 * WORDS + 2
+    }
+
+    pub fn wire_input_x(&self, i: usize) -> usize {
+        debug_assert!(i < WORDS);
+        i
+    }
+    pub fn wire_input_y(&self, i: usize) -> usize {
+        debug_assert!(i < WORDS);
+        WORDS + i
+    }
+    pub fn wire_input_borrow(&self) -> usize {
+        2 * WORDS
+    }
+
+    pub fn wire_output_result(&self, i: usize) -> usize {
+        debug_assert!(i < WORDS);
+        2 * WORDS + 1 + i
+    }
+    pub fn wire_output_borrow(&self) -> usize {
+        3 * WORDS + 1
+    }
+
+    /// The borrow from word `i + 1` into word `i`, for all but the last word.
+    pub fn wire_internal_borrow(&self, i: usize) -> usize {
+        debug_assert!(i < WORDS - 1);
+        Self::num_routed_wires() + i
+    }
+
+    /// The borrow subtracted from word `i`.
+    fn wire_borrow_in(&self, i: usize) -> usize {
+        if i == 0 {
+            self.wire_input_borrow()
+        } else {
+            self.wire_internal_borrow(i - 1)
+        }
+    }
+
+    /// The borrow taken by word `i` if its difference underflows.
+    fn wire_borrow_out(&self, i: usize) -> usize {
+        if i == WORDS - 1 {
+            self.wire_output_borrow()
+        } else {
+            self.wire_internal_borrow(i)
+        }
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // We have limbs for the `BITS` bits of each word of `output_result`.
+    pub fn num_limbs() -> usize {
+        BITS / Self::limb_bits()
+    }
+
+    pub fn wire_output_ith_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < WORDS);
+        debug_assert!(j < Self::num_limbs());
+        4 * WORDS + 1 + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const WORDS: usize> Gate<F, D>
+    for MultiWordSubGate<F, D, BITS, WORDS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}, WORDS={}>", self, BITS, WORDS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let base = F::Extension::from_canonical_u64(1 << BITS);
+        for i in 0..WORDS {
+            let input_x = vars.local_wires[self.wire_input_x(i)];
+            let input_y = vars.local_wires[self.wire_input_y(i)];
+            let borrow_in = vars.local_wires[self.wire_borrow_in(i)];
+
+            let result_initial = input_x - input_y - borrow_in;
+
+            let output_result = vars.local_wires[self.wire_output_result(i)];
+            let borrow_out = vars.local_wires[self.wire_borrow_out(i)];
+
+            constraints.push(output_result - (result_initial + base * borrow_out));
+
+            // Range-check output_result to be at most BITS bits.
+            let mut combined_limbs = F::Extension::ZERO;
+            let limb_base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_output_ith_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                    .product();
+                constraints.push(product);
+
+                combined_limbs = limb_base * combined_limbs + this_limb;
+            }
+            constraints.push(combined_limbs - output_result);
+
+            // Range-check borrow_out to be one bit.
+            constraints.push(borrow_out * (F::Extension::ONE - borrow_out));
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let base = builder.constant_extension(F::Extension::from_canonical_u64(1 << BITS));
+        for i in 0..WORDS {
+            let input_x = vars.local_wires[self.wire_input_x(i)];
+            let input_y = vars.local_wires[self.wire_input_y(i)];
+            let borrow_in = vars.local_wires[self.wire_borrow_in(i)];
+
+            let diff = builder.sub_extension(input_x, input_y);
+            let result_initial = builder.sub_extension(diff, borrow_in);
+
+            let output_result = vars.local_wires[self.wire_output_result(i)];
+            let borrow_out = vars.local_wires[self.wire_borrow_out(i)];
+
+            let computed_output = builder.mul_add_extension(base, borrow_out, result_initial);
+            constraints.push(builder.sub_extension(output_result, computed_output));
+
+            // Range-check output_result to be at most BITS bits.
+            let mut combined_limbs = builder.zero_extension();
+            let limb_base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_output_ith_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let mut product = builder.one_extension();
+                for x in 0..max_limb {
+                    let x_target =
+                        builder.constant_extension(F::Extension::from_canonical_usize(x));
+                    let diff = builder.sub_extension(this_limb, x_target);
+                    product = builder.mul_extension(product, diff);
+                }
+                constraints.push(product);
+
+                combined_limbs = builder.mul_add_extension(limb_base, combined_limbs, this_limb);
+            }
+            constraints.push(builder.sub_extension(combined_limbs, output_result));
+
+            // Range-check borrow_out to be one bit.
+            let one = builder.one_extension();
+            let not_borrow = builder.sub_extension(one, borrow_out);
+            constraints.push(builder.mul_extension(borrow_out, not_borrow));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        let gen = MultiWordSubGenerator {
+            gate: *self,
+            gate_index,
+        };
+        vec![Box::new(gen.adapter())]
+    }
+
+    fn num_wires(&self) -> usize {
+        4 * WORDS + 1 + WORDS * Self::num_limbs()
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        WORDS * (3 + Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const WORDS: usize>
+    PackedEvaluableBase<F, D> for MultiWordSubGate<F, D, BITS, WORDS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        let base = F::from_canonical_u64(1 << BITS);
+        for i in 0..WORDS {
+            let input_x = vars.local_wires[self.wire_input_x(i)];
+            let input_y = vars.local_wires[self.wire_input_y(i)];
+            let borrow_in = vars.local_wires[self.wire_borrow_in(i)];
+
+            let result_initial = input_x - input_y - borrow_in;
+
+            let output_result = vars.local_wires[self.wire_output_result(i)];
+            let borrow_out = vars.local_wires[self.wire_borrow_out(i)];
+
+            yield_constr.one(output_result - (result_initial + borrow_out * base));
+
+            // Range-check output_result to be at most BITS bits.
+            let mut combined_limbs = P::ZEROS;
+            let limb_base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_output_ith_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::from_canonical_usize(x))
+                    .product();
+                yield_constr.one(product);
+
+                combined_limbs = combined_limbs * limb_base + this_limb;
+            }
+            yield_constr.one(combined_limbs - output_result);
+
+            // Range-check borrow_out to be one bit.
+            yield_constr.one(borrow_out * (P::ONES - borrow_out));
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MultiWordSubGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const WORDS: usize,
+> {
+    gate: MultiWordSubGate<F, D, BITS, WORDS>,
+    gate_index: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const WORDS: usize>
+    SimpleGenerator<F> for MultiWordSubGenerator<F, D, BITS, WORDS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        (0..WORDS)
+            .flat_map(|i| [self.gate.wire_input_x(i), self.gate.wire_input_y(i)])
+            .chain([self.gate.wire_input_borrow()])
+            .map(local_target)
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input)).to_canonical_u64();
+
+        let num_limbs = MultiWordSubGate::<F, D, BITS, WORDS>::num_limbs();
+        let limb_base = 1 << MultiWordSubGate::<F, D, BITS, WORDS>::limb_bits();
+        let mut borrow = get_local_wire(self.gate.wire_input_borrow());
+        for i in 0..WORDS {
+            let input_x = get_local_wire(self.gate.wire_input_x(i));
+            let input_y = get_local_wire(self.gate.wire_input_y(i));
+            debug_assert!(
+                input_x >> BITS == 0 && input_y >> BITS == 0,
+                "Inputs have more than {} bits",
+                BITS
+            );
+
+            let subtrahend = input_y + borrow;
+            let (output_result, output_borrow) = if input_x >= subtrahend {
+                (input_x - subtrahend, 0)
+            } else {
+                (input_x + (1 << BITS) - subtrahend, 1)
+            };
+
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_output_result(i)),
+                F::from_canonical_u64(output_result),
+            );
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_borrow_out(i)),
+                F::from_canonical_u64(output_borrow),
+            );
+
+            let output_limbs = (0..num_limbs).scan(output_result, |acc, _| {
+                let tmp = *acc % limb_base;
+                *acc /= limb_base;
+                Some(F::from_canonical_u64(tmp))
+            });
+            for (j, limb) in output_limbs.enumerate() {
+                let wire = local_wire(self.gate.wire_output_ith_jth_limb(i, j));
+                out_buffer.set_wire(wire, limb);
+            }
+
+            borrow = output_borrow;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use num::bigint::RandBigInt;
+    use num::{BigUint, One};
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::thread_rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::multi_word_sub::MultiWordSubGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(
+            MultiWordSubGate::<GoldilocksField, 4, 32, 4>::new_from_config(
+                &CircuitConfig::standard_recursion_config(),
+            ),
+        )
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(MultiWordSubGate::<F, D, 32, 4>::new_from_config(
+            &CircuitConfig::standard_recursion_config(),
+        ))
+    }
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FF = <C as GenericConfig<D>>::FE;
+    const BITS: usize = 32;
+
+    /// Returns the `num_words` little-endian 32-bit words of `x`.
+    fn to_words(x: &BigUint, num_words: usize) -> Vec<u64> {
+        let mut words: Vec<u64> = x.to_u32_digits().into_iter().map(u64::from).collect();
+        words.resize(num_words, 0);
+        words
+    }
+
+    /// Returns random 256-bit pairs, including pairs whose difference underflows.
+    fn test_pairs() -> Vec<(BigUint, BigUint)> {
+        let mut rng = thread_rng();
+        let max = (BigUint::one() << 256usize) - BigUint::one();
+        let x = rng.gen_biguint(256);
+        let y = rng.gen_biguint(256);
+        vec![
+            (x.clone(), y.clone()),
+            (y, x.clone()),
+            (x.clone(), x),
+            (BigUint::default(), BigUint::one()),
+            (max.clone(), max),
+        ]
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const WORDS: usize = 8;
+        type SubGate = MultiWordSubGate<F, D, BITS, WORDS>;
+
+        /// Returns the wires of a gate subtracting `y` from `x`, with the final borrow replaced
+        /// by `output_borrow` if given.
+        fn get_wires(gate: &SubGate, x: &[u64], y: &[u64], output_borrow: Option<u64>) -> Vec<FF> {
+            let mut wires = vec![F::ZERO; gate.num_wires()];
+
+            let limb_base = 1 << SubGate::limb_bits();
+            let mut borrow = 0;
+            for i in 0..WORDS {
+                let result = (x[i] + (1 << BITS) - y[i] - borrow) % (1 << BITS);
+                let new_borrow = (x[i] < y[i] + borrow) as u64;
+
+                wires[gate.wire_input_x(i)] = F::from_canonical_u64(x[i]);
+                wires[gate.wire_input_y(i)] = F::from_canonical_u64(y[i]);
+                wires[gate.wire_output_result(i)] = F::from_canonical_u64(result);
+                let borrow_wire = if i == WORDS - 1 {
+                    gate.wire_output_borrow()
+                } else {
+                    gate.wire_internal_borrow(i)
+                };
+                wires[borrow_wire] = F::from_canonical_u64(new_borrow);
+                for j in 0..SubGate::num_limbs() {
+                    wires[gate.wire_output_ith_jth_limb(i, j)] =
+                        F::from_canonical_u64((result >> (SubGate::limb_bits() * j)) % limb_base);
+                }
+
+                borrow = new_borrow;
+            }
+            if let Some(output_borrow) = output_borrow {
+                wires[gate.wire_output_borrow()] = F::from_canonical_u64(output_borrow);
+            }
+
+            wires.iter().map(|&x| x.into()).collect()
+        }
+
+        // 256-bit values take more wires than the standard config has.
+        let gate = SubGate::new_from_config(&CircuitConfig {
+            num_wires: 256,
+            ..CircuitConfig::standard_recursion_config()
+        });
+        for (x, y) in test_pairs() {
+            let x_words = to_words(&x, WORDS);
+            let y_words = to_words(&y, WORDS);
+            let wires = get_wires(&gate, &x_words, &y_words, None);
+
+            // The words and final borrow should be those of the 256-bit difference.
+            let modulus = BigUint::one() << 256usize;
+            let expected = (&x + &modulus - &y) % &modulus;
+            let expected_words: Vec<FF> = to_words(&expected, WORDS)
+                .into_iter()
+                .map(|w| F::from_canonical_u64(w).into())
+                .collect();
+            let output_words: Vec<FF> = (0..WORDS)
+                .map(|i| wires[gate.wire_output_result(i)])
+                .collect();
+            assert_eq!(output_words, expected_words);
+            let expected_borrow = F::from_bool(x < y).into();
+            assert_eq!(wires[gate.wire_output_borrow()], expected_borrow);
+
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &wires,
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "Gate constraints are not satisfied."
+            );
+
+            let wrong_borrow = (x >= y) as u64;
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&gate, &x_words, &y_words, Some(wrong_borrow)),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "A wrong borrow should not satisfy the constraints."
+            );
+        }
+    }
+
+    #[test]
+    fn test_multi_word_sub() -> Result<()> {
+        // Two 128-bit gates fit the standard config, and are chained for 256-bit values.
+        const WORDS: usize = 4;
+
+        let config = CircuitConfig::standard_ecc_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let gate = MultiWordSubGate::<F, D, BITS, WORDS>::new_from_config(&builder.config);
+        for (x, y) in test_pairs() {
+            let x_words = to_words(&x, 2 * WORDS);
+            let y_words = to_words(&y, 2 * WORDS);
+            let modulus = BigUint::one() << 256usize;
+            let expected_words = to_words(&((&x + &modulus - &y) % &modulus), 2 * WORDS);
+
+            let mut borrow = builder.zero();
+            for half in 0..2 {
+                let gate_index = builder.add_gate(gate, vec![]);
+                builder.connect(borrow, Target::wire(gate_index, gate.wire_input_borrow()));
+                for i in 0..WORDS {
+                    let word = WORDS * half + i;
+                    let input_x = builder.add_virtual_target();
+                    let input_y = builder.add_virtual_target();
+                    builder.connect(input_x, Target::wire(gate_index, gate.wire_input_x(i)));
+                    builder.connect(input_y, Target::wire(gate_index, gate.wire_input_y(i)));
+                    pw.set_target(input_x, F::from_canonical_u64(x_words[word]));
+                    pw.set_target(input_y, F::from_canonical_u64(y_words[word]));
+
+                    let result = Target::wire(gate_index, gate.wire_output_result(i));
+                    let expected = builder.constant(F::from_canonical_u64(expected_words[word]));
+                    builder.connect(result, expected);
+                }
+                borrow = Target::wire(gate_index, gate.wire_output_borrow());
+            }
+            let expected_borrow = builder.constant(F::from_bool(x < y));
+            builder.connect(borrow, expected_borrow);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
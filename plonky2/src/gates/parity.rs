@@ -0,0 +1,363 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute the parity of a `BITS`-bit input, i.e. the XOR of all its bits, which is the
+/// sum of its bits modulo 2. The input is split into bits, which also range-checks it, and a chain
+/// of running parities `p_j = p_{j - 1} + b_j - 2 p_{j - 1} b_j` carries the sum modulo 2 from the
+/// lowest bit to the highest, keeping each step of degree 2.
+#[derive(Copy, Clone, Debug)]
+pub struct ParityGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> ParityGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS >= 1, "Inputs must have at least one bit");
+        assert!(BITS < 64, "Values must fit in the field");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 2 + 2 * BITS - 1;
+        let routed_wires_per_op = 2;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i + 1
+    }
+
+    pub fn wire_ith_input_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        2 * self.num_ops + (2 * BITS - 1) * i + j
+    }
+    /// The parity of the bits `0..=j` of the input, for `j` in `1..BITS`. The parity of bit 0 is
+    /// the bit itself, so it has no wire.
+    pub fn wire_ith_running_parity(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!((1..BITS).contains(&j));
+        2 * self.num_ops + (2 * BITS - 1) * i + BITS + j - 1
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for ParityGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = F::Extension::ZERO;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(bit * (bit - F::Extension::ONE));
+                computed_input = computed_input.double() + bit;
+            }
+            constraints.push(computed_input - input);
+
+            let mut parity = vars.local_wires[self.wire_ith_input_bit(i, 0)];
+            for j in 1..BITS {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                let running_parity = vars.local_wires[self.wire_ith_running_parity(i, j)];
+                let computed_parity = parity + bit - (parity * bit).double();
+                constraints.push(running_parity - computed_parity);
+                parity = running_parity;
+            }
+            constraints.push(parity - output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let two = F::TWO;
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = builder.zero_extension();
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+                computed_input = builder.mul_const_add_extension(two, computed_input, bit);
+            }
+            constraints.push(builder.sub_extension(computed_input, input));
+
+            let mut parity = vars.local_wires[self.wire_ith_input_bit(i, 0)];
+            for j in 1..BITS {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                let running_parity = vars.local_wires[self.wire_ith_running_parity(i, j)];
+                let sum = builder.add_extension(parity, bit);
+                let computed_parity = builder.arithmetic_extension(-two, F::ONE, parity, bit, sum);
+                constraints.push(builder.sub_extension(running_parity, computed_parity));
+                parity = running_parity;
+            }
+            constraints.push(builder.sub_extension(parity, output));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    ParityGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (2 + 2 * BITS - 1)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (2 * BITS + 1)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for ParityGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+
+            let mut computed_input = P::ZEROS;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                yield_constr.one(bit * (bit - F::ONE));
+                computed_input = computed_input * F::TWO + bit;
+            }
+            yield_constr.one(computed_input - input);
+
+            let mut parity = vars.local_wires[self.wire_ith_input_bit(i, 0)];
+            for j in 1..BITS {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                let running_parity = vars.local_wires[self.wire_ith_running_parity(i, j)];
+                let computed_parity = parity + bit - parity * bit * F::TWO;
+                yield_constr.one(running_parity - computed_parity);
+                parity = running_parity;
+            }
+            yield_constr.one(parity - output);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ParityGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: ParityGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for ParityGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(input >> BITS == 0, "Input has more than {} bits", BITS);
+
+        for j in 0..BITS {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_input_bit(self.i, j)),
+                F::from_canonical_u64((input >> j) & 1),
+            );
+        }
+        for j in 1..BITS {
+            let low_bits = input & ((1 << (j + 1)) - 1);
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_running_parity(self.i, j)),
+                F::from_canonical_u32(low_bits.count_ones() & 1),
+            );
+        }
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output(self.i)),
+            F::from_canonical_u32(input.count_ones() & 1),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::parity::ParityGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(ParityGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(ParityGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 32;
+
+        /// Returns the wires for the given inputs and claimed outputs, with running parities
+        /// computed honestly.
+        fn get_wires(inputs: &[u32], outputs: &[u32]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            for (&input, &output) in inputs.iter().zip(outputs) {
+                v0.extend([input, output].map(F::from_canonical_u32));
+                v1.extend((0..BITS).map(|j| F::from_canonical_u32((input >> j) & 1)));
+                v1.extend((1..BITS).map(|j| {
+                    let low_bits = input as u64 & ((1 << (j + 1)) - 1);
+                    F::from_canonical_u32(low_bits.count_ones() & 1)
+                }));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut inputs = vec![0, 1, 3, u32::MAX, 1 << 31];
+        inputs.extend((0..3).map(|_| rng.gen::<u32>()));
+        let outputs: Vec<u32> = inputs.iter().map(|x| x.count_ones() & 1).collect();
+        let gate = ParityGate::<F, D, BITS> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        let wrong_outputs: Vec<u32> = outputs.iter().map(|&x| x ^ 1).collect();
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &wrong_outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars)
+                .chunks(2 * BITS + 1)
+                .all(|op_constraints| op_constraints.iter().any(|x| !x.is_zero())),
+            "Flipped parities should not satisfy the constraints."
+        );
+    }
+}
@@ -0,0 +1,503 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+use plonky2_util::{ceil_div_usize, log2_ceil};
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute `sum_j a_j * b_j + addend` for `N` pairs of `BITS`-bit values, as in one limb
+/// position of a schoolbook multiplication or multi-scalar multiplication. It returns the low
+/// `BITS` bits of the sum as `output_low`, and the carry into the next limb position as
+/// `output_high`. Both outputs are range-checked, but inputs are not.
+#[derive(Copy, Clone, Debug)]
+pub struct ProductAccumulateGate<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const N: usize,
+> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const N: usize>
+    ProductAccumulateGate<F, D, BITS, N>
+{
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert_eq!(
+            Self::num_low_limbs() * Self::limb_bits(),
+            BITS,
+            "BITS must be a multiple of the limb size"
+        );
+        // The constraint `sum_j a_j * b_j + addend = output_low + 2^BITS * output_high` must hold
+        // over the integers, and the range checks bound the right-hand side by
+        // `2^(BITS + limb_bits * num_high_limbs)`.
+        assert!(
+            BITS + Self::limb_bits() * Self::num_high_limbs() < 64,
+            "Sums must fit in the field"
+        );
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = Self::routed_wires_per_op() + Self::num_limbs();
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / Self::routed_wires_per_op())
+    }
+
+    fn routed_wires_per_op() -> usize {
+        2 * N + 3
+    }
+
+    pub fn wire_ith_multiplicand_a(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < N);
+        Self::routed_wires_per_op() * i + j
+    }
+    pub fn wire_ith_multiplicand_b(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < N);
+        Self::routed_wires_per_op() * i + N + j
+    }
+    pub fn wire_ith_addend(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        Self::routed_wires_per_op() * i + 2 * N
+    }
+
+    pub fn wire_ith_output_low(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        Self::routed_wires_per_op() * i + 2 * N + 1
+    }
+    pub fn wire_ith_output_high(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        Self::routed_wires_per_op() * i + 2 * N + 2
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    pub fn num_low_limbs() -> usize {
+        BITS / Self::limb_bits()
+    }
+    // The sum is below `(N + 1) * 2^(2 * BITS)`, so the carry has `BITS + log2(N + 1)` bits.
+    pub fn num_high_limbs() -> usize {
+        ceil_div_usize(BITS + log2_ceil(N + 1), Self::limb_bits())
+    }
+    pub fn num_limbs() -> usize {
+        Self::num_low_limbs() + Self::num_high_limbs()
+    }
+
+    /// The `j`th limb of `output_low` for `j < num_low_limbs()`, followed by the limbs of
+    /// `output_high`.
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        Self::routed_wires_per_op() * self.num_ops + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const N: usize> Gate<F, D>
+    for ProductAccumulateGate<F, D, BITS, N>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}, N={}>", self, BITS, N)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let mut sum = vars.local_wires[self.wire_ith_addend(i)];
+            for j in 0..N {
+                let a = vars.local_wires[self.wire_ith_multiplicand_a(i, j)];
+                let b = vars.local_wires[self.wire_ith_multiplicand_b(i, j)];
+                sum += a * b;
+            }
+
+            let output_low = vars.local_wires[self.wire_ith_output_low(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high(i)];
+            let base = F::Extension::from_canonical_u64(1 << BITS);
+            constraints.push(sum - (output_low + base * output_high));
+
+            // Range-check the outputs using their limbs.
+            let limb_base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            let mut combined_low = F::Extension::ZERO;
+            let mut combined_high = F::Extension::ZERO;
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                    .product();
+                constraints.push(product);
+
+                if j < Self::num_low_limbs() {
+                    combined_low = limb_base * combined_low + this_limb;
+                } else {
+                    combined_high = limb_base * combined_high + this_limb;
+                }
+            }
+            constraints.push(combined_low - output_low);
+            constraints.push(combined_high - output_high);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let mut sum = vars.local_wires[self.wire_ith_addend(i)];
+            for j in 0..N {
+                let a = vars.local_wires[self.wire_ith_multiplicand_a(i, j)];
+                let b = vars.local_wires[self.wire_ith_multiplicand_b(i, j)];
+                sum = builder.mul_add_extension(a, b, sum);
+            }
+
+            let output_low = vars.local_wires[self.wire_ith_output_low(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high(i)];
+            let base = builder.constant_extension(F::Extension::from_canonical_u64(1 << BITS));
+            let computed_output = builder.mul_add_extension(base, output_high, output_low);
+            constraints.push(builder.sub_extension(sum, computed_output));
+
+            // Range-check the outputs using their limbs.
+            let limb_base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            let mut combined_low = builder.zero_extension();
+            let mut combined_high = builder.zero_extension();
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let mut product = builder.one_extension();
+                for x in 0..max_limb {
+                    let x_target =
+                        builder.constant_extension(F::Extension::from_canonical_usize(x));
+                    let diff = builder.sub_extension(this_limb, x_target);
+                    product = builder.mul_extension(product, diff);
+                }
+                constraints.push(product);
+
+                if j < Self::num_low_limbs() {
+                    combined_low = builder.mul_add_extension(limb_base, combined_low, this_limb);
+                } else {
+                    combined_high = builder.mul_add_extension(limb_base, combined_high, this_limb);
+                }
+            }
+            constraints.push(builder.sub_extension(combined_low, output_low));
+            constraints.push(builder.sub_extension(combined_high, output_high));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    ProductAccumulateGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (Self::routed_wires_per_op() + Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (3 + Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const N: usize>
+    PackedEvaluableBase<F, D> for ProductAccumulateGate<F, D, BITS, N>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let mut sum = vars.local_wires[self.wire_ith_addend(i)];
+            for j in 0..N {
+                let a = vars.local_wires[self.wire_ith_multiplicand_a(i, j)];
+                let b = vars.local_wires[self.wire_ith_multiplicand_b(i, j)];
+                sum += a * b;
+            }
+
+            let output_low = vars.local_wires[self.wire_ith_output_low(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high(i)];
+            let base = F::from_canonical_u64(1 << BITS);
+            yield_constr.one(sum - (output_low + output_high * base));
+
+            // Range-check the outputs using their limbs.
+            let limb_base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            let mut combined_low = P::ZEROS;
+            let mut combined_high = P::ZEROS;
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::from_canonical_usize(x))
+                    .product();
+                yield_constr.one(product);
+
+                if j < Self::num_low_limbs() {
+                    combined_low = combined_low * limb_base + this_limb;
+                } else {
+                    combined_high = combined_high * limb_base + this_limb;
+                }
+            }
+            yield_constr.one(combined_low - output_low);
+            yield_constr.one(combined_high - output_high);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ProductAccumulateGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const N: usize,
+> {
+    gate: ProductAccumulateGate<F, D, BITS, N>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const N: usize>
+    SimpleGenerator<F> for ProductAccumulateGenerator<F, D, BITS, N>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let local_target = |input| Target::wire(self.gate_index, input);
+
+        (0..N)
+            .flat_map(|j| {
+                [
+                    local_target(self.gate.wire_ith_multiplicand_a(self.i, j)),
+                    local_target(self.gate.wire_ith_multiplicand_b(self.i, j)),
+                ]
+            })
+            .chain([local_target(self.gate.wire_ith_addend(self.i))])
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let get_local_wire = |input| witness.get_wire(local_wire(input)).to_canonical_u64();
+
+        let sum = (0..N).fold(
+            get_local_wire(self.gate.wire_ith_addend(self.i)),
+            |acc, j| {
+                let a = get_local_wire(self.gate.wire_ith_multiplicand_a(self.i, j));
+                let b = get_local_wire(self.gate.wire_ith_multiplicand_b(self.i, j));
+                acc + a * b
+            },
+        );
+        let output_low = sum & ((1 << BITS) - 1);
+        let output_high = sum >> BITS;
+
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_low(self.i)),
+            F::from_canonical_u64(output_low),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_high(self.i)),
+            F::from_canonical_u64(output_high),
+        );
+
+        let num_low_limbs = ProductAccumulateGate::<F, D, BITS, N>::num_low_limbs();
+        let num_high_limbs = ProductAccumulateGate::<F, D, BITS, N>::num_high_limbs();
+        let limb_base = 1 << ProductAccumulateGate::<F, D, BITS, N>::limb_bits();
+        let to_limbs = |x: u64, num_limbs: usize| {
+            (0..num_limbs).scan(x, move |acc, _| {
+                let tmp = *acc % limb_base;
+                *acc /= limb_base;
+                Some(F::from_canonical_u64(tmp))
+            })
+        };
+        let output_limbs =
+            to_limbs(output_low, num_low_limbs).chain(to_limbs(output_high, num_high_limbs));
+
+        for (j, limb) in output_limbs.enumerate() {
+            let wire = local_wire(self.gate.wire_ith_output_jth_limb(self.i, j));
+            out_buffer.set_wire(wire, limb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::extension_field::quartic::QuarticExtension;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::product_accumulate::ProductAccumulateGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(
+            ProductAccumulateGate::<GoldilocksField, 4, 28, 4> {
+                num_ops: 2,
+                _phantom: PhantomData,
+            },
+        )
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(ProductAccumulateGate::<GoldilocksField, D, 28, 4> {
+            num_ops: 2,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        type F = GoldilocksField;
+        type FF = QuarticExtension<GoldilocksField>;
+        const D: usize = 4;
+        const BITS: usize = 28;
+        const N: usize = 4;
+        type G = ProductAccumulateGate<F, D, BITS, N>;
+
+        /// Returns the wires accumulating 4 products of the given inputs into `addend`. If
+        /// `shift_carry` is set, one is moved from the carry into the low output, which keeps the
+        /// sum intact but leaves `output_low` out of range.
+        fn get_wires(inputs: &[([u64; N], [u64; N], u64)], shift_carry: bool) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+
+            let limb_bits = G::limb_bits();
+            let limb_base = 1 << limb_bits;
+            let to_limbs = |x: u64, num_limbs: usize| {
+                (0..num_limbs)
+                    .map(|j| F::from_canonical_u64((x >> (limb_bits * j)) % limb_base))
+                    .collect::<Vec<_>>()
+            };
+            for &(a, b, addend) in inputs {
+                let sum = a.iter().zip(b).map(|(&a, b)| a * b).sum::<u64>() + addend;
+                let (mut low, mut high) = (sum % (1 << BITS), sum >> BITS);
+                if shift_carry {
+                    low += 1 << BITS;
+                    high -= 1;
+                }
+
+                v0.extend(a.map(F::from_canonical_u64));
+                v0.extend(b.map(F::from_canonical_u64));
+                v0.push(F::from_canonical_u64(addend));
+                v0.push(F::from_canonical_u64(low));
+                v0.push(F::from_canonical_u64(high));
+                v1.extend(to_limbs(low, G::num_low_limbs()));
+                v1.extend(to_limbs(high, G::num_high_limbs()));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut rand_input = || rng.gen::<u64>() >> (64 - BITS);
+        let max = (1 << BITS) - 1;
+        let inputs = vec![
+            (
+                [(); N].map(|_| rand_input()),
+                [(); N].map(|_| rand_input()),
+                rand_input(),
+            ),
+            // The largest possible sum.
+            ([max; N], [max; N], max),
+        ];
+        let gate = G {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, false),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, true),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "An out-of-range low output should not satisfy the constraints."
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute `(x << K) mod 2^BITS` for a `BITS`-bit value `x`, along with the `K` bits
+/// shifted out at the top, `x >> (BITS - K)`. The input is split into bits, which also
+/// range-checks it; both outputs are linear combinations of those bits, so they are range-checked
+/// by construction. The high output allows shifts of multi-word values, by adding it into the
+/// shifted next word.
+#[derive(Copy, Clone, Debug)]
+pub struct ShlModGate<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const K: usize,
+> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const K: usize>
+    ShlModGate<F, D, BITS, K>
+{
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS < 64, "Values must fit in the field");
+        assert!(K <= BITS, "Cannot shift by more than BITS");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 3 + BITS;
+        let routed_wires_per_op = 3;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i
+    }
+    pub fn wire_ith_output_low(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 1
+    }
+    pub fn wire_ith_output_high(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 2
+    }
+
+    pub fn wire_ith_input_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        3 * self.num_ops + BITS * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const K: usize> Gate<F, D>
+    for ShlModGate<F, D, BITS, K>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}, K={}>", self, BITS, K)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output_low = vars.local_wires[self.wire_ith_output_low(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high(i)];
+
+            // The top `K` bits form the high output, and the rest, shifted up by `K`, the low one.
+            let mut computed_input = F::Extension::ZERO;
+            let mut computed_high = F::Extension::ZERO;
+            let mut computed_low = F::Extension::ZERO;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(bit * (bit - F::Extension::ONE));
+                computed_input = computed_input.double() + bit;
+                if j >= BITS - K {
+                    computed_high = computed_high.double() + bit;
+                } else {
+                    computed_low = computed_low.double() + bit;
+                }
+            }
+            let computed_low = computed_low * F::Extension::from_canonical_u64(1 << K);
+
+            constraints.push(computed_input - input);
+            constraints.push(computed_low - output_low);
+            constraints.push(computed_high - output_high);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let two = F::TWO;
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output_low = vars.local_wires[self.wire_ith_output_low(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high(i)];
+
+            let mut computed_input = builder.zero_extension();
+            let mut computed_high = builder.zero_extension();
+            let mut computed_low = builder.zero_extension();
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+                computed_input = builder.mul_const_add_extension(two, computed_input, bit);
+                if j >= BITS - K {
+                    computed_high = builder.mul_const_add_extension(two, computed_high, bit);
+                } else {
+                    computed_low = builder.mul_const_add_extension(two, computed_low, bit);
+                }
+            }
+
+            constraints.push(builder.sub_extension(computed_input, input));
+            let computed_low =
+                builder.mul_const_extension(F::from_canonical_u64(1 << K), computed_low);
+            constraints.push(builder.sub_extension(computed_low, output_low));
+            constraints.push(builder.sub_extension(computed_high, output_high));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    ShlModGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (3 + BITS)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (BITS + 3)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const K: usize>
+    PackedEvaluableBase<F, D> for ShlModGate<F, D, BITS, K>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output_low = vars.local_wires[self.wire_ith_output_low(i)];
+            let output_high = vars.local_wires[self.wire_ith_output_high(i)];
+
+            let mut computed_input = P::ZEROS;
+            let mut computed_high = P::ZEROS;
+            let mut computed_low = P::ZEROS;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                yield_constr.one(bit * (bit - F::ONE));
+                computed_input = computed_input * F::TWO + bit;
+                if j >= BITS - K {
+                    computed_high = computed_high * F::TWO + bit;
+                } else {
+                    computed_low = computed_low * F::TWO + bit;
+                }
+            }
+            let computed_low = computed_low * F::from_canonical_u64(1 << K);
+
+            yield_constr.one(computed_input - input);
+            yield_constr.one(computed_low - output_low);
+            yield_constr.one(computed_high - output_high);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ShlModGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const K: usize,
+> {
+    gate: ShlModGate<F, D, BITS, K>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const K: usize>
+    SimpleGenerator<F> for ShlModGenerator<F, D, BITS, K>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(input >> BITS == 0, "Input has more than {} bits", BITS);
+
+        for j in 0..BITS {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_input_bit(self.i, j)),
+                F::from_canonical_u64((input >> j) & 1),
+            );
+        }
+        let mask = (1 << BITS) - 1;
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_low(self.i)),
+            F::from_canonical_u64((input << K) & mask),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_high(self.i)),
+            F::from_canonical_u64(input >> (BITS - K)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::shl_mod::ShlModGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(ShlModGate::<GoldilocksField, 4, 32, 5> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(ShlModGate::<GoldilocksField, D, 32, 5> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 32;
+        const K: usize = 5;
+
+        fn get_wires(inputs: &[u32], outputs: &[(u32, u32)]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            for (&input, &(low, high)) in inputs.iter().zip(outputs) {
+                v0.extend([input, low, high].map(F::from_canonical_u32));
+                v1.extend((0..BITS).map(|j| F::from_canonical_u32((input >> j) & 1)));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let inputs: Vec<u32> = vec![1, u32::MAX, 1 << 27, rng.gen()];
+        let outputs: Vec<(u32, u32)> = inputs.iter().map(|&x| (x << K, x >> (BITS - K))).collect();
+        let gate = ShlModGate::<F, D, BITS, K> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Swapping the low and high outputs must violate the constraints.
+        let wrong_outputs: Vec<(u32, u32)> =
+            outputs.iter().map(|&(low, high)| (high, low)).collect();
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &wrong_outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Incorrect outputs should not satisfy the constraints."
+        );
+    }
+}
@@ -0,0 +1,422 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute the absolute value of a `BITS`-bit input interpreted in two's complement.
+/// The input is split into bits, which range-checks it, and its top bit is output as the sign.
+/// The absolute value is output in `BITS - 1` bits, which holds for every input except
+/// `-2^(BITS - 1)`; for that input, the `overflow` flag is set and the output is zero, so that
+/// `|input| = output + 2^(BITS - 1) * overflow` in all cases.
+#[derive(Copy, Clone, Debug)]
+pub struct SignedAbsGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SignedAbsGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS >= 2, "Signed values need a sign bit and a magnitude");
+        assert!(BITS < 64, "Values must fit in the field");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 4 + 2 * BITS - 1;
+        let routed_wires_per_op = 4;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i
+    }
+    pub fn wire_ith_output_abs(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 1
+    }
+    pub fn wire_ith_output_sign(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 2
+    }
+    pub fn wire_ith_output_overflow(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        4 * i + 3
+    }
+
+    pub fn wire_ith_input_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS);
+        4 * self.num_ops + (2 * BITS - 1) * i + j
+    }
+    pub fn wire_ith_abs_bit(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < BITS - 1);
+        4 * self.num_ops + (2 * BITS - 1) * i + BITS + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for SignedAbsGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output_abs = vars.local_wires[self.wire_ith_output_abs(i)];
+            let output_sign = vars.local_wires[self.wire_ith_output_sign(i)];
+            let output_overflow = vars.local_wires[self.wire_ith_output_overflow(i)];
+
+            let mut computed_input = F::Extension::ZERO;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(bit * (bit - F::Extension::ONE));
+                computed_input = computed_input.double() + bit;
+            }
+            let mut computed_abs = F::Extension::ZERO;
+            for j in (0..BITS - 1).rev() {
+                let bit = vars.local_wires[self.wire_ith_abs_bit(i, j)];
+                constraints.push(bit * (bit - F::Extension::ONE));
+                computed_abs = computed_abs.double() + bit;
+            }
+            constraints.push(computed_input - input);
+            constraints.push(computed_abs - output_abs);
+            constraints.push(vars.local_wires[self.wire_ith_input_bit(i, BITS - 1)] - output_sign);
+            constraints.push(output_overflow * (output_overflow - F::Extension::ONE));
+
+            // `|input|` is `input` if the sign is clear, and `2^BITS - input` otherwise.
+            let modulus = F::Extension::from_canonical_u64(1 << BITS);
+            let magnitude = input + output_sign * (modulus - input.double());
+            let half_modulus = F::Extension::from_canonical_u64(1 << (BITS - 1));
+            constraints.push(magnitude - (output_abs + half_modulus * output_overflow));
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let two = F::TWO;
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output_abs = vars.local_wires[self.wire_ith_output_abs(i)];
+            let output_sign = vars.local_wires[self.wire_ith_output_sign(i)];
+            let output_overflow = vars.local_wires[self.wire_ith_output_overflow(i)];
+
+            let mut computed_input = builder.zero_extension();
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+                computed_input = builder.mul_const_add_extension(two, computed_input, bit);
+            }
+            let mut computed_abs = builder.zero_extension();
+            for j in (0..BITS - 1).rev() {
+                let bit = vars.local_wires[self.wire_ith_abs_bit(i, j)];
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+                computed_abs = builder.mul_const_add_extension(two, computed_abs, bit);
+            }
+            constraints.push(builder.sub_extension(computed_input, input));
+            constraints.push(builder.sub_extension(computed_abs, output_abs));
+            let sign_bit = vars.local_wires[self.wire_ith_input_bit(i, BITS - 1)];
+            constraints.push(builder.sub_extension(sign_bit, output_sign));
+            constraints.push(builder.mul_sub_extension(
+                output_overflow,
+                output_overflow,
+                output_overflow,
+            ));
+
+            // `|input|` is `input` if the sign is clear, and `2^BITS - input` otherwise.
+            let modulus = builder.constant_extension(F::Extension::from_canonical_u64(1 << BITS));
+            let double_input = builder.mul_const_extension(two, input);
+            let flipped = builder.sub_extension(modulus, double_input);
+            let magnitude = builder.mul_add_extension(output_sign, flipped, input);
+            let half_modulus = F::from_canonical_u64(1 << (BITS - 1));
+            let computed_magnitude =
+                builder.mul_const_add_extension(half_modulus, output_overflow, output_abs);
+            constraints.push(builder.sub_extension(magnitude, computed_magnitude));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    SignedAbsGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (4 + 2 * BITS - 1)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (2 * BITS - 1 + 5)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for SignedAbsGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output_abs = vars.local_wires[self.wire_ith_output_abs(i)];
+            let output_sign = vars.local_wires[self.wire_ith_output_sign(i)];
+            let output_overflow = vars.local_wires[self.wire_ith_output_overflow(i)];
+
+            let mut computed_input = P::ZEROS;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_ith_input_bit(i, j)];
+                yield_constr.one(bit * (bit - F::ONE));
+                computed_input = computed_input * F::TWO + bit;
+            }
+            let mut computed_abs = P::ZEROS;
+            for j in (0..BITS - 1).rev() {
+                let bit = vars.local_wires[self.wire_ith_abs_bit(i, j)];
+                yield_constr.one(bit * (bit - F::ONE));
+                computed_abs = computed_abs * F::TWO + bit;
+            }
+            yield_constr.one(computed_input - input);
+            yield_constr.one(computed_abs - output_abs);
+            yield_constr.one(vars.local_wires[self.wire_ith_input_bit(i, BITS - 1)] - output_sign);
+            yield_constr.one(output_overflow * (output_overflow - F::ONE));
+
+            // `|input|` is `input` if the sign is clear, and `2^BITS - input` otherwise.
+            let modulus = F::from_canonical_u64(1 << BITS);
+            let magnitude = input - output_sign * (input * F::TWO - modulus);
+            let half_modulus = F::from_canonical_u64(1 << (BITS - 1));
+            yield_constr.one(magnitude - (output_abs + output_overflow * half_modulus));
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SignedAbsGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: SignedAbsGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for SignedAbsGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(input >> BITS == 0, "Input has more than {} bits", BITS);
+
+        let sign = input >> (BITS - 1);
+        let magnitude = if sign == 1 {
+            (1 << BITS) - input
+        } else {
+            input
+        };
+        let overflow = magnitude >> (BITS - 1);
+        let abs = magnitude & ((1 << (BITS - 1)) - 1);
+
+        for j in 0..BITS {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_input_bit(self.i, j)),
+                F::from_canonical_u64((input >> j) & 1),
+            );
+        }
+        for j in 0..BITS - 1 {
+            out_buffer.set_wire(
+                local_wire(self.gate.wire_ith_abs_bit(self.i, j)),
+                F::from_canonical_u64((abs >> j) & 1),
+            );
+        }
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_abs(self.i)),
+            F::from_canonical_u64(abs),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_sign(self.i)),
+            F::from_canonical_u64(sign),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output_overflow(self.i)),
+            F::from_canonical_u64(overflow),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::signed_abs::SignedAbsGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(SignedAbsGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(SignedAbsGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 32;
+
+        /// Returns the wires for the given inputs and claimed `(abs, overflow)` outputs. The bits
+        /// of `abs` are truncated to `BITS - 1` bits.
+        fn get_wires(inputs: &[i32], outputs: &[(u64, bool)]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            for (&input, &(abs, overflow)) in inputs.iter().zip(outputs) {
+                let input = input as u32 as u64;
+                let sign = input >> (BITS - 1);
+                v0.extend([input, abs, sign, overflow as u64].map(F::from_canonical_u64));
+                v1.extend((0..BITS).map(|j| F::from_canonical_u64((input >> j) & 1)));
+                v1.extend((0..BITS - 1).map(|j| F::from_canonical_u64((abs >> j) & 1)));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let inputs = [rng.gen(), 0, -1, i32::MAX, i32::MIN];
+        let outputs: Vec<_> = inputs
+            .iter()
+            .map(|&x| match x.checked_abs() {
+                Some(abs) => (abs as u64, false),
+                None => (0, true),
+            })
+            .collect();
+        let gate = SignedAbsGate::<F, D, BITS> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // The absolute value of the minimum does not fit in `BITS - 1` bits without the flag.
+        let inputs = [i32::MIN];
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &[(1 << (BITS - 1), false)]),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        let gate = SignedAbsGate::<F, D, BITS> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "An unflagged overflow should not satisfy the constraints."
+        );
+    }
+}
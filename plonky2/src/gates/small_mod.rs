@@ -0,0 +1,524 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+use plonky2_util::ceil_div_usize;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to reduce inputs modulo a constant `P < 2^32`. It enforces `input = q * P + r` with
+/// `0 <= r < P`, by range-checking both `r` and `P - 1 - r`. The quotient is range-checked to
+/// `2 * num_quotient_limbs()` bits, chosen so that `q * P + r` cannot wrap around the field order;
+/// this covers all inputs below `2^32`, but not every field element.
+#[derive(Copy, Clone, Debug)]
+pub struct SmallModGate<F: RichField + Extendable<D>, const D: usize, const P: u64> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const P: u64> SmallModGate<F, D, P> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!((2..1 << 32).contains(&P), "Modulus must lie in [2, 2^32)");
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 3 + Self::num_quotient_limbs() + 2 * Self::num_remainder_limbs();
+        let routed_wires_per_op = 3;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i
+    }
+    pub fn wire_ith_quotient(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 1
+    }
+    pub fn wire_ith_remainder(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * i + 2
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // The largest number of limbs such that `2^(limb_bits * num_quotient_limbs) * P` is at most
+    // the field order, so that `q * P + r` is below it.
+    pub fn num_quotient_limbs() -> usize {
+        let max_quotient_bits = 63 - (F::ORDER / P).leading_zeros() as usize;
+        max_quotient_bits / Self::limb_bits()
+    }
+    // We have limbs for `P - 1`, which also fit `P - 1 - r`.
+    pub fn num_remainder_limbs() -> usize {
+        let remainder_bits = 64 - (P - 1).leading_zeros() as usize;
+        ceil_div_usize(remainder_bits, Self::limb_bits())
+    }
+
+    pub fn wire_ith_quotient_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_quotient_limbs());
+        3 * self.num_ops + self.limbs_per_op() * i + j
+    }
+    pub fn wire_ith_remainder_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_remainder_limbs());
+        3 * self.num_ops + self.limbs_per_op() * i + Self::num_quotient_limbs() + j
+    }
+    pub fn wire_ith_slack_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_remainder_limbs());
+        3 * self.num_ops
+            + self.limbs_per_op() * i
+            + Self::num_quotient_limbs()
+            + Self::num_remainder_limbs()
+            + j
+    }
+
+    fn limbs_per_op(&self) -> usize {
+        Self::num_quotient_limbs() + 2 * Self::num_remainder_limbs()
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const P: u64> Gate<F, D>
+    for SmallModGate<F, D, P>
+{
+    fn id(&self) -> String {
+        format!("{:?}<P={}>", self, P)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let limb_base = F::Extension::from_canonical_u64(1 << Self::limb_bits());
+        let max_limb = 1 << Self::limb_bits();
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let quotient = vars.local_wires[self.wire_ith_quotient(i)];
+            let remainder = vars.local_wires[self.wire_ith_remainder(i)];
+
+            let p = F::Extension::from_canonical_u64(P);
+            constraints.push(input - (quotient * p + remainder));
+
+            // Range-check the quotient, the remainder, and `P - 1 - remainder` using their limbs.
+            let p_minus_one = F::Extension::from_canonical_u64(P - 1);
+            for (num_limbs, limb_wire, value) in [
+                (
+                    Self::num_quotient_limbs(),
+                    Self::wire_ith_quotient_jth_limb as fn(&Self, usize, usize) -> usize,
+                    quotient,
+                ),
+                (
+                    Self::num_remainder_limbs(),
+                    Self::wire_ith_remainder_jth_limb,
+                    remainder,
+                ),
+                (
+                    Self::num_remainder_limbs(),
+                    Self::wire_ith_slack_jth_limb,
+                    p_minus_one - remainder,
+                ),
+            ] {
+                let mut combined_limbs = F::Extension::ZERO;
+                for j in (0..num_limbs).rev() {
+                    let this_limb = vars.local_wires[limb_wire(self, i, j)];
+                    let product = (0..max_limb)
+                        .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                        .product();
+                    constraints.push(product);
+
+                    combined_limbs = limb_base * combined_limbs + this_limb;
+                }
+                constraints.push(combined_limbs - value);
+            }
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let limb_base = F::from_canonical_u64(1 << Self::limb_bits());
+        let max_limb = 1 << Self::limb_bits();
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let quotient = vars.local_wires[self.wire_ith_quotient(i)];
+            let remainder = vars.local_wires[self.wire_ith_remainder(i)];
+
+            let p = F::from_canonical_u64(P);
+            let computed_input = builder.mul_const_add_extension(p, quotient, remainder);
+            constraints.push(builder.sub_extension(input, computed_input));
+
+            // Range-check the quotient, the remainder, and `P - 1 - remainder` using their limbs.
+            let p_minus_one = builder.constant_extension(F::Extension::from_canonical_u64(P - 1));
+            let slack = builder.sub_extension(p_minus_one, remainder);
+            for (num_limbs, limb_wire, value) in [
+                (
+                    Self::num_quotient_limbs(),
+                    Self::wire_ith_quotient_jth_limb as fn(&Self, usize, usize) -> usize,
+                    quotient,
+                ),
+                (
+                    Self::num_remainder_limbs(),
+                    Self::wire_ith_remainder_jth_limb,
+                    remainder,
+                ),
+                (
+                    Self::num_remainder_limbs(),
+                    Self::wire_ith_slack_jth_limb,
+                    slack,
+                ),
+            ] {
+                let mut combined_limbs = builder.zero_extension();
+                for j in (0..num_limbs).rev() {
+                    let this_limb = vars.local_wires[limb_wire(self, i, j)];
+                    let mut product = builder.one_extension();
+                    for x in 0..max_limb {
+                        let x_target =
+                            builder.constant_extension(F::Extension::from_canonical_usize(x));
+                        let diff = builder.sub_extension(this_limb, x_target);
+                        product = builder.mul_extension(product, diff);
+                    }
+                    constraints.push(product);
+
+                    combined_limbs =
+                        builder.mul_const_add_extension(limb_base, combined_limbs, this_limb);
+                }
+                constraints.push(builder.sub_extension(combined_limbs, value));
+            }
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    SmallModGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    // Zero wires don't satisfy the constraints, as the slack `P - 1 - r` must be range-checked.
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(vec![(self.wire_ith_input(op), F::ZERO)])
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (3 + self.limbs_per_op())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (4 + self.limbs_per_op())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const P: u64> PackedEvaluableBase<F, D>
+    for SmallModGate<F, D, P>
+{
+    fn eval_unfiltered_base_packed<Q: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<Q>,
+        mut yield_constr: StridedConstraintConsumer<Q>,
+    ) {
+        let limb_base = F::from_canonical_u64(1 << Self::limb_bits());
+        let max_limb = 1 << Self::limb_bits();
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let quotient = vars.local_wires[self.wire_ith_quotient(i)];
+            let remainder = vars.local_wires[self.wire_ith_remainder(i)];
+
+            let p = F::from_canonical_u64(P);
+            yield_constr.one(input - (quotient * p + remainder));
+
+            // Range-check the quotient, the remainder, and `P - 1 - remainder` using their limbs.
+            let p_minus_one = F::from_canonical_u64(P - 1);
+            for (num_limbs, limb_wire, value) in [
+                (
+                    Self::num_quotient_limbs(),
+                    Self::wire_ith_quotient_jth_limb as fn(&Self, usize, usize) -> usize,
+                    quotient,
+                ),
+                (
+                    Self::num_remainder_limbs(),
+                    Self::wire_ith_remainder_jth_limb,
+                    remainder,
+                ),
+                (
+                    Self::num_remainder_limbs(),
+                    Self::wire_ith_slack_jth_limb,
+                    -remainder + p_minus_one,
+                ),
+            ] {
+                let mut combined_limbs = Q::ZEROS;
+                for j in (0..num_limbs).rev() {
+                    let this_limb = vars.local_wires[limb_wire(self, i, j)];
+                    let product = (0..max_limb)
+                        .map(|x| this_limb - F::from_canonical_usize(x))
+                        .product();
+                    yield_constr.one(product);
+
+                    combined_limbs = combined_limbs * limb_base + this_limb;
+                }
+                yield_constr.one(combined_limbs - value);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SmallModGenerator<F: RichField + Extendable<D>, const D: usize, const P: u64> {
+    gate: SmallModGate<F, D, P>,
+    gate_index: usize,
+    i: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const P: u64> SimpleGenerator<F>
+    for SmallModGenerator<F, D, P>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        let quotient = input / P;
+        let remainder = input % P;
+
+        let limb_bits = SmallModGate::<F, D, P>::limb_bits();
+        let num_quotient_limbs = SmallModGate::<F, D, P>::num_quotient_limbs();
+        let num_remainder_limbs = SmallModGate::<F, D, P>::num_remainder_limbs();
+        debug_assert!(
+            quotient >> (limb_bits * num_quotient_limbs) == 0,
+            "Input {} is too large to reduce",
+            input
+        );
+
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_quotient(self.i)),
+            F::from_canonical_u64(quotient),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_remainder(self.i)),
+            F::from_canonical_u64(remainder),
+        );
+
+        let limb = |value: u64, j: usize| {
+            F::from_canonical_u64((value >> (limb_bits * j)) % (1 << limb_bits))
+        };
+        for j in 0..num_quotient_limbs {
+            let wire = local_wire(self.gate.wire_ith_quotient_jth_limb(self.i, j));
+            out_buffer.set_wire(wire, limb(quotient, j));
+        }
+        for j in 0..num_remainder_limbs {
+            let wire = local_wire(self.gate.wire_ith_remainder_jth_limb(self.i, j));
+            out_buffer.set_wire(wire, limb(remainder, j));
+            let wire = local_wire(self.gate.wire_ith_slack_jth_limb(self.i, j));
+            out_buffer.set_wire(wire, limb(P - 1 - remainder, j));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::small_mod::SmallModGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    const P: u64 = (1 << 31) - 1;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(SmallModGate::<GoldilocksField, 4, P> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(SmallModGate::<GoldilocksField, D, P> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        type G = SmallModGate<F, D, P>;
+
+        /// Returns the wires for the given inputs and claimed `(quotient, remainder)` outputs.
+        fn get_wires(inputs: &[u64], outputs: &[(u64, u64)]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+
+            let limb_bits = G::limb_bits();
+            let to_limbs = |value: u64, num_limbs: usize| {
+                (0..num_limbs).map(move |j| {
+                    F::from_canonical_u64((value >> (limb_bits * j)) % (1 << limb_bits))
+                })
+            };
+            for (&input, &(quotient, remainder)) in inputs.iter().zip(outputs) {
+                v0.extend([input, quotient, remainder].map(F::from_canonical_u64));
+                v1.extend(to_limbs(quotient, G::num_quotient_limbs()));
+                v1.extend(to_limbs(remainder, G::num_remainder_limbs()));
+                // A wrapped-around `P - 1 - remainder` will not fit in the limbs.
+                v1.extend(to_limbs(
+                    (P - 1).wrapping_sub(remainder),
+                    G::num_remainder_limbs(),
+                ));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut inputs = vec![0, P - 1, P, u32::MAX as u64];
+        inputs.extend((0..8).map(|_| rng.gen::<u32>() as u64));
+        let outputs: Vec<_> = inputs.iter().map(|&x| (x / P, x % P)).collect();
+        let gate = G {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Each input is also `(q - 1) * P + (r + P)`, but then the remainder is not below `P`.
+        let inputs = vec![P, u32::MAX as u64];
+        let wrong_outputs: Vec<_> = inputs.iter().map(|&x| (x / P - 1, x % P + P)).collect();
+        let gate = G {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &wrong_outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Unreduced remainders should not satisfy the constraints."
+        );
+    }
+
+    #[test]
+    fn test_small_mod_partial_row() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // A single operation leaves the rest of the row unused.
+        let gate = SmallModGate::<F, D, P>::new_from_config(&builder.config);
+        assert!(gate.num_ops > 1);
+        let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+        let x = builder.add_virtual_target();
+        builder.connect(x, Target::wire(gate_index, gate.wire_ith_input(i)));
+        pw.set_target(x, F::from_canonical_u64(P + 5));
+
+        let output = Target::wire(gate_index, gate.wire_ith_remainder(i));
+        let expected = builder.constant(F::from_canonical_u64(5));
+        builder.connect(output, expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
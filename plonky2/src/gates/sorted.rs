@@ -0,0 +1,339 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::plonk_common::{reduce_with_powers, reduce_with_powers_ext_recursive};
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate which checks that a sequence of `BITS`-bit values (we assume they are range-checked
+/// beforehand) is sorted in non-decreasing order. For each adjacent pair, the difference
+/// `a[i + 1] - a[i]` is split into `BITS / 2` 2-bit limbs, which is only possible if no borrow
+/// occurs, i.e. if `a[i] <= a[i + 1]`.
+#[derive(Copy, Clone, Debug)]
+pub struct SortedGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_values: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SortedGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        Self::new(Self::max_num_values(config))
+    }
+
+    pub fn new(num_values: usize) -> Self {
+        // A borrow wraps the difference around to at least `|F| - 2^BITS`, which must not fit in
+        // `BITS` bits.
+        assert!(BITS <= 62, "Values must have at most 62 bits");
+        assert_eq!(BITS % Self::limb_bits(), 0);
+        assert!(
+            num_values >= 2,
+            "A sequence needs at least two values to compare"
+        );
+        Self {
+            num_values,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn max_num_values(config: &CircuitConfig) -> usize {
+        // `n` values need `n - 1` differences, each split into `num_limbs` limbs.
+        let wires_per_value = 1 + Self::num_limbs();
+        let max_by_wires = (config.num_wires + Self::num_limbs()) / wires_per_value;
+        max_by_wires.min(config.num_routed_wires)
+    }
+
+    pub fn wire_ith_value(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_values);
+        i
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    pub fn num_limbs() -> usize {
+        BITS / Self::limb_bits()
+    }
+
+    /// The `j`th limb of `a[i + 1] - a[i]`.
+    pub fn wire_ith_diff_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_values - 1);
+        debug_assert!(j < Self::num_limbs());
+        self.num_values + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for SortedGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        let base = F::Extension::from_canonical_usize(1 << Self::limb_bits());
+        for i in 0..self.num_values - 1 {
+            let value = vars.local_wires[self.wire_ith_value(i)];
+            let next_value = vars.local_wires[self.wire_ith_value(i + 1)];
+            let limbs: Vec<_> = (0..Self::num_limbs())
+                .map(|j| vars.local_wires[self.wire_ith_diff_jth_limb(i, j)])
+                .collect();
+
+            constraints.push(reduce_with_powers(&limbs, base) - (next_value - value));
+            for limb in limbs {
+                constraints.push(
+                    (0..(1 << Self::limb_bits()))
+                        .map(|k| limb - F::Extension::from_canonical_usize(k))
+                        .product(),
+                );
+            }
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        let base = builder.constant(F::from_canonical_usize(1 << Self::limb_bits()));
+        for i in 0..self.num_values - 1 {
+            let value = vars.local_wires[self.wire_ith_value(i)];
+            let next_value = vars.local_wires[self.wire_ith_value(i + 1)];
+            let limbs: Vec<_> = (0..Self::num_limbs())
+                .map(|j| vars.local_wires[self.wire_ith_diff_jth_limb(i, j)])
+                .collect();
+
+            let diff = builder.sub_extension(next_value, value);
+            let computed_diff = reduce_with_powers_ext_recursive(builder, &limbs, base);
+            constraints.push(builder.sub_extension(computed_diff, diff));
+            for limb in limbs {
+                let mut product = builder.one_extension();
+                for k in 0..(1 << Self::limb_bits()) {
+                    let neg_k = -F::from_canonical_usize(k);
+                    product = builder.arithmetic_extension(F::ONE, neg_k, product, limb, product);
+                }
+                constraints.push(product);
+            }
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        let gen = SortedGenerator {
+            gate: *self,
+            gate_index,
+        };
+        vec![Box::new(gen.adapter())]
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_values + (self.num_values - 1) * Self::num_limbs()
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        (self.num_values - 1) * (1 + Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for SortedGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        let base = F::from_canonical_usize(1 << Self::limb_bits());
+        for i in 0..self.num_values - 1 {
+            let value = vars.local_wires[self.wire_ith_value(i)];
+            let next_value = vars.local_wires[self.wire_ith_value(i + 1)];
+            let limbs: Vec<_> = (0..Self::num_limbs())
+                .map(|j| vars.local_wires[self.wire_ith_diff_jth_limb(i, j)])
+                .collect();
+
+            yield_constr.one(reduce_with_powers(&limbs, base) - (next_value - value));
+            for limb in limbs {
+                yield_constr.one(
+                    (0..(1 << Self::limb_bits()))
+                        .map(|k| limb - F::from_canonical_usize(k))
+                        .product(),
+                );
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SortedGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: SortedGate<F, D, BITS>,
+    gate_index: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for SortedGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        (0..self.gate.num_values)
+            .map(|i| Target::wire(self.gate_index, self.gate.wire_ith_value(i)))
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let values: Vec<u64> = (0..self.gate.num_values)
+            .map(|i| {
+                witness
+                    .get_wire(local_wire(self.gate.wire_ith_value(i)))
+                    .to_canonical_u64()
+            })
+            .collect();
+
+        let limb_bits = SortedGate::<F, D, BITS>::limb_bits();
+        let limb_mask = (1 << limb_bits) - 1;
+        for (i, pair) in values.windows(2).enumerate() {
+            assert!(pair[0] <= pair[1], "Sequence is not sorted");
+            let diff = pair[1] - pair[0];
+            for j in 0..SortedGate::<F, D, BITS>::num_limbs() {
+                let limb = (diff >> (limb_bits * j)) & limb_mask;
+                out_buffer.set_wire(
+                    local_wire(self.gate.wire_ith_diff_jth_limb(i, j)),
+                    F::from_canonical_u64(limb),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::sorted::SortedGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(SortedGate::<GoldilocksField, 4, 16> {
+            num_values: 5,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(SortedGate::<GoldilocksField, D, 16> {
+            num_values: 5,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 16;
+
+        // Differences are taken modulo `2^BITS`, as if the borrow were ignored.
+        fn get_wires(values: &[u64]) -> Vec<FF> {
+            let num_limbs = SortedGate::<F, D, BITS>::num_limbs();
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            for &value in values {
+                v0.push(F::from_canonical_u64(value));
+            }
+            for pair in values.windows(2) {
+                let diff = pair[1].wrapping_sub(pair[0]) & ((1 << BITS) - 1);
+                v1.extend((0..num_limbs).map(|j| F::from_canonical_u64((diff >> (2 * j)) & 3)));
+            }
+
+            v0.iter().chain(v1.iter()).map(|&x| x.into()).collect()
+        }
+
+        let gate = SortedGate::<F, D, BITS>::new(5);
+
+        let sorted = [0, 3, 3, 1000, u16::MAX as u64];
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&sorted),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        let unsorted = [0, 3, 2, 1000, u16::MAX as u64];
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&unsorted),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "An out-of-order sequence should not satisfy the constraints."
+        );
+    }
+}
@@ -0,0 +1,511 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to compute `x * x mod 2^BITS` for a `BITS`-bit value `x` (we assume it is range-checked
+/// beforehand). The square is split into a low and a high half of `BITS` bits each, which are both
+/// range-checked, but only the low half is routed; the high half is discarded.
+///
+/// Since `x * x <= (2^BITS - 1)^2`, the high half is never `2^BITS - 1`. We enforce this by
+/// witnessing the inverse of `high_half - (2^BITS - 1)`, which rules out decompositions that differ
+/// from the square by the field order when `BITS = 32`.
+#[derive(Copy, Clone, Debug)]
+pub struct SquareModGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_ops: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SquareModGate<F, D, BITS> {
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(
+            BITS <= 32,
+            "Products of more than 32 bits would overflow the field"
+        );
+        assert_eq!(BITS % Self::limb_bits(), 0);
+        Self {
+            num_ops: Self::num_ops(config),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub(crate) fn num_ops(config: &CircuitConfig) -> usize {
+        let wires_per_op = 4 + Self::num_limbs();
+        let routed_wires_per_op = 2;
+        (config.num_wires / wires_per_op).min(config.num_routed_wires / routed_wires_per_op)
+    }
+
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i
+    }
+    pub fn wire_ith_output(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * i + 1
+    }
+
+    // The high half of the square is not routed.
+    pub fn wire_ith_high_half(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        2 * self.num_ops + i
+    }
+    pub fn wire_ith_high_half_diff_inverse(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        3 * self.num_ops + i
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // We have limbs for the `2 * BITS` bits of the square.
+    pub fn num_limbs() -> usize {
+        2 * BITS / Self::limb_bits()
+    }
+
+    pub fn wire_ith_output_jth_limb(&self, i: usize, j: usize) -> usize {
+        debug_assert!(i < self.num_ops);
+        debug_assert!(j < Self::num_limbs());
+        4 * self.num_ops + Self::num_limbs() * i + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for SquareModGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let high_half = vars.local_wires[self.wire_ith_high_half(i)];
+
+            let base = F::Extension::from_canonical_u64(1 << BITS);
+            let combined_square = high_half * base + output;
+
+            constraints.push(combined_square - input * input);
+
+            let high_half_diff = high_half - F::Extension::from_canonical_u64((1 << BITS) - 1);
+            let inverse = vars.local_wires[self.wire_ith_high_half_diff_inverse(i)];
+            constraints.push(high_half_diff * inverse - F::Extension::ONE);
+
+            let mut combined_low_limbs = F::Extension::ZERO;
+            let mut combined_high_limbs = F::Extension::ZERO;
+            let midpoint = Self::num_limbs() / 2;
+            let base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                    .product();
+                constraints.push(product);
+
+                if j < midpoint {
+                    combined_low_limbs = base * combined_low_limbs + this_limb;
+                } else {
+                    combined_high_limbs = base * combined_high_limbs + this_limb;
+                }
+            }
+            constraints.push(combined_low_limbs - output);
+            constraints.push(combined_high_limbs - high_half);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let high_half = vars.local_wires[self.wire_ith_high_half(i)];
+
+            let base: F::Extension = F::from_canonical_u64(1 << BITS).into();
+            let base_target = builder.constant_extension(base);
+            let combined_square = builder.mul_add_extension(high_half, base_target, output);
+
+            let computed_square = builder.square_extension(input);
+            constraints.push(builder.sub_extension(combined_square, computed_square));
+
+            let max_half =
+                builder.constant_extension(F::Extension::from_canonical_u64((1 << BITS) - 1));
+            let high_half_diff = builder.sub_extension(high_half, max_half);
+            let inverse = vars.local_wires[self.wire_ith_high_half_diff_inverse(i)];
+            let one = builder.one_extension();
+            constraints.push(builder.mul_sub_extension(high_half_diff, inverse, one));
+
+            let mut combined_low_limbs = builder.zero_extension();
+            let mut combined_high_limbs = builder.zero_extension();
+            let midpoint = Self::num_limbs() / 2;
+            let base = builder
+                .constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+
+                let mut product = builder.one_extension();
+                for x in 0..max_limb {
+                    let x_target =
+                        builder.constant_extension(F::Extension::from_canonical_usize(x));
+                    let diff = builder.sub_extension(this_limb, x_target);
+                    product = builder.mul_extension(product, diff);
+                }
+                constraints.push(product);
+
+                if j < midpoint {
+                    combined_low_limbs =
+                        builder.mul_add_extension(base, combined_low_limbs, this_limb);
+                } else {
+                    combined_high_limbs =
+                        builder.mul_add_extension(base, combined_high_limbs, this_limb);
+                }
+            }
+
+            constraints.push(builder.sub_extension(combined_low_limbs, output));
+            constraints.push(builder.sub_extension(combined_high_limbs, high_half));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        (0..self.num_ops)
+            .map(|i| {
+                let g: Box<dyn WitnessGenerator<F>> = Box::new(
+                    SquareModGenerator {
+                        gate: *self,
+                        gate_index,
+                        i,
+                        _phantom: PhantomData,
+                    }
+                    .adapter(),
+                );
+                g
+            })
+            .collect()
+    }
+
+    // Zero wires don't satisfy the constraints, as `high_half - (2^BITS - 1)` must have an inverse.
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(vec![(self.wire_ith_input(op), F::ZERO)])
+    }
+
+    fn num_wires(&self) -> usize {
+        self.num_ops * (4 + Self::num_limbs())
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        self.num_ops * (4 + Self::num_limbs())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for SquareModGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for i in 0..self.num_ops {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+            let output = vars.local_wires[self.wire_ith_output(i)];
+            let high_half = vars.local_wires[self.wire_ith_high_half(i)];
+
+            let base = F::from_canonical_u64(1 << BITS);
+            let combined_square = high_half * base + output;
+
+            yield_constr.one(combined_square - input.square());
+
+            let high_half_diff = high_half - F::from_canonical_u64((1 << BITS) - 1);
+            let inverse = vars.local_wires[self.wire_ith_high_half_diff_inverse(i)];
+            yield_constr.one(high_half_diff * inverse - P::ONES);
+
+            let mut combined_low_limbs = P::ZEROS;
+            let mut combined_high_limbs = P::ZEROS;
+            let midpoint = Self::num_limbs() / 2;
+            let base = F::from_canonical_u64(1u64 << Self::limb_bits());
+            for j in (0..Self::num_limbs()).rev() {
+                let this_limb = vars.local_wires[self.wire_ith_output_jth_limb(i, j)];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::from_canonical_usize(x))
+                    .product();
+                yield_constr.one(product);
+
+                if j < midpoint {
+                    combined_low_limbs = combined_low_limbs * base + this_limb;
+                } else {
+                    combined_high_limbs = combined_high_limbs * base + this_limb;
+                }
+            }
+            yield_constr.one(combined_low_limbs - output);
+            yield_constr.one(combined_high_limbs - high_half);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SquareModGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: SquareModGate<F, D, BITS>,
+    gate_index: usize,
+    i: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for SquareModGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            self.gate.wire_ith_input(self.i),
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let input = witness
+            .get_wire(local_wire(self.gate.wire_ith_input(self.i)))
+            .to_canonical_u64();
+        debug_assert!(input >> BITS == 0, "Input does not fit in {} bits", BITS);
+
+        let square = input.wrapping_mul(input);
+        let output = square & ((1 << BITS) - 1);
+        let high_half = square >> BITS;
+
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_output(self.i)),
+            F::from_canonical_u64(output),
+        );
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_high_half(self.i)),
+            F::from_canonical_u64(high_half),
+        );
+
+        let high_half_diff =
+            F::from_canonical_u64(high_half) - F::from_canonical_u64((1 << BITS) - 1);
+        out_buffer.set_wire(
+            local_wire(self.gate.wire_ith_high_half_diff_inverse(self.i)),
+            high_half_diff.inverse(),
+        );
+
+        let num_limbs = SquareModGate::<F, D, BITS>::num_limbs();
+        let limb_base = 1 << SquareModGate::<F, D, BITS>::limb_bits();
+        let square_limbs: Vec<_> = (0..num_limbs)
+            .scan(square, |acc, _| {
+                let tmp = *acc % limb_base;
+                *acc /= limb_base;
+                Some(F::from_canonical_u64(tmp))
+            })
+            .collect();
+
+        for j in 0..num_limbs {
+            let wire = local_wire(self.gate.wire_ith_output_jth_limb(self.i, j));
+            out_buffer.set_wire(wire, square_limbs[j]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::square_mod::SquareModGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(SquareModGate::<GoldilocksField, 4, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(SquareModGate::<GoldilocksField, D, 32> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 32;
+
+        fn get_wires(inputs: &[u32], outputs: &[u32]) -> Vec<FF> {
+            let mut v0 = Vec::new();
+            let mut v1 = Vec::new();
+            let mut v2 = Vec::new();
+            let mut v3 = Vec::new();
+
+            let limb_bits = SquareModGate::<F, D, BITS>::limb_bits();
+            let num_limbs = SquareModGate::<F, D, BITS>::num_limbs();
+            let limb_base = 1 << limb_bits;
+            for (&input, &output) in inputs.iter().zip(outputs) {
+                let mut square = (input as u64) * (input as u64);
+                let high_half = square >> BITS;
+
+                let mut square_limbs = Vec::with_capacity(num_limbs);
+                for _i in 0..num_limbs {
+                    square_limbs.push(square % limb_base);
+                    square /= limb_base;
+                }
+                let mut square_limbs_f: Vec<_> = square_limbs
+                    .into_iter()
+                    .map(F::from_canonical_u64)
+                    .collect();
+
+                v0.push(F::from_canonical_u32(input));
+                v0.push(F::from_canonical_u32(output));
+                v1.push(F::from_canonical_u64(high_half));
+                v2.push(
+                    (F::from_canonical_u64(high_half) - F::from_canonical_u64((1 << BITS) - 1))
+                        .inverse(),
+                );
+                v3.append(&mut square_limbs_f);
+            }
+
+            v0.iter()
+                .chain(v1.iter())
+                .chain(v2.iter())
+                .chain(v3.iter())
+                .map(|&x| x.into())
+                .collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let inputs = [rng.gen(), rng.gen(), u32::MAX];
+        let outputs: Vec<_> = inputs.iter().map(|x| x.wrapping_mul(*x)).collect();
+
+        let gate = SquareModGate::<F, D, BITS> {
+            num_ops: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // The output must be the wrapped square, so an off-by-one output is rejected.
+        let wrong_outputs: Vec<_> = outputs.iter().map(|x| x.wrapping_add(1)).collect();
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(&inputs, &wrong_outputs),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Incorrect outputs should not satisfy the constraints."
+        );
+    }
+
+    #[test]
+    fn test_square_mod_partial_row() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // A single operation leaves the rest of the row unused.
+        let gate = SquareModGate::<F, D, 32>::new_from_config(&builder.config);
+        assert!(gate.num_ops > 1);
+        let (gate_index, i) = builder.find_slot(gate, &[], &[]);
+        let x = builder.add_virtual_target();
+        builder.connect(x, Target::wire(gate_index, gate.wire_ith_input(i)));
+        pw.set_target(x, F::from_canonical_u64((1 << 32) - 1));
+
+        let output = Target::wire(gate_index, gate.wire_ith_output(i));
+        let expected = builder.constant(F::from_canonical_u64(1));
+        builder.connect(output, expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
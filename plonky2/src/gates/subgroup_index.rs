@@ -0,0 +1,282 @@
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::plonk_common::{reduce_with_powers, reduce_with_powers_ext_recursive};
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate which reduces an index modulo the order `n = 2^log_n` of a multiplicative subgroup, so
+/// that `g^input = g^output` for any generator `g` of the subgroup.
+///
+/// The input is decomposed into `num_bits` little-endian bits, which range-checks it, and the
+/// output is recombined from the low `log_n` bits. Since `num_bits < F::BITS`, the decomposition is
+/// unique.
+#[derive(Copy, Clone, Debug)]
+pub struct SubgroupIndexGate<F: RichField + Extendable<D>, const D: usize> {
+    pub num_bits: usize,
+    pub log_n: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SubgroupIndexGate<F, D> {
+    pub fn new(num_bits: usize, log_n: usize) -> Self {
+        assert!(
+            num_bits < F::BITS,
+            "Decompositions of {} or more bits are not unique",
+            F::BITS
+        );
+        assert!(
+            log_n <= num_bits,
+            "The subgroup is larger than the input range"
+        );
+        Self {
+            num_bits,
+            log_n,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub const WIRE_INPUT: usize = 0;
+    pub const WIRE_OUTPUT: usize = 1;
+    pub const START_BITS: usize = 2;
+
+    /// Returns the indices of the bit wires, least significant first.
+    pub fn bits(&self) -> Range<usize> {
+        Self::START_BITS..Self::START_BITS + self.num_bits
+    }
+
+    fn low_bits(&self) -> Range<usize> {
+        Self::START_BITS..Self::START_BITS + self.log_n
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for SubgroupIndexGate<F, D> {
+    fn id(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let input = vars.local_wires[Self::WIRE_INPUT];
+        let output = vars.local_wires[Self::WIRE_OUTPUT];
+        let bits = &vars.local_wires[self.bits()];
+
+        let computed_input = reduce_with_powers(bits, F::Extension::TWO);
+        let mut constraints = vec![computed_input - input];
+        for &bit in bits {
+            constraints.push(bit * (bit - F::Extension::ONE));
+        }
+
+        let computed_output =
+            reduce_with_powers(&vars.local_wires[self.low_bits()], F::Extension::TWO);
+        constraints.push(computed_output - output);
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let two = builder.two();
+        let input = vars.local_wires[Self::WIRE_INPUT];
+        let output = vars.local_wires[Self::WIRE_OUTPUT];
+        let bits = &vars.local_wires[self.bits()];
+
+        let computed_input = reduce_with_powers_ext_recursive(builder, bits, two);
+        let mut constraints = vec![builder.sub_extension(computed_input, input)];
+        for &bit in bits {
+            constraints.push(builder.mul_sub_extension(bit, bit, bit));
+        }
+
+        let computed_output =
+            reduce_with_powers_ext_recursive(builder, &vars.local_wires[self.low_bits()], two);
+        constraints.push(builder.sub_extension(computed_output, output));
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        let gen = SubgroupIndexGenerator {
+            gate: *self,
+            gate_index,
+        };
+        vec![Box::new(gen.adapter())]
+    }
+
+    // 1 for the input, 1 for the output, then `num_bits` for the bits.
+    fn num_wires(&self) -> usize {
+        self.num_bits + 2
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    // 1 for the input, `num_bits` for the bit checks, then 1 for the output.
+    fn num_constraints(&self) -> usize {
+        self.num_bits + 2
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D>
+    for SubgroupIndexGate<F, D>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        let input = vars.local_wires[Self::WIRE_INPUT];
+        let output = vars.local_wires[Self::WIRE_OUTPUT];
+        let bits = vars.local_wires.view(self.bits());
+
+        let computed_input = reduce_with_powers(bits, F::TWO);
+        yield_constr.one(computed_input - input);
+        yield_constr.many(bits.iter().map(|&bit| bit * (bit - F::ONE)));
+
+        let computed_output = reduce_with_powers(vars.local_wires.view(self.low_bits()), F::TWO);
+        yield_constr.one(computed_output - output);
+    }
+}
+
+#[derive(Debug)]
+struct SubgroupIndexGenerator<F: RichField + Extendable<D>, const D: usize> {
+    gate: SubgroupIndexGate<F, D>,
+    gate_index: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F>
+    for SubgroupIndexGenerator<F, D>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        vec![Target::wire(
+            self.gate_index,
+            SubgroupIndexGate::<F, D>::WIRE_INPUT,
+        )]
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let input = witness
+            .get_target(Target::wire(
+                self.gate_index,
+                SubgroupIndexGate::<F, D>::WIRE_INPUT,
+            ))
+            .to_canonical_u64();
+        debug_assert!(
+            input >> self.gate.num_bits == 0,
+            "Input does not fit in {} bits",
+            self.gate.num_bits
+        );
+
+        for (i, wire) in self.gate.bits().enumerate() {
+            out_buffer.set_target(
+                Target::wire(self.gate_index, wire),
+                F::from_canonical_u64((input >> i) & 1),
+            );
+        }
+
+        let output = input & ((1 << self.gate.log_n) - 1);
+        out_buffer.set_target(
+            Target::wire(self.gate_index, SubgroupIndexGate::<F, D>::WIRE_OUTPUT),
+            F::from_canonical_u64(output),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::subgroup_index::SubgroupIndexGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(SubgroupIndexGate::new(32, 8))
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(SubgroupIndexGate::new(32, 8))
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const NUM_BITS: usize = 32;
+
+        fn constraints_satisfied(gate: SubgroupIndexGate<F, D>, input: u64, output: u64) -> bool {
+            let mut wires = vec![F::from_canonical_u64(input), F::from_canonical_u64(output)];
+            wires.extend((0..NUM_BITS).map(|i| F::from_canonical_u64((input >> i) & 1)));
+            let wires: Vec<FF> = wires.into_iter().map(|x| x.into()).collect();
+
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &wires,
+                public_inputs_hash: &HashOut::rand(),
+            };
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero())
+        }
+
+        let mut rng = rand::thread_rng();
+        for log_n in [0, 1, 4, 16, NUM_BITS] {
+            let gate = SubgroupIndexGate::<F, D>::new(NUM_BITS, log_n);
+            let n = 1u64 << log_n;
+            let inputs = [0, n - 1, n, u32::MAX as u64, rng.gen::<u32>() as u64];
+            for input in inputs.into_iter().filter(|x| x >> NUM_BITS == 0) {
+                let output = input % n;
+                assert!(constraints_satisfied(gate, input, output));
+                assert!(!constraints_satisfied(gate, input, output + 1));
+            }
+        }
+    }
+}
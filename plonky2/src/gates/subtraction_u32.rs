@@ -204,6 +204,14 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for U32Subtraction
             .collect()
     }
 
+    fn unused_op_inputs(&self, op: usize) -> Option<Vec<(usize, F)>> {
+        Some(vec![
+            (self.wire_ith_input_x(op), F::ZERO),
+            (self.wire_ith_input_y(op), F::ZERO),
+            (self.wire_ith_input_borrow(op), F::ZERO),
+        ])
+    }
+
     fn num_wires(&self) -> usize {
         self.num_ops * (5 + Self::num_limbs())
     }
@@ -219,6 +227,33 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for U32Subtraction
     fn num_constraints(&self) -> usize {
         self.num_ops * (3 + Self::num_limbs())
     }
+
+    fn wire_labels(&self) -> Vec<(usize, String)> {
+        let mut labels = Vec::new();
+        for i in 0..self.num_ops {
+            labels.push((self.wire_ith_input_x(i), format!("input_x[{}]", i)));
+            labels.push((self.wire_ith_input_y(i), format!("input_y[{}]", i)));
+            labels.push((
+                self.wire_ith_input_borrow(i),
+                format!("input_borrow[{}]", i),
+            ));
+            labels.push((
+                self.wire_ith_output_result(i),
+                format!("output_result[{}]", i),
+            ));
+            labels.push((
+                self.wire_ith_output_borrow(i),
+                format!("output_borrow[{}]", i),
+            ));
+            for j in 0..Self::num_limbs() {
+                labels.push((
+                    self.wire_ith_output_jth_limb(i, j),
+                    format!("output_limb[{}][{}]", i, j),
+                ));
+            }
+        }
+        labels
+    }
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> PackedEvaluableBase<F, D>
@@ -447,4 +482,35 @@ mod tests {
             "Gate constraints are not satisfied."
         );
     }
+
+    #[test]
+    fn wire_labels() {
+        let gate = U32SubtractionGate::<GoldilocksField, 4> {
+            num_ops: 3,
+            _phantom: PhantomData,
+        };
+        let labels = gate.wire_labels();
+
+        // Every wire is labelled exactly once.
+        let mut indices: Vec<_> = labels.iter().map(|(index, _)| *index).collect();
+        indices.sort_unstable();
+        assert_eq!(indices, (0..gate.num_wires()).collect::<Vec<_>>());
+
+        let label_of = |index| {
+            labels
+                .iter()
+                .find(|(i, _)| *i == index)
+                .map(|(_, label)| label.as_str())
+                .unwrap()
+        };
+        assert_eq!(label_of(gate.wire_ith_input_x(1)), "input_x[1]");
+        assert_eq!(label_of(gate.wire_ith_input_y(2)), "input_y[2]");
+        assert_eq!(label_of(gate.wire_ith_input_borrow(0)), "input_borrow[0]");
+        assert_eq!(label_of(gate.wire_ith_output_result(1)), "output_result[1]");
+        assert_eq!(label_of(gate.wire_ith_output_borrow(2)), "output_borrow[2]");
+        assert_eq!(
+            label_of(gate.wire_ith_output_jth_limb(2, 5)),
+            "output_limb[2][5]"
+        );
+    }
 }
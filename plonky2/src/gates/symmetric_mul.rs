@@ -0,0 +1,605 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+use plonky2_util::{ceil_div_usize, log2_ceil};
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate to square a `WORDS`-word integer of `BITS`-bit words in a single row, returning the
+/// `2 * WORDS` words of the square. As both operands of the product are the same, each column of
+/// the schoolbook product takes its diagonal term `x_i^2` once and each off-diagonal term
+/// `x_i * x_j` doubled, rather than both `x_i * x_j` and `x_j * x_i`. The carries between columns
+/// are internal to the gate. The output words and carries are range-checked, but inputs are not.
+#[derive(Copy, Clone, Debug)]
+pub struct SymmetricMulGate<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const WORDS: usize,
+> {
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const WORDS: usize>
+    SymmetricMulGate<F, D, BITS, WORDS>
+{
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert_eq!(BITS % Self::limb_bits(), 0);
+        assert!(WORDS > 0, "There must be at least one word");
+        // Each column sums at most `WORDS` products of `2 * BITS` bits, and the carries into and
+        // out of it are range-checked to `limb_bits() * num_carry_limbs()` bits. The column
+        // constraints must hold over the integers.
+        assert!(
+            2 * BITS + log2_ceil(WORDS) + 2 < 64,
+            "Columns of the square must fit in the field"
+        );
+        let gate = Self {
+            _phantom: PhantomData,
+        };
+        assert!(
+            Self::num_routed_wires() <= config.num_routed_wires,
+            "{} words need more than {} routed wires",
+            WORDS,
+            config.num_routed_wires
+        );
+        assert!(
+            gate.num_wires() <= config.num_wires,
+            "{} words need more than {} wires",
+            WORDS,
+            config.num_wires
+        );
+        gate
+    }
+
+    fn num_routed_wires() -> usize {
+        3 * WORDS
+    }
+
+    pub fn wire_input(&self, i: usize) -> usize {
+        debug_assert!(i < WORDS);
+        i
+    }
+
+    pub fn wire_output(&self, k: usize) -> usize {
+        debug_assert!(k < 2 * WORDS);
+        WORDS + k
+    }
+
+    /// The carry from column `k` into column `k + 1`. The carry out of the last nonzero column,
+    /// `2 * WORDS - 2`, is the top output word, so only the first `2 * WORDS - 2` columns have
+    /// a carry wire.
+    pub fn wire_carry(&self, k: usize) -> usize {
+        debug_assert!(k < 2 * WORDS - 2);
+        Self::num_routed_wires() + k
+    }
+
+    /// The carry added to column `k`.
+    fn wire_carry_in(&self, k: usize) -> Option<usize> {
+        if k == 0 {
+            None
+        } else {
+            Some(self.wire_carry(k - 1))
+        }
+    }
+
+    /// The carry taken from column `k`.
+    fn wire_carry_out(&self, k: usize) -> usize {
+        if k == 2 * WORDS - 2 {
+            self.wire_output(2 * WORDS - 1)
+        } else {
+            self.wire_carry(k)
+        }
+    }
+
+    /// Returns the pairs `(i, j)` with `i <= j` and `i + j = k`, whose products make up column
+    /// `k` of the square.
+    fn column_pairs(k: usize) -> impl Iterator<Item = (usize, usize)> {
+        let first = (k + 1).saturating_sub(WORDS);
+        (first..=k / 2).map(move |i| (i, k - i))
+    }
+
+    pub fn limb_bits() -> usize {
+        2
+    }
+    // We have limbs for the `BITS` bits of each output word.
+    pub fn num_limbs() -> usize {
+        BITS / Self::limb_bits()
+    }
+    /// Carries are less than `WORDS * 2^BITS`.
+    pub fn carry_bits() -> usize {
+        BITS + log2_ceil(WORDS)
+    }
+    pub fn num_carry_limbs() -> usize {
+        ceil_div_usize(Self::carry_bits(), Self::limb_bits())
+    }
+
+    pub fn wire_output_kth_jth_limb(&self, k: usize, j: usize) -> usize {
+        debug_assert!(k < 2 * WORDS);
+        debug_assert!(j < Self::num_limbs());
+        5 * WORDS - 2 + Self::num_limbs() * k + j
+    }
+
+    pub fn wire_carry_kth_jth_limb(&self, k: usize, j: usize) -> usize {
+        debug_assert!(k < 2 * WORDS - 2);
+        debug_assert!(j < Self::num_carry_limbs());
+        5 * WORDS - 2 + 2 * WORDS * Self::num_limbs() + Self::num_carry_limbs() * k + j
+    }
+
+    /// Returns each range-checked wire along with the wires of its limbs.
+    fn range_checked_wires(&self) -> Vec<(usize, Vec<usize>)> {
+        let outputs = (0..2 * WORDS).map(|k| {
+            let limbs = (0..Self::num_limbs())
+                .map(|j| self.wire_output_kth_jth_limb(k, j))
+                .collect();
+            (self.wire_output(k), limbs)
+        });
+        let carries = (0..2 * WORDS - 2).map(|k| {
+            let limbs = (0..Self::num_carry_limbs())
+                .map(|j| self.wire_carry_kth_jth_limb(k, j))
+                .collect();
+            (self.wire_carry(k), limbs)
+        });
+        outputs.chain(carries).collect()
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const WORDS: usize> Gate<F, D>
+    for SymmetricMulGate<F, D, BITS, WORDS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}, WORDS={}>", self, BITS, WORDS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let base = F::Extension::from_canonical_u64(1 << BITS);
+        for k in 0..2 * WORDS - 1 {
+            let mut column = match self.wire_carry_in(k) {
+                Some(wire) => vars.local_wires[wire],
+                None => F::Extension::ZERO,
+            };
+            for (i, j) in Self::column_pairs(k) {
+                let product =
+                    vars.local_wires[self.wire_input(i)] * vars.local_wires[self.wire_input(j)];
+                column += if i == j { product } else { product.double() };
+            }
+
+            let output = vars.local_wires[self.wire_output(k)];
+            let carry_out = vars.local_wires[self.wire_carry_out(k)];
+            constraints.push(column - (output + base * carry_out));
+        }
+
+        let limb_base = F::Extension::from_canonical_u64(1u64 << Self::limb_bits());
+        for (wire, limbs) in self.range_checked_wires() {
+            let mut combined_limbs = F::Extension::ZERO;
+            for &limb_wire in limbs.iter().rev() {
+                let this_limb = vars.local_wires[limb_wire];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::Extension::from_canonical_usize(x))
+                    .product();
+                constraints.push(product);
+
+                combined_limbs = limb_base * combined_limbs + this_limb;
+            }
+            constraints.push(combined_limbs - vars.local_wires[wire]);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let base = builder.constant_extension(F::Extension::from_canonical_u64(1 << BITS));
+        for k in 0..2 * WORDS - 1 {
+            let mut column = match self.wire_carry_in(k) {
+                Some(wire) => vars.local_wires[wire],
+                None => builder.zero_extension(),
+            };
+            for (i, j) in Self::column_pairs(k) {
+                let input_i = vars.local_wires[self.wire_input(i)];
+                let input_j = vars.local_wires[self.wire_input(j)];
+                let coeff = if i == j { F::ONE } else { F::TWO };
+                column = builder.arithmetic_extension(coeff, F::ONE, input_i, input_j, column);
+            }
+
+            let output = vars.local_wires[self.wire_output(k)];
+            let carry_out = vars.local_wires[self.wire_carry_out(k)];
+            let computed_column = builder.mul_add_extension(base, carry_out, output);
+            constraints.push(builder.sub_extension(column, computed_column));
+        }
+
+        let limb_base =
+            builder.constant_extension(F::Extension::from_canonical_u64(1u64 << Self::limb_bits()));
+        for (wire, limbs) in self.range_checked_wires() {
+            let mut combined_limbs = builder.zero_extension();
+            for &limb_wire in limbs.iter().rev() {
+                let this_limb = vars.local_wires[limb_wire];
+                let max_limb = 1 << Self::limb_bits();
+                let mut product = builder.one_extension();
+                for x in 0..max_limb {
+                    let x_target =
+                        builder.constant_extension(F::Extension::from_canonical_usize(x));
+                    let diff = builder.sub_extension(this_limb, x_target);
+                    product = builder.mul_extension(product, diff);
+                }
+                constraints.push(product);
+
+                combined_limbs = builder.mul_add_extension(limb_base, combined_limbs, this_limb);
+            }
+            constraints.push(builder.sub_extension(combined_limbs, vars.local_wires[wire]));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        let gen = SymmetricMulGenerator {
+            gate: *self,
+            gate_index,
+        };
+        vec![Box::new(gen.adapter())]
+    }
+
+    fn num_wires(&self) -> usize {
+        5 * WORDS - 2 + 2 * WORDS * Self::num_limbs() + (2 * WORDS - 2) * Self::num_carry_limbs()
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        1 << Self::limb_bits()
+    }
+
+    fn num_constraints(&self) -> usize {
+        (2 * WORDS - 1)
+            + 2 * WORDS * (Self::num_limbs() + 1)
+            + (2 * WORDS - 2) * (Self::num_carry_limbs() + 1)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const WORDS: usize>
+    PackedEvaluableBase<F, D> for SymmetricMulGate<F, D, BITS, WORDS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        let base = F::from_canonical_u64(1 << BITS);
+        for k in 0..2 * WORDS - 1 {
+            let mut column = match self.wire_carry_in(k) {
+                Some(wire) => vars.local_wires[wire],
+                None => P::ZEROS,
+            };
+            for (i, j) in Self::column_pairs(k) {
+                let product =
+                    vars.local_wires[self.wire_input(i)] * vars.local_wires[self.wire_input(j)];
+                column += if i == j { product } else { product * F::TWO };
+            }
+
+            let output = vars.local_wires[self.wire_output(k)];
+            let carry_out = vars.local_wires[self.wire_carry_out(k)];
+            yield_constr.one(column - (output + carry_out * base));
+        }
+
+        let limb_base = F::from_canonical_u64(1u64 << Self::limb_bits());
+        for (wire, limbs) in self.range_checked_wires() {
+            let mut combined_limbs = P::ZEROS;
+            for &limb_wire in limbs.iter().rev() {
+                let this_limb = vars.local_wires[limb_wire];
+                let max_limb = 1 << Self::limb_bits();
+                let product = (0..max_limb)
+                    .map(|x| this_limb - F::from_canonical_usize(x))
+                    .product();
+                yield_constr.one(product);
+
+                combined_limbs = combined_limbs * limb_base + this_limb;
+            }
+            yield_constr.one(combined_limbs - vars.local_wires[wire]);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SymmetricMulGenerator<
+    F: RichField + Extendable<D>,
+    const D: usize,
+    const BITS: usize,
+    const WORDS: usize,
+> {
+    gate: SymmetricMulGate<F, D, BITS, WORDS>,
+    gate_index: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize, const WORDS: usize>
+    SimpleGenerator<F> for SymmetricMulGenerator<F, D, BITS, WORDS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        (0..WORDS)
+            .map(|i| Target::wire(self.gate_index, self.gate.wire_input(i)))
+            .collect()
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+
+        let inputs: Vec<u64> = (0..WORDS)
+            .map(|i| {
+                let input = witness
+                    .get_wire(local_wire(self.gate.wire_input(i)))
+                    .to_canonical_u64();
+                debug_assert!(input >> BITS == 0, "Inputs have more than {} bits", BITS);
+                input
+            })
+            .collect();
+
+        let limb_bits = SymmetricMulGate::<F, D, BITS, WORDS>::limb_bits();
+        let limb_base = 1 << limb_bits;
+        let mut set_with_limbs = |wire, limb_wires: Vec<usize>, value: u64| {
+            out_buffer.set_wire(local_wire(wire), F::from_canonical_u64(value));
+            let limbs = (0..limb_wires.len()).scan(value, |acc, _| {
+                let tmp = *acc % limb_base;
+                *acc /= limb_base;
+                Some(F::from_canonical_u64(tmp))
+            });
+            for (limb_wire, limb) in limb_wires.into_iter().zip(limbs) {
+                out_buffer.set_wire(local_wire(limb_wire), limb);
+            }
+        };
+
+        let mut range_checked_wires = self.gate.range_checked_wires().into_iter();
+        let mut output_limbs = range_checked_wires.by_ref().take(2 * WORDS);
+        let mut carry = 0;
+        let mut carries = Vec::with_capacity(2 * WORDS - 2);
+        for k in 0..2 * WORDS - 1 {
+            let column: u64 = SymmetricMulGate::<F, D, BITS, WORDS>::column_pairs(k)
+                .map(|(i, j)| {
+                    let product = inputs[i] * inputs[j];
+                    if i == j {
+                        product
+                    } else {
+                        2 * product
+                    }
+                })
+                .sum::<u64>()
+                + carry;
+
+            let (wire, limbs) = output_limbs.next().unwrap();
+            set_with_limbs(wire, limbs, column % (1 << BITS));
+            carry = column >> BITS;
+            if k < 2 * WORDS - 2 {
+                carries.push(carry);
+            }
+        }
+        let (wire, limbs) = output_limbs.next().unwrap();
+        set_with_limbs(wire, limbs, carry);
+
+        for ((wire, limbs), carry) in range_checked_wires.zip(carries) {
+            set_with_limbs(wire, limbs, carry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use num::bigint::RandBigInt;
+    use num::{BigUint, One};
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::thread_rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::symmetric_mul::SymmetricMulGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(
+            SymmetricMulGate::<GoldilocksField, 4, 16, 2>::new_from_config(
+                &CircuitConfig::standard_recursion_config(),
+            ),
+        )
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(SymmetricMulGate::<F, D, 16, 2>::new_from_config(
+            &CircuitConfig::standard_recursion_config(),
+        ))
+    }
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+    type FF = <C as GenericConfig<D>>::FE;
+    const BITS: usize = 16;
+
+    /// Returns the `num_words` little-endian 16-bit words of `x`.
+    fn to_words(x: &BigUint, num_words: usize) -> Vec<u64> {
+        let mut words: Vec<u64> = x
+            .to_u32_digits()
+            .into_iter()
+            .flat_map(|d| [d as u64 & 0xffff, d as u64 >> 16])
+            .collect();
+        words.resize(num_words, 0);
+        words
+    }
+
+    /// Returns random values of `num_words` words, along with zero and the maximum value.
+    fn test_values(num_words: usize) -> Vec<BigUint> {
+        let mut rng = thread_rng();
+        let num_bits = (BITS * num_words) as u64;
+        vec![
+            rng.gen_biguint(num_bits),
+            rng.gen_biguint(num_bits),
+            BigUint::default(),
+            (BigUint::one() << num_bits) - BigUint::one(),
+        ]
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const WORDS: usize = 4;
+        type MulGate = SymmetricMulGate<F, D, BITS, WORDS>;
+
+        /// Returns the wires of a gate squaring `x`, with the output words set to those of
+        /// `square` and the carries to those of the correct square.
+        fn get_wires(gate: &MulGate, x: &[u64], square: &[u64]) -> Vec<FF> {
+            let mut wires = vec![F::ZERO; gate.num_wires()];
+            let limb_base = 1 << MulGate::limb_bits();
+            let set_limbs = |wires: &mut Vec<F>, limb_wires: &[usize], value: u64| {
+                for (j, &wire) in limb_wires.iter().enumerate() {
+                    wires[wire] =
+                        F::from_canonical_u64((value >> (MulGate::limb_bits() * j)) % limb_base);
+                }
+            };
+
+            for i in 0..WORDS {
+                wires[gate.wire_input(i)] = F::from_canonical_u64(x[i]);
+            }
+            let mut carry = 0;
+            for k in 0..2 * WORDS - 2 {
+                // Compute the column from the full product, with both `x_i * x_j` and
+                // `x_j * x_i`.
+                let column: u64 = (0..WORDS)
+                    .filter(|&i| k >= i && k - i < WORDS)
+                    .map(|i| x[i] * x[k - i])
+                    .sum::<u64>()
+                    + carry;
+                carry = column >> BITS;
+                wires[gate.wire_carry(k)] = F::from_canonical_u64(carry);
+                let limb_wires: Vec<_> = (0..MulGate::num_carry_limbs())
+                    .map(|j| gate.wire_carry_kth_jth_limb(k, j))
+                    .collect();
+                set_limbs(&mut wires, &limb_wires, carry);
+            }
+            for k in 0..2 * WORDS {
+                wires[gate.wire_output(k)] = F::from_canonical_u64(square[k]);
+                let limb_wires: Vec<_> = (0..MulGate::num_limbs())
+                    .map(|j| gate.wire_output_kth_jth_limb(k, j))
+                    .collect();
+                set_limbs(&mut wires, &limb_wires, square[k]);
+            }
+
+            wires.iter().map(|&x| x.into()).collect()
+        }
+
+        // Four 16-bit words take more wires than the standard config has.
+        let gate = MulGate::new_from_config(&CircuitConfig {
+            num_wires: 256,
+            ..CircuitConfig::standard_recursion_config()
+        });
+        for x in test_values(WORDS) {
+            let x_words = to_words(&x, WORDS);
+
+            // The square should match the general product of `x` with itself.
+            let square = &x * &x;
+            let square_words = to_words(&square, 2 * WORDS);
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&gate, &x_words, &square_words),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "Gate constraints are not satisfied."
+            );
+
+            let wrong_square = to_words(&(square + BigUint::one()), 2 * WORDS);
+            let vars = EvaluationVars {
+                local_constants: &[],
+                local_wires: &get_wires(&gate, &x_words, &wrong_square),
+                public_inputs_hash: &HashOut::rand(),
+            };
+            assert!(
+                !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+                "A wrong square should not satisfy the constraints."
+            );
+        }
+    }
+
+    #[test]
+    fn test_symmetric_mul() -> Result<()> {
+        const WORDS: usize = 2;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let gate = SymmetricMulGate::<F, D, BITS, WORDS>::new_from_config(&builder.config);
+        for x in test_values(WORDS) {
+            let x_words = to_words(&x, WORDS);
+            let square_words = to_words(&(&x * &x), 2 * WORDS);
+
+            let gate_index = builder.add_gate(gate, vec![]);
+            for i in 0..WORDS {
+                let input = builder.add_virtual_target();
+                builder.connect(input, Target::wire(gate_index, gate.wire_input(i)));
+                pw.set_target(input, F::from_canonical_u64(x_words[i]));
+            }
+            for k in 0..2 * WORDS {
+                let output = Target::wire(gate_index, gate.wire_output(k));
+                let expected = builder.constant(F::from_canonical_u64(square_words[k]));
+                builder.connect(output, expected);
+            }
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
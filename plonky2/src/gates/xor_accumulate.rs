@@ -0,0 +1,421 @@
+use std::marker::PhantomData;
+
+use plonky2_field::extension_field::Extendable;
+use plonky2_field::field_types::Field;
+use plonky2_field::packed_field::PackedField;
+
+use crate::gates::gate::Gate;
+use crate::gates::packed_util::PackedEvaluableBase;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGenerator};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
+use crate::plonk::vars::{
+    EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
+    EvaluationVarsBasePacked,
+};
+
+/// A gate which XORs a sequence of `num_inputs` `BITS`-bit words into a running accumulator, and
+/// exposes the accumulator after each step. Accumulator 0 is the initial value, so gates can be
+/// chained, and accumulator `i + 1` is accumulator `i` XORed with input `i`.
+///
+/// Only the accumulators are split into bits. Input `i` is recomposed from the bits `a_j` and
+/// `b_j` of accumulators `i` and `i + 1` as `sum_j (a_j + b_j - 2 a_j b_j) 2^j`, so consecutive
+/// steps share a bit decomposition, and the inputs need none of their own.
+#[derive(Copy, Clone, Debug)]
+pub struct XorAccumulateGate<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    pub num_inputs: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize>
+    XorAccumulateGate<F, D, BITS>
+{
+    pub fn new_from_config(config: &CircuitConfig) -> Self {
+        assert!(BITS < 64, "Values must fit in the field");
+        let num_inputs = Self::num_inputs(config);
+        assert!(num_inputs > 0, "Not enough wires for a single input");
+        Self {
+            num_inputs,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Determine the maximum number of inputs that can fit in one gate for the given config.
+    pub(crate) fn num_inputs(config: &CircuitConfig) -> usize {
+        // Each input adds two routed wires and the bits of one accumulator, on top of the initial
+        // accumulator and its bits.
+        let wires_per_input = 2 + BITS;
+        let routed_wires_per_input = 2;
+        (config.num_wires.saturating_sub(1 + BITS) / wires_per_input)
+            .min(config.num_routed_wires.saturating_sub(1) / routed_wires_per_input)
+    }
+
+    /// The `k`th accumulator, for `k` in `0..=num_inputs`.
+    pub fn wire_accumulator(&self, k: usize) -> usize {
+        debug_assert!(k <= self.num_inputs);
+        2 * k
+    }
+    pub fn wire_ith_input(&self, i: usize) -> usize {
+        debug_assert!(i < self.num_inputs);
+        2 * i + 1
+    }
+
+    pub fn wire_accumulator_bit(&self, k: usize, j: usize) -> usize {
+        debug_assert!(k <= self.num_inputs);
+        debug_assert!(j < BITS);
+        2 * self.num_inputs + 1 + BITS * k + j
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> Gate<F, D>
+    for XorAccumulateGate<F, D, BITS>
+{
+    fn id(&self) -> String {
+        format!("{:?}<BITS={}>", self, BITS)
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        for k in 0..=self.num_inputs {
+            let accumulator = vars.local_wires[self.wire_accumulator(k)];
+
+            let mut computed_accumulator = F::Extension::ZERO;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_accumulator_bit(k, j)];
+                constraints.push(bit * (bit - F::Extension::ONE));
+                computed_accumulator = computed_accumulator.double() + bit;
+            }
+            constraints.push(computed_accumulator - accumulator);
+        }
+
+        for i in 0..self.num_inputs {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+
+            let mut computed_input = F::Extension::ZERO;
+            for j in (0..BITS).rev() {
+                let a = vars.local_wires[self.wire_accumulator_bit(i, j)];
+                let b = vars.local_wires[self.wire_accumulator_bit(i + 1, j)];
+                computed_input = computed_input.double() + a + b - (a * b).double();
+            }
+            constraints.push(computed_input - input);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        _vars: EvaluationVarsBase<F>,
+        _yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        panic!("use eval_unfiltered_base_packed instead");
+    }
+
+    fn eval_unfiltered_base_batch(&self, vars_base: EvaluationVarsBaseBatch<F>) -> Vec<F> {
+        self.eval_unfiltered_base_batch_packed(vars_base)
+    }
+
+    fn eval_unfiltered_recursively(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let two = F::TWO;
+        for k in 0..=self.num_inputs {
+            let accumulator = vars.local_wires[self.wire_accumulator(k)];
+
+            let mut computed_accumulator = builder.zero_extension();
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_accumulator_bit(k, j)];
+                constraints.push(builder.mul_sub_extension(bit, bit, bit));
+                computed_accumulator =
+                    builder.mul_const_add_extension(two, computed_accumulator, bit);
+            }
+            constraints.push(builder.sub_extension(computed_accumulator, accumulator));
+        }
+
+        for i in 0..self.num_inputs {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+
+            let mut computed_input = builder.zero_extension();
+            for j in (0..BITS).rev() {
+                let a = vars.local_wires[self.wire_accumulator_bit(i, j)];
+                let b = vars.local_wires[self.wire_accumulator_bit(i + 1, j)];
+                let sum = builder.add_extension(a, b);
+                let xor = builder.arithmetic_extension(-two, F::ONE, a, b, sum);
+                computed_input = builder.mul_const_add_extension(two, computed_input, xor);
+            }
+            constraints.push(builder.sub_extension(computed_input, input));
+        }
+
+        constraints
+    }
+
+    fn generators(
+        &self,
+        gate_index: usize,
+        _local_constants: &[F],
+    ) -> Vec<Box<dyn WitnessGenerator<F>>> {
+        let gen = XorAccumulateGenerator {
+            gate: *self,
+            gate_index,
+        };
+        vec![Box::new(gen.adapter())]
+    }
+
+    fn num_wires(&self) -> usize {
+        2 * self.num_inputs + 1 + BITS * (self.num_inputs + 1)
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn num_constraints(&self) -> usize {
+        (BITS + 1) * (self.num_inputs + 1) + self.num_inputs
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> PackedEvaluableBase<F, D>
+    for XorAccumulateGate<F, D, BITS>
+{
+    fn eval_unfiltered_base_packed<P: PackedField<Scalar = F>>(
+        &self,
+        vars: EvaluationVarsBasePacked<P>,
+        mut yield_constr: StridedConstraintConsumer<P>,
+    ) {
+        for k in 0..=self.num_inputs {
+            let accumulator = vars.local_wires[self.wire_accumulator(k)];
+
+            let mut computed_accumulator = P::ZEROS;
+            for j in (0..BITS).rev() {
+                let bit = vars.local_wires[self.wire_accumulator_bit(k, j)];
+                yield_constr.one(bit * (bit - F::ONE));
+                computed_accumulator = computed_accumulator * F::TWO + bit;
+            }
+            yield_constr.one(computed_accumulator - accumulator);
+        }
+
+        for i in 0..self.num_inputs {
+            let input = vars.local_wires[self.wire_ith_input(i)];
+
+            let mut computed_input = P::ZEROS;
+            for j in (0..BITS).rev() {
+                let a = vars.local_wires[self.wire_accumulator_bit(i, j)];
+                let b = vars.local_wires[self.wire_accumulator_bit(i + 1, j)];
+                computed_input = computed_input * F::TWO + a + b - a * b * F::TWO;
+            }
+            yield_constr.one(computed_input - input);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct XorAccumulateGenerator<F: RichField + Extendable<D>, const D: usize, const BITS: usize> {
+    gate: XorAccumulateGate<F, D, BITS>,
+    gate_index: usize,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize, const BITS: usize> SimpleGenerator<F>
+    for XorAccumulateGenerator<F, D, BITS>
+{
+    fn dependencies(&self) -> Vec<Target> {
+        let mut deps = vec![Target::wire(self.gate_index, self.gate.wire_accumulator(0))];
+        deps.extend(
+            (0..self.gate.num_inputs)
+                .map(|i| Target::wire(self.gate_index, self.gate.wire_ith_input(i))),
+        );
+        deps
+    }
+
+    fn run_once(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        let local_wire = |input| Wire {
+            gate: self.gate_index,
+            input,
+        };
+        let get_word = |input| {
+            let word = witness.get_wire(local_wire(input)).to_canonical_u64();
+            debug_assert!(word >> BITS == 0, "Word has more than {} bits", BITS);
+            word
+        };
+
+        let mut accumulator = get_word(self.gate.wire_accumulator(0));
+        for k in 0..=self.gate.num_inputs {
+            if k > 0 {
+                accumulator ^= get_word(self.gate.wire_ith_input(k - 1));
+                out_buffer.set_wire(
+                    local_wire(self.gate.wire_accumulator(k)),
+                    F::from_canonical_u64(accumulator),
+                );
+            }
+            for j in 0..BITS {
+                out_buffer.set_wire(
+                    local_wire(self.gate.wire_accumulator_bit(k, j)),
+                    F::from_canonical_u64((accumulator >> j) & 1),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+    use rand::Rng;
+
+    use crate::gates::gate::Gate;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::gates::xor_accumulate::XorAccumulateGate;
+    use crate::hash::hash_types::HashOut;
+    use crate::iop::target::Target;
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::vars::EvaluationVars;
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn low_degree() {
+        test_low_degree::<GoldilocksField, _, 4>(XorAccumulateGate::<GoldilocksField, 4, 8> {
+            num_inputs: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        test_eval_fns::<F, C, _, D>(XorAccumulateGate::<GoldilocksField, D, 8> {
+            num_inputs: 3,
+            _phantom: PhantomData,
+        })
+    }
+
+    #[test]
+    fn test_gate_constraint() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const BITS: usize = 32;
+
+        /// Returns the wires for the given initial accumulator, inputs and claimed accumulators,
+        /// with the accumulators split into bits.
+        fn get_wires(initial: u32, inputs: &[u32], accumulators: &[u32]) -> Vec<FF> {
+            let mut v0 = vec![initial];
+            for (&input, &accumulator) in inputs.iter().zip(accumulators) {
+                v0.extend([input, accumulator]);
+            }
+            let v1: Vec<u32> = [initial]
+                .iter()
+                .chain(accumulators)
+                .flat_map(|&acc| (0..BITS).map(move |j| (acc >> j) & 1))
+                .collect();
+
+            v0.into_iter()
+                .chain(v1)
+                .map(|x| F::from_canonical_u32(x).into())
+                .collect()
+        }
+
+        let mut rng = rand::thread_rng();
+        let initial = rng.gen::<u32>();
+        let inputs = [rng.gen::<u32>(), 0, u32::MAX, rng.gen::<u32>()];
+        let accumulators: Vec<u32> = inputs
+            .iter()
+            .scan(initial, |acc, &input| {
+                *acc ^= input;
+                Some(*acc)
+            })
+            .collect();
+        let gate = XorAccumulateGate::<F, D, BITS> {
+            num_inputs: inputs.len(),
+            _phantom: PhantomData,
+        };
+
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(initial, &inputs, &accumulators),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "Gate constraints are not satisfied."
+        );
+
+        // Adding instead of XORing at one step.
+        let mut wrong_accumulators = accumulators.clone();
+        wrong_accumulators[0] = initial.wrapping_add(inputs[0]);
+        assert_ne!(wrong_accumulators[0], accumulators[0]);
+        let vars = EvaluationVars {
+            local_constants: &[],
+            local_wires: &get_wires(initial, &inputs, &wrong_accumulators),
+            public_inputs_hash: &HashOut::rand(),
+        };
+        assert!(
+            !gate.eval_unfiltered(vars).iter().all(|x| x.is_zero()),
+            "A wrong accumulator should not satisfy the constraints."
+        );
+    }
+
+    #[test]
+    fn test_chained_gates() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        const BITS: usize = 16;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // Fold more words than fit in one gate, chaining each gate's last accumulator into the
+        // next gate's initial one.
+        let gate = XorAccumulateGate::<F, D, BITS>::new_from_config(&builder.config);
+        let mut rng = rand::thread_rng();
+        let words: Vec<u64> = (0..gate.num_inputs + 3)
+            .map(|_| rng.gen_range(0..1 << BITS))
+            .collect();
+
+        let mut accumulator = builder.zero();
+        for chunk in words.chunks(gate.num_inputs) {
+            let gate_index = builder.add_gate(gate, vec![]);
+            builder.connect(
+                accumulator,
+                Target::wire(gate_index, gate.wire_accumulator(0)),
+            );
+            for i in 0..gate.num_inputs {
+                let word = chunk.get(i).copied().unwrap_or(0);
+                let input = builder.constant(F::from_canonical_u64(word));
+                builder.connect(input, Target::wire(gate_index, gate.wire_ith_input(i)));
+            }
+            accumulator = Target::wire(gate_index, gate.wire_accumulator(gate.num_inputs));
+        }
+
+        let expected = words.iter().fold(0, |acc, &w| acc ^ w);
+        let expected = builder.constant(F::from_canonical_u64(expected));
+        builder.connect(accumulator, expected);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
@@ -11,6 +11,7 @@ use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::config::{AlgebraicHasher, GenericHashOut, Hasher};
+use crate::plonk::plonk_common::reduce_with_powers_ext_recursive;
 
 /// Observes prover messages, and generates challenges by hashing the transcript, a la Fiat-Shamir.
 #[derive(Clone)]
@@ -272,8 +273,23 @@ impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
     }
 }
 
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Squeezes a challenge `alpha` from `challenger` and returns `sum_i terms[i] * alpha^i`. The
+    /// terms should already have been observed by the challenger, so that `alpha` depends on them.
+    pub fn reduce_with_challenge<H: AlgebraicHasher<F>>(
+        &mut self,
+        terms: &[ExtensionTarget<D>],
+        challenger: &mut RecursiveChallenger<F, H, D>,
+    ) -> ExtensionTarget<D> {
+        let alpha = challenger.get_challenge(self);
+        reduce_with_powers_ext_recursive(self, terms, alpha)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use anyhow::Result;
+    use plonky2_field::extension_field::FieldExtension;
     use plonky2_field::field_types::Field;
 
     use crate::iop::challenger::{Challenger, RecursiveChallenger};
@@ -283,6 +299,8 @@ mod tests {
     use crate::plonk::circuit_builder::CircuitBuilder;
     use crate::plonk::circuit_data::CircuitConfig;
     use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::plonk_common::reduce_with_powers;
+    use crate::plonk::verifier::verify;
 
     #[test]
     fn no_duplicate_challenges() {
@@ -351,4 +369,44 @@ mod tests {
 
         assert_eq!(outputs_per_round, recursive_output_values_per_round);
     }
+
+    #[test]
+    fn test_reduce_with_challenge() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        type H = <C as GenericConfig<D>>::InnerHasher;
+
+        let terms = FF::rand_vec(5);
+
+        let mut challenger = Challenger::<F, H>::new();
+        challenger.observe_extension_elements::<D>(&terms);
+        let alpha = challenger.get_challenge();
+        let next_challenge = challenger.get_challenge();
+        let expected = reduce_with_powers(&terms, <FF as FieldExtension<D>>::from_basefield(alpha));
+
+        let config = CircuitConfig::standard_recursion_config();
+        let pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut recursive_challenger = RecursiveChallenger::<F, H, D>::new(&mut builder);
+        let terms_t = terms
+            .iter()
+            .map(|&t| builder.constant_extension(t))
+            .collect::<Vec<_>>();
+        recursive_challenger.observe_extension_elements(&terms_t);
+        let reduced = builder.reduce_with_challenge(&terms_t, &mut recursive_challenger);
+        let expected_t = builder.constant_extension(expected);
+        builder.connect_extension(reduced, expected_t);
+
+        // The challenger should have squeezed exactly `alpha`, leaving its transcript in sync with
+        // the native one.
+        let next_challenge_t = recursive_challenger.get_challenge(&mut builder);
+        let expected_next_challenge = builder.constant(next_challenge);
+        builder.connect(next_challenge_t, expected_next_challenge);
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
 }
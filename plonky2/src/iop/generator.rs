@@ -7,7 +7,7 @@ use plonky2_field::field_types::{Field, PrimeField};
 
 use crate::gadgets::arithmetic_u32::U32Target;
 use crate::gadgets::biguint::BigUintTarget;
-use crate::gadgets::nonnative::NonNativeTarget;
+use crate::gadgets::nonnative::{biguint_to_limbs, NonNativeTarget};
 use crate::hash::hash_types::{HashOut, HashOutTarget, RichField};
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::{BoolTarget, Target};
@@ -91,11 +91,19 @@ pub(crate) fn generate_partial_witness<
         pending_generator_indices = next_pending_generator_indices;
     }
 
-    assert_eq!(
-        remaining_generators, 0,
-        "{} generators weren't run",
-        remaining_generators,
-    );
+    if remaining_generators != 0 {
+        let mut unset_names = prover_data
+            .named_targets
+            .iter()
+            .filter(|(_, &target)| witness.try_get_target(target).is_none())
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        unset_names.sort_unstable();
+        panic!(
+            "{} generators weren't run; unset named targets: {:?}",
+            remaining_generators, unset_names
+        );
+    }
 
     witness
 }
@@ -180,8 +188,19 @@ impl<F: Field> GeneratedValues<F> {
         }
     }
 
-    pub fn set_nonnative_target<FF: PrimeField>(&mut self, target: NonNativeTarget<FF>, value: FF) {
-        self.set_biguint_target(target.value, value.to_canonical_biguint())
+    pub fn set_nonnative_target<FF: PrimeField, const LIMB_BITS: usize>(
+        &mut self,
+        target: NonNativeTarget<FF, LIMB_BITS>,
+        value: FF,
+    ) {
+        let mut limbs = biguint_to_limbs(&value.to_canonical_biguint(), LIMB_BITS);
+
+        assert!(target.value.num_limbs() >= limbs.len());
+
+        limbs.resize(target.value.num_limbs(), 0);
+        for (&limb_target, limb) in target.value.limbs.iter().zip(limbs) {
+            self.set_u32_target(limb_target, limb);
+        }
     }
 
     pub fn set_hash_target(&mut self, ht: HashOutTarget, value: HashOut<F>) {
@@ -283,6 +302,24 @@ impl<F: Field> SimpleGenerator<F> for CopyGenerator {
     }
 }
 
+/// A generator which sets targets to fixed values.
+#[derive(Debug)]
+pub(crate) struct FixedValuesGenerator<F: Field> {
+    pub(crate) target_values: Vec<(Target, F)>,
+}
+
+impl<F: Field> SimpleGenerator<F> for FixedValuesGenerator<F> {
+    fn dependencies(&self) -> Vec<Target> {
+        Vec::new()
+    }
+
+    fn run_once(&self, _witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) {
+        for &(target, value) in &self.target_values {
+            out_buffer.set_target(target, value);
+        }
+    }
+}
+
 /// A generator for including a random value
 #[derive(Debug)]
 pub(crate) struct RandomValueGenerator {
@@ -9,7 +9,7 @@ use crate::fri::structure::{FriOpenings, FriOpeningsTarget};
 use crate::fri::witness_util::set_fri_proof_target;
 use crate::gadgets::arithmetic_u32::U32Target;
 use crate::gadgets::biguint::BigUintTarget;
-use crate::gadgets::nonnative::NonNativeTarget;
+use crate::gadgets::nonnative::{limbs_to_biguint, NonNativeTarget};
 use crate::hash::hash_types::HashOutTarget;
 use crate::hash::hash_types::RichField;
 use crate::hash::hash_types::{HashOut, MerkleCapTarget};
@@ -79,12 +79,15 @@ pub trait Witness<F: Field> {
         result
     }
 
-    fn get_nonnative_target<FF: PrimeField>(&self, target: NonNativeTarget<FF>) -> FF
+    fn get_nonnative_target<FF: PrimeField, const LIMB_BITS: usize>(
+        &self,
+        target: NonNativeTarget<FF, LIMB_BITS>,
+    ) -> FF
     where
         F: PrimeField,
+        Self: Sized,
     {
-        let val = self.get_biguint_target(target.value);
-        FF::from_biguint(val)
+        FF::from_biguint(limbs_to_biguint(self, &target.value, LIMB_BITS))
     }
 
     fn get_hash_target(&self, ht: HashOutTarget) -> HashOut<F> {
@@ -349,6 +352,18 @@ impl<'a, F: Field> PartitionWitness<'a, F> {
         target.index(self.num_wires, self.degree)
     }
 
+    /// Returns the wires which haven't been set, whether by the inputs, a generator or a copy
+    /// constraint. This is a debugging aid for gadgets which fail to generate some of their gates'
+    /// outputs, which would otherwise default to zero and only surface as unsatisfied constraints.
+    /// Note that wires which are unused by their gate are listed as well.
+    #[cfg(any(test, debug_assertions))]
+    pub fn unassigned_wires(&self) -> Vec<Wire> {
+        (0..self.degree)
+            .flat_map(|gate| (0..self.num_wires).map(move |input| Wire { gate, input }))
+            .filter(|&wire| self.try_get_target(Target::Wire(wire)).is_none())
+            .collect()
+    }
+
     pub fn full_witness(self) -> MatrixWitness<F> {
         let mut wire_values = vec![vec![F::ZERO; self.degree]; self.num_wires];
         for i in 0..self.degree {
@@ -374,3 +389,77 @@ impl<'a, F: Field> Witness<F> for PartitionWitness<'a, F> {
         self.set_target_returning_rep(target, value);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::field_types::Field;
+
+    use crate::gates::arithmetic_base::ArithmeticGate;
+    use crate::iop::generator::generate_partial_witness;
+    use crate::iop::target::Target;
+    use crate::iop::wire::Wire;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_unassigned_wires() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // An incomplete gadget computing `x * y`, which routes its inputs into one operation of an
+        // `ArithmeticGate` but reads its output from the next, unused, operation.
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        pw.set_target(x, F::rand());
+        pw.set_target(y, F::rand());
+        let gate = ArithmeticGate::new_from_config(&builder.config);
+        let constants = [F::ONE, F::ZERO];
+        let (gate_index, i) = builder.find_slot(gate, &constants, &constants);
+        let zero = builder.zero();
+        builder.connect(
+            x,
+            Target::wire(gate_index, ArithmeticGate::wire_ith_multiplicand_0(i)),
+        );
+        builder.connect(
+            y,
+            Target::wire(gate_index, ArithmeticGate::wire_ith_multiplicand_1(i)),
+        );
+        builder.connect(
+            zero,
+            Target::wire(gate_index, ArithmeticGate::wire_ith_addend(i)),
+        );
+        let used_output = Wire {
+            gate: gate_index,
+            input: ArithmeticGate::wire_ith_output(i),
+        };
+        let read_output = Wire {
+            gate: gate_index,
+            input: ArithmeticGate::wire_ith_output(i + 1),
+        };
+
+        let data = builder.build::<C>();
+        let witness = generate_partial_witness(pw, &data.prover_only, &data.common);
+        let unassigned = witness.unassigned_wires();
+
+        assert!(unassigned.contains(&read_output));
+        assert!(!unassigned.contains(&used_output));
+        // Every other wire of the operation is set, by the inputs or the generator.
+        for input in [
+            ArithmeticGate::wire_ith_multiplicand_0(i),
+            ArithmeticGate::wire_ith_multiplicand_1(i),
+            ArithmeticGate::wire_ith_addend(i),
+        ] {
+            assert!(!unassigned.contains(&Wire {
+                gate: gate_index,
+                input
+            }));
+        }
+    }
+}
@@ -27,7 +27,7 @@ use crate::hash::hash_types::{HashOutTarget, MerkleCapTarget, RichField};
 use crate::hash::merkle_proofs::MerkleProofTarget;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::generator::{
-    CopyGenerator, RandomValueGenerator, SimpleGenerator, WitnessGenerator,
+    CopyGenerator, FixedValuesGenerator, RandomValueGenerator, SimpleGenerator, WitnessGenerator,
 };
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::wire::Wire;
@@ -69,6 +69,10 @@ pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     /// A vector of marked targets. The values assigned to these targets will be displayed by the prover.
     marked_targets: Vec<MarkedTargets<D>>,
 
+    /// Targets tagged with a name by `name_target`, so that their values can be looked up by name
+    /// while debugging witness generation.
+    named_targets: HashMap<String, Target>,
+
     /// Generators used to generate the witness.
     generators: Vec<Box<dyn WitnessGenerator<F>>>,
 
@@ -81,6 +85,9 @@ pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     /// Memoized results of `arithmetic_extension` calls.
     pub(crate) arithmetic_results: HashMap<ExtensionArithmeticOperation<F, D>, ExtensionTarget<D>>,
 
+    /// Memoized results of `split_le` calls, keyed by the integer and its number of bits.
+    pub(crate) split_le_results: HashMap<(Target, usize), Vec<BoolTarget>>,
+
     /// Map between gate type and the current gate of this type with available slots.
     current_slots: HashMap<GateRef<F, D>, CurrentSlot<F, D>>,
 }
@@ -96,11 +103,13 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             copy_constraints: Vec::new(),
             context_log: ContextTree::new(),
             marked_targets: Vec::new(),
+            named_targets: HashMap::new(),
             generators: Vec::new(),
             constants_to_targets: HashMap::new(),
             base_arithmetic_results: HashMap::new(),
             arithmetic_results: HashMap::new(),
             targets_to_constants: HashMap::new(),
+            split_le_results: HashMap::new(),
             current_slots: HashMap::new(),
         };
         builder.check_config();
@@ -268,6 +277,12 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             .push(CopyConstraint::new((x, y), self.context_log.open_stack()));
     }
 
+    /// Like `connect`, but for two wires of arbitrary gates, e.g. to chain a limb output of one
+    /// gate into a limb input of another without an intermediate target.
+    pub fn connect_wires(&mut self, x: Wire, y: Wire) {
+        self.connect(Target::Wire(x), Target::Wire(y));
+    }
+
     pub fn assert_zero(&mut self, x: Target) {
         let zero = self.zero();
         self.connect(x, zero);
@@ -391,6 +406,13 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         })
     }
 
+    /// Tags `target` with `name`, so that its value can be looked up by name with
+    /// `CircuitData::named_target`, and so that witness generation reports it if it is left unset.
+    pub fn name_target(&mut self, target: Target, name: &str) {
+        let previous = self.named_targets.insert(name.to_string(), target);
+        assert!(previous.is_none(), "A target is already named {}", name);
+    }
+
     /// Find an available slot, of the form `(gate_index, op)` for gate `G` using parameters `params`
     /// and constants `constants`. Parameters are any data used to differentiate which gate should be
     /// used for the given operation.
@@ -423,6 +445,62 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         (gate_idx, slot_idx)
     }
 
+    /// Returns whether an operation which either `a` (with parameters `a_params`) or `b` could
+    /// perform should be placed in `a`. A gate with an unused slot in an existing row is preferred,
+    /// since the operation then adds no row; otherwise the gate with fewer constraints per operation
+    /// is.
+    pub(crate) fn prefer_gate<A: Gate<F, D> + Clone, B: Gate<F, D> + Clone>(
+        &self,
+        a: &A,
+        a_params: &[F],
+        b: &B,
+        b_params: &[F],
+    ) -> bool {
+        match (
+            self.has_open_slot(a, a_params),
+            self.has_open_slot(b, b_params),
+        ) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => a.num_constraints() * b.num_ops() <= b.num_constraints() * a.num_ops(),
+        }
+    }
+
+    /// Returns whether a gate of type `gate` with parameters `params` has an unused slot, i.e.
+    /// whether `find_slot` would reuse an existing row.
+    fn has_open_slot<G: Gate<F, D> + Clone>(&self, gate: &G, params: &[F]) -> bool {
+        match self.current_slots.get(&GateRef::new(gate.clone())) {
+            Some(slots) => slots.current_slot.contains_key(params),
+            None => false,
+        }
+    }
+
+    /// Assigns the inputs given by `Gate::unused_op_inputs` to the unused operations of any gates
+    /// which specify them, so that the gates' own generators fill in a satisfying witness. Such
+    /// gates are no longer considered incomplete afterwards, so none of their generators are
+    /// dropped in `build`.
+    fn fill_unused_ops(&mut self) {
+        let mut target_values = Vec::new();
+        self.current_slots.retain(|gate_ref, slots| {
+            let gate = &gate_ref.0;
+            if gate.unused_op_inputs(0).is_none() {
+                return true;
+            }
+            for &(gate_index, first_unused_op) in slots.current_slot.values() {
+                for op in first_unused_op..gate.num_ops() {
+                    let inputs = gate.unused_op_inputs(op).unwrap();
+                    target_values.extend(
+                        inputs
+                            .into_iter()
+                            .map(|(wire, value)| (Target::wire(gate_index, wire), value)),
+                    );
+                }
+            }
+            false
+        });
+        self.add_simple_generator(FixedValuesGenerator { target_values });
+    }
+
     fn fri_params(&self, degree_bits: usize) -> FriParams {
         self.config
             .fri_config
@@ -631,6 +709,56 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 
+    /// Returns, for each gate type, its ID along with the fraction of its operation slots which
+    /// are actually used. Gates which were packed via `find_slot` and still have free slots lower
+    /// this ratio, so this can be used to spot wasted rows before building the circuit.
+    pub fn gate_utilization(&self) -> Vec<(String, f64)> {
+        // Map between gates with free slots and their number of used slots.
+        let incomplete_gates = self
+            .current_slots
+            .values()
+            .flat_map(|current_slot| current_slot.current_slot.values().copied())
+            .collect::<HashMap<_, _>>();
+
+        let mut utilization = self
+            .gates
+            .iter()
+            .map(|gate| {
+                let num_ops = gate.0.num_ops();
+                let (mut used_ops, mut total_ops) = (0, 0);
+                for (index, _) in self
+                    .gate_instances
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, inst)| inst.gate_ref == *gate)
+                {
+                    used_ops += incomplete_gates.get(&index).copied().unwrap_or(num_ops);
+                    total_ops += num_ops;
+                }
+                let ratio = if total_ops == 0 {
+                    1.0
+                } else {
+                    used_ops as f64 / total_ops as f64
+                };
+                (gate.0.id(), ratio)
+            })
+            .collect::<Vec<_>>();
+        utilization.sort_by(|a, b| a.0.cmp(&b.0));
+        utilization
+    }
+
+    /// Returns the maximum degree of the gates added so far, or zero if there are none. The
+    /// quotient degree factor, and hence the FRI blowup factor needed to build the circuit, is
+    /// bounded below by this degree, so this shows e.g. how the `limb_bits` of the binary gates,
+    /// whose degree is `1 << limb_bits`, affect the blowup before building.
+    pub fn max_gate_degree(&self) -> usize {
+        self.gates
+            .iter()
+            .map(|gate| gate.0.degree())
+            .max()
+            .unwrap_or(0)
+    }
+
     /// Builds a "full circuit", with both prover and verifier data.
     pub fn build<C: GenericConfig<D, F = F>>(mut self) -> CircuitData<F, C, D>
     where
@@ -641,6 +769,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let rate_bits = self.config.fri_config.rate_bits;
         let cap_height = self.config.fri_config.cap_height;
 
+        self.fill_unused_ops();
+
         // Hash the public inputs, and route them to a `PublicInputGate` which will enforce that
         // those hash wires match the claimed public inputs.
         let num_public_inputs = self.public_inputs.len();
@@ -764,6 +894,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             subgroup,
             public_inputs: self.public_inputs,
             marked_targets: self.marked_targets,
+            named_targets: self.named_targets,
             representative_map: forest.parents,
             fft_root_table: Some(fft_root_table),
         };
@@ -847,3 +978,178 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+
+    use crate::gates::arithmetic_u32::U32ArithmeticGate;
+    use crate::gates::binary_add::BinaryAddGate;
+    use crate::gates::full_adder::FullAdderGate;
+    use crate::gates::gate::Gate;
+    use crate::iop::generator::generate_partial_witness;
+    use crate::iop::target::Target;
+    use crate::iop::wire::Wire;
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::verifier::verify;
+
+    #[test]
+    fn test_gate_utilization() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+
+        // A single op leaves the rest of the `U32ArithmeticGate` unused.
+        let x = builder.add_virtual_u32_target();
+        let y = builder.add_virtual_u32_target();
+        let z = builder.add_virtual_u32_target();
+        builder.mul_add_u32(x, y, z);
+
+        let gate = U32ArithmeticGate::<F, D>::new_from_config(&config);
+        let num_ops = Gate::<F, D>::num_ops(&gate);
+        assert!(num_ops > 1);
+
+        let utilization = builder.gate_utilization();
+        let (_, ratio) = utilization
+            .iter()
+            .find(|(id, _)| *id == gate.id())
+            .expect("U32ArithmeticGate should be reported");
+        assert!(*ratio < 1.0);
+        assert_eq!(*ratio, 1.0 / num_ops as f64);
+    }
+
+    #[test]
+    fn test_max_gate_degree() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        assert_eq!(builder.max_gate_degree(), 0);
+
+        // A product is computed with an `ArithmeticGate`, of degree 3.
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        builder.mul(x, y);
+        assert_eq!(builder.max_gate_degree(), 3);
+
+        // The `U32ArithmeticGate` has 2-bit limbs, so its degree is 4.
+        let x = builder.add_virtual_u32_target();
+        let y = builder.add_virtual_u32_target();
+        let z = builder.add_virtual_u32_target();
+        builder.mul_add_u32(x, y, z);
+        assert_eq!(builder.max_gate_degree(), 4);
+    }
+
+    #[test]
+    fn test_named_target() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let product = builder.mul(x, y);
+        builder.name_target(product, "product");
+
+        let data = builder.build::<C>();
+        assert_eq!(data.named_target("product"), Some(product));
+        assert_eq!(data.named_target("sum"), None);
+
+        let (x_value, y_value) = (F::rand(), F::rand());
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, x_value);
+        pw.set_target(y, y_value);
+        let witness = generate_partial_witness(pw, &data.prover_only, &data.common);
+        assert_eq!(
+            witness.get_target(data.named_target("product").unwrap()),
+            x_value * y_value
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unset named targets: [\"sum\"]")]
+    fn test_unset_named_target_reported() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let x = builder.add_virtual_target();
+        let y = builder.add_virtual_target();
+        let sum = builder.add(x, y);
+        builder.name_target(x, "x");
+        builder.name_target(sum, "sum");
+
+        // `y` is never set, so `sum` can't be generated.
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand());
+        generate_partial_witness(pw, &data.prover_only, &data.common);
+    }
+
+    #[test]
+    fn test_connect_wires() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // Chain the carry out of a full adder into the carry in of a 16-bit adder in another row.
+        let full_adder = FullAdderGate::<F, D>::new_from_config(&builder.config);
+        let (full_adder_index, i) = builder.find_slot(full_adder, &[], &[]);
+        let full_adder_wire = |input| Wire {
+            gate: full_adder_index,
+            input,
+        };
+        let carry_out = full_adder_wire(full_adder.wire_ith_carry_out(i));
+        pw.set_wire(full_adder_wire(full_adder.wire_ith_a(i)), F::ONE);
+        pw.set_wire(full_adder_wire(full_adder.wire_ith_b(i)), F::ONE);
+        pw.set_wire(full_adder_wire(full_adder.wire_ith_carry_in(i)), F::ZERO);
+
+        let adder = BinaryAddGate::<F, D, 16>::new_from_config(&builder.config);
+        let (adder_index, j) = builder.find_slot(adder, &[], &[]);
+        assert_ne!(full_adder_index, adder_index);
+        let adder_wire = |input| Wire {
+            gate: adder_index,
+            input,
+        };
+        let carry_in = adder_wire(adder.wire_ith_input_carry(j));
+        pw.set_wire(
+            adder_wire(adder.wire_ith_input_x(j)),
+            F::from_canonical_u32(5),
+        );
+        pw.set_wire(
+            adder_wire(adder.wire_ith_input_y(j)),
+            F::from_canonical_u32(7),
+        );
+
+        builder.connect_wires(carry_out, carry_in);
+        let result = Target::Wire(adder_wire(adder.wire_ith_output_result(j)));
+
+        let data = builder.build::<C>();
+        let witness = generate_partial_witness(pw.clone(), &data.prover_only, &data.common);
+        assert_eq!(witness.get_wire(carry_out), F::ONE);
+        assert_eq!(witness.get_wire(carry_in), F::ONE);
+        assert_eq!(witness.get_target(result), F::from_canonical_u32(13));
+
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::{Range, RangeFrom};
 
 use anyhow::Result;
@@ -123,6 +123,11 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         verify(proof_with_pis, &self.verifier_only, &self.common)
     }
 
+    /// Returns the target tagged with `name` by `CircuitBuilder::name_target`, if any.
+    pub fn named_target(&self, name: &str) -> Option<Target> {
+        self.prover_only.named_targets.get(name).copied()
+    }
+
     pub fn verify_compressed(
         &self,
         compressed_proof_with_pis: CompressedProofWithPublicInputs<F, C, D>,
@@ -218,6 +223,8 @@ pub(crate) struct ProverOnlyCircuitData<
     pub public_inputs: Vec<Target>,
     /// A vector of marked targets. The values assigned to these targets will be displayed by the prover.
     pub marked_targets: Vec<MarkedTargets<D>>,
+    /// Targets tagged with a name by `CircuitBuilder::name_target`, keyed by name.
+    pub named_targets: HashMap<String, Target>,
     /// A map from each `Target`'s index to the index of its representative in the disjoint-set
     /// forest.
     pub representative_map: Vec<usize>,
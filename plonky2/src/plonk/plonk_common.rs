@@ -1,6 +1,7 @@
 use plonky2_field::extension_field::Extendable;
 use plonky2_field::field_types::Field;
 use plonky2_field::packed_field::PackedField;
+use plonky2_util::log2_strict;
 
 use crate::fri::oracle::SALT_SIZE;
 use crate::fri::structure::FriOracleInfo;
@@ -8,7 +9,7 @@ use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
 use crate::plonk::circuit_builder::CircuitBuilder;
-use crate::util::reducing::ReducingFactorTarget;
+use crate::util::reducing::{ReducingFactor, ReducingFactorTarget};
 
 pub(crate) const FRI_ORACLES: [FriOracleInfo; 4] = [
     PlonkOracle::CONSTANTS_SIGMAS.as_fri_oracle(),
@@ -49,6 +50,23 @@ impl PlonkOracle {
     }
 }
 
+/// Reduces the openings of each oracle of `FRI_ORACLES`, given in index order, with powers of
+/// `alpha`. This is the same as reducing all openings as a single list ordered by oracle index.
+pub fn combine_oracle_openings<F: Field>(openings: &[Vec<F>], alpha: F) -> F {
+    assert_eq!(
+        openings.len(),
+        FRI_ORACLES.len(),
+        "Expected the openings of each oracle"
+    );
+    let mut alpha = ReducingFactor::new(alpha);
+    // Starting from the last oracle, shift the running sum by `alpha^n`, where `n` is the number
+    // of openings of the current oracle, before adding their reduction.
+    openings.iter().rev().fold(F::ZERO, |acc, oracle_openings| {
+        let reduced = alpha.reduce(oracle_openings.iter());
+        alpha.shift(acc) + reduced
+    })
+}
+
 pub fn salt_size(salted: bool) -> usize {
     if salted {
         SALT_SIZE
@@ -87,20 +105,57 @@ pub(crate) fn eval_l_1_recursively<F: RichField + Extendable<D>, const D: usize>
     x: ExtensionTarget<D>,
     x_pow_n: ExtensionTarget<D>,
 ) -> ExtensionTarget<D> {
+    eval_l_1_batch_recursively(builder, n, &[x], &[x_pow_n])[0]
+}
+
+/// Like `eval_l_1_recursively`, but evaluates L_1 at each of `xs`, given their `n`th powers
+/// `x_pows`. The constants are shared between all points.
+///
+/// Assumes no `x` is 1; if one could be 1 then this is unsound.
+pub(crate) fn eval_l_1_batch_recursively<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    n: usize,
+    xs: &[ExtensionTarget<D>],
+    x_pows: &[ExtensionTarget<D>],
+) -> Vec<ExtensionTarget<D>> {
+    assert_eq!(xs.len(), x_pows.len());
+
     // L_1(x) = (x^n - 1) / (n * (x - 1))
     //        = Z(x) / (n * (x - 1))
     let one = builder.one_extension();
     let neg_one = builder.neg_one();
     let neg_one = builder.convert_to_ext(neg_one);
-    let eval_zero_poly = builder.sub_extension(x_pow_n, one);
-    let denominator = builder.arithmetic_extension(
-        F::from_canonical_usize(n),
-        F::from_canonical_usize(n),
-        x,
-        one,
-        neg_one,
-    );
-    builder.div_extension(eval_zero_poly, denominator)
+    let n = F::from_canonical_usize(n);
+    xs.iter()
+        .zip(x_pows)
+        .map(|(&x, &x_pow_n)| {
+            let eval_zero_poly = builder.sub_extension(x_pow_n, one);
+            let denominator = builder.arithmetic_extension(n, n, x, one, neg_one);
+            builder.div_extension(eval_zero_poly, denominator)
+        })
+        .collect()
+}
+
+/// Evaluates the Lagrange basis L_i(x), which has L_i(g^i) = 1 and vanishes at all other points in
+/// the order-`n` subgroup generated by `g`.
+///
+/// Requires `x != g^i`. At `x = g^i` the denominator is zero, so the circuit cannot be satisfied,
+/// even though `L_i(g^i) = 1` is well defined; callers must rule out such points beforehand.
+pub(crate) fn eval_l_i_recursively<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    n: usize,
+    i: usize,
+    x: ExtensionTarget<D>,
+    x_pow_n: ExtensionTarget<D>,
+) -> ExtensionTarget<D> {
+    // L_i(x) = g^i (x^n - 1) / (n * (x - g^i))
+    //        = g^i Z(x) / (n * (x - g^i))
+    let g_i = F::primitive_root_of_unity(log2_strict(n)).exp_u64(i as u64);
+    let one = builder.one_extension();
+    let numerator = builder.arithmetic_extension(g_i, -g_i, x_pow_n, one, one);
+    let n = F::from_canonical_usize(n);
+    let denominator = builder.arithmetic_extension(n, -n * g_i, x, one, one);
+    builder.div_extension(numerator, denominator)
 }
 
 /// For each alpha in alphas, compute a reduction of the given terms using powers of alpha. T can
@@ -147,3 +202,66 @@ pub fn reduce_with_powers_ext_recursive<F: RichField + Extendable<D>, const D: u
     let mut alpha = ReducingFactorTarget::new(alpha);
     alpha.reduce(terms, builder)
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::field_types::Field;
+
+    use crate::iop::witness::{PartialWitness, Witness};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::plonk_common::{
+        combine_oracle_openings, eval_l_1, eval_l_1_batch_recursively, eval_l_1_recursively,
+        FRI_ORACLES,
+    };
+    use crate::plonk::verifier::verify;
+    use crate::util::reducing::ReducingFactor;
+
+    #[test]
+    fn test_eval_l_1_batch_recursively() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        const N: usize = 1 << 4;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut pw = PartialWitness::new();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let xs_values = FF::rand_vec(3);
+        let xs = builder.add_virtual_extension_targets(xs_values.len());
+        let x_pows = builder.add_virtual_extension_targets(xs_values.len());
+        for ((&x_value, &x), &x_pow_n) in xs_values.iter().zip(&xs).zip(&x_pows) {
+            pw.set_extension_target(x, x_value);
+            pw.set_extension_target(x_pow_n, x_value.exp_u64(N as u64));
+        }
+
+        let batch = eval_l_1_batch_recursively(&mut builder, N, &xs, &x_pows);
+        for (i, &l_1) in batch.iter().enumerate() {
+            let single = eval_l_1_recursively(&mut builder, N, xs[i], x_pows[i]);
+            builder.connect_extension(l_1, single);
+            let expected = builder.constant_extension(eval_l_1(N, xs_values[i]));
+            builder.connect_extension(l_1, expected);
+        }
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw)?;
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    fn test_combine_oracle_openings() {
+        type FF = <PoseidonGoldilocksConfig as GenericConfig<2>>::FE;
+
+        let alpha = FF::rand();
+        let openings: Vec<_> = [5, 0, 3, 8].into_iter().map(FF::rand_vec).collect();
+        assert_eq!(openings.len(), FRI_ORACLES.len());
+
+        // This is how the openings of the four oracles were reduced together, by oracle index.
+        let expected = ReducingFactor::new(alpha).reduce(openings.iter().flatten());
+        assert_eq!(combine_oracle_openings(&openings, alpha), expected);
+    }
+}
@@ -155,7 +155,7 @@ pub(crate) fn eval_vanishing_poly_base_batch<
 
         let constraint_terms = PackedStridedView::new(&constraint_terms_batch, n, k);
 
-        let l1_x = z_h_on_coset.eval_l1(index, x);
+        let l1_x = z_h_on_coset.l1(index, x);
         for i in 0..num_challenges {
             let z_x = local_zs[i];
             let z_gx = next_zs[i];